@@ -159,6 +159,11 @@ impl ConnectionManager {
     pub fn conn(&self) -> Channel {
         self.inner.next()
     }
+
+    /// conn_at returns the channel at the specified index of the pool.
+    pub fn conn_at(&self, index: usize) -> Channel {
+        self.inner.values[index % self.inner.values.len()].clone()
+    }
 }
 
 #[cfg(test)]
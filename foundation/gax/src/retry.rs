@@ -1,12 +1,13 @@
+use std::convert::TryFrom;
 use std::future::Future;
 use std::iter::Take;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use prost::Message;
 use tokio::select;
 pub use tokio_retry::strategy::ExponentialBackoff;
 use tokio_retry::Action;
 pub use tokio_retry::Condition;
-use tokio_retry::RetryIf;
 
 use crate::cancel::CancellationToken;
 use crate::grpc::{Code, Status};
@@ -24,6 +25,13 @@ impl TryAs<Status> for Status {
 pub trait Retry<E: TryAs<Status>, T: Condition<E>> {
     fn strategy(&self) -> Take<ExponentialBackoff>;
     fn condition(&self) -> T;
+
+    /// timeout caps the total time spent on all the attempts and the backoffs. The deadline is resolved
+    /// when the retry starts, and neither an attempt nor a backoff is started after it.
+    /// The retry in progress at the deadline is stopped with DEADLINE_EXCEEDED.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
 pub struct CodeCondition {
@@ -75,6 +83,43 @@ impl Retry<Status, CodeCondition> for RetrySetting {
     }
 }
 
+impl RetrySetting {
+    /// disabled returns the setting that never retries, so that the first error is returned immediately.
+    pub fn disabled() -> Self {
+        Self {
+            take: 0,
+            codes: vec![],
+            ..Default::default()
+        }
+    }
+
+    /// is_disabled returns true if the setting never retries.
+    pub fn is_disabled(&self) -> bool {
+        self.take == 0 || self.codes.is_empty()
+    }
+
+    /// with_backoff_base sets from_millis and max_delay with Duration, so that the units are not mixed up.
+    /// The base is the exponential base in milliseconds, not the initial backoff: the n-th backoff is
+    /// base^n milliseconds capped at max, e.g. 10ms, 100ms, 1s for the base of 10ms, and a base of 1ms
+    /// backs off 1ms without growing. The fraction of a millisecond in the base is truncated.
+    /// Panics if the base is shorter than 1ms, which would retry without the backoff.
+    pub fn with_backoff_base(mut self, base: Duration, max: Duration) -> Self {
+        assert!(
+            base >= Duration::from_millis(1),
+            "backoff base must be at least 1ms: {:?}",
+            base
+        );
+        self.from_millis = base.as_millis() as u64;
+        self.max_delay = Some(max);
+        self
+    }
+
+    /// backoff_base returns from_millis, the exponential base of the backoff, as Duration.
+    pub fn backoff_base(&self) -> Duration {
+        Duration::from_millis(self.from_millis)
+    }
+}
+
 impl Default for RetrySetting {
     fn default() -> Self {
         Self {
@@ -87,6 +132,37 @@ impl Default for RetrySetting {
     }
 }
 
+/// google.rpc.Status encoded in the grpc-status-details-bin.
+#[derive(Clone, PartialEq, Message)]
+struct StatusDetails {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// google.rpc.RetryInfo
+#[derive(Clone, PartialEq, Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<prost_types::Duration>,
+}
+
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+
+/// retry_delay returns the delay suggested by the server with google.rpc.RetryInfo in the status details.
+pub fn retry_delay(status: &Status) -> Option<Duration> {
+    let details = StatusDetails::decode(status.details()).ok()?;
+    details
+        .details
+        .iter()
+        .filter(|detail| detail.type_url == RETRY_INFO_TYPE_URL)
+        .find_map(|detail| RetryInfo::decode(detail.value.as_slice()).ok()?.retry_delay)
+        .and_then(|delay| Duration::try_from(delay).ok())
+}
+
 pub async fn invoke<A, R, RT, C, E>(cancel: Option<CancellationToken>, retry: Option<RT>, action: A) -> Result<R, E>
 where
     E: TryAs<Status> + From<Status>,
@@ -94,36 +170,110 @@ where
     C: Condition<E>,
     RT: Retry<E, C> + Default,
 {
-    let retry = retry.unwrap_or_default();
-    match cancel {
-        Some(cancel) => {
-            select! {
-                _ = cancel.cancelled() => Err(Status::cancelled("client cancel").into()),
-                v = RetryIf::spawn(retry.strategy(), action, retry.condition()) => v
-            }
-        }
-        None => RetryIf::spawn(retry.strategy(), action, retry.condition()).await,
-    }
+    invoke_fn(
+        cancel,
+        retry,
+        |mut action| async move { action.run().await.map_err(|e| (e, action)) },
+        action,
+    )
+    .await
+}
+
+/// RetryMetrics is the latency breakdown of the retries.
+#[derive(Clone, Debug, Default)]
+pub struct RetryMetrics {
+    /// attempts is the elapsed time of each attempt.
+    pub attempts: Vec<Duration>,
+    /// backoff is the total time spent sleeping between the attempts.
+    pub backoff: Duration,
+}
+
+/// is_connection_error returns true if the status is caused by the broken connection to the server,
+/// such as the connection refused or reset, rather than returned by the server.
+pub fn is_connection_error(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::Unknown) && std::error::Error::source(status).is_some()
 }
+
 /// Repeats retries when the specified error is detected.
 /// The argument specified by 'v' can be reused for each retry.
+/// When the error has the delay suggested by the server, it waits at least the suggested delay.
 pub async fn invoke_fn<R, V, A, RT, C, E>(
     cancel: Option<CancellationToken>,
     retry: Option<RT>,
+    f: impl FnMut(V) -> A,
+    v: V,
+) -> Result<R, E>
+where
+    E: TryAs<Status> + From<Status>,
+    A: Future<Output = Result<R, (E, V)>>,
+    C: Condition<E>,
+    RT: Retry<E, C> + Default,
+{
+    invoke_fn_with_metrics(cancel, retry, f, v).await.0
+}
+
+/// invoke_fn_with_connection_retry is the same as invoke_fn, but when connection_retry is true
+/// the action is retried once immediately on the connection error, so that the channel is re-established.
+/// This connection-level retry is done regardless of the retry setting and does not consume its attempts.
+pub async fn invoke_fn_with_connection_retry<R, V, A, RT, C, E>(
+    cancel: Option<CancellationToken>,
+    retry: Option<RT>,
+    connection_retry: bool,
+    f: impl FnMut(V) -> A,
+    v: V,
+) -> Result<R, E>
+where
+    E: TryAs<Status> + From<Status>,
+    A: Future<Output = Result<R, (E, V)>>,
+    C: Condition<E>,
+    RT: Retry<E, C> + Default,
+{
+    invoke_loop(cancel, retry, connection_retry, f, v).await.0
+}
+
+/// invoke_fn_with_metrics is the same as invoke_fn, but also returns the elapsed time of each attempt
+/// and the time spent sleeping between the attempts.
+pub async fn invoke_fn_with_metrics<R, V, A, RT, C, E>(
+    cancel: Option<CancellationToken>,
+    retry: Option<RT>,
+    f: impl FnMut(V) -> A,
+    v: V,
+) -> (Result<R, E>, RetryMetrics)
+where
+    E: TryAs<Status> + From<Status>,
+    A: Future<Output = Result<R, (E, V)>>,
+    C: Condition<E>,
+    RT: Retry<E, C> + Default,
+{
+    invoke_loop(cancel, retry, false, f, v).await
+}
+
+async fn invoke_loop<R, V, A, RT, C, E>(
+    cancel: Option<CancellationToken>,
+    retry: Option<RT>,
+    connection_retry: bool,
     mut f: impl FnMut(V) -> A,
     mut v: V,
-) -> Result<R, E>
+) -> (Result<R, E>, RetryMetrics)
 where
     E: TryAs<Status> + From<Status>,
     A: Future<Output = Result<R, (E, V)>>,
     C: Condition<E>,
     RT: Retry<E, C> + Default,
 {
+    let mut metrics = RetryMetrics::default();
+    let retry = retry.unwrap_or_default();
+    let deadline = retry.timeout().map(|timeout| Instant::now() + timeout);
     let fn_loop = async {
-        let retry = retry.unwrap_or_default();
         let mut strategy = retry.strategy();
+        let mut reconnected = false;
         loop {
+            if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                return Err(deadline_exceeded(None).into());
+            }
+            let start = Instant::now();
             let result = f(v).await;
+            metrics.attempts.push(start.elapsed());
             let status = match result {
                 Ok(s) => return Ok(s),
                 Err(e) => {
@@ -131,19 +281,43 @@ where
                     e.0
                 }
             };
+            if connection_retry && !reconnected && matches!(status.try_as(), Some(s) if is_connection_error(s)) {
+                reconnected = true;
+                tracing::debug!("retry on connection error");
+                continue;
+            }
             if retry.condition().should_retry(&status) {
                 let duration = match strategy.next() {
                     None => return Err(status),
                     Some(s) => s,
                 };
+                let duration = match status.try_as().and_then(retry_delay) {
+                    Some(suggested) if suggested > duration => suggested,
+                    _ => duration,
+                };
+                // Stop now rather than sleeping out the backoff that ends after the deadline.
+                if matches!(deadline, Some(deadline) if Instant::now() + duration >= deadline) {
+                    return Err(deadline_exceeded(status.try_as()).into());
+                }
+                let start = Instant::now();
                 tokio::time::sleep(duration).await;
+                metrics.backoff += start.elapsed();
                 tracing::trace!("retry fn");
             } else {
                 return Err(status);
             }
         }
     };
-    match cancel {
+    // The attempt in progress at the deadline is also stopped.
+    let fn_loop = async {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), fn_loop)
+                .await
+                .unwrap_or_else(|_| Err(deadline_exceeded(None).into())),
+            None => fn_loop.await,
+        }
+    };
+    let result = match cancel {
         Some(cancel) => {
             select! {
                 _ = cancel.cancelled() => Err(Status::cancelled("client cancel").into()),
@@ -151,5 +325,326 @@ where
             }
         }
         None => fn_loop.await,
+    };
+    (result, metrics)
+}
+
+/// deadline_exceeded returns the error of the retry stopped by the deadline with the last error.
+fn deadline_exceeded(last: Option<&Status>) -> Status {
+    match last {
+        Some(last) => Status::new(
+            Code::DeadlineExceeded,
+            format!("retry deadline exceeded: last error: {}", last.message()),
+        ),
+        None => Status::new(Code::DeadlineExceeded, "retry deadline exceeded"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use prost::Message;
+    use tonic::codegen::Bytes;
+
+    use crate::grpc::{Code, Status};
+    use crate::retry::{
+        invoke_fn, invoke_fn_with_connection_retry, invoke_fn_with_metrics, is_connection_error, retry_delay,
+        CodeCondition, ExponentialBackoff, Retry, RetryInfo, RetrySetting, StatusDetails, RETRY_INFO_TYPE_URL,
+    };
+    use std::iter::Take;
+
+    /// TimeoutSetting is the RetrySetting bounded by the timeout.
+    #[derive(Clone, Default)]
+    struct TimeoutSetting {
+        inner: RetrySetting,
+        timeout: Option<Duration>,
+    }
+
+    impl Retry<Status, CodeCondition> for TimeoutSetting {
+        fn strategy(&self) -> Take<ExponentialBackoff> {
+            self.inner.strategy()
+        }
+
+        fn condition(&self) -> CodeCondition {
+            self.inner.condition()
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+    }
+
+    fn with_timeout(from_millis: u64, timeout: Duration) -> TimeoutSetting {
+        TimeoutSetting {
+            inner: RetrySetting {
+                from_millis,
+                max_delay: Some(Duration::from_millis(from_millis)),
+                codes: vec![Code::Aborted],
+                ..Default::default()
+            },
+            timeout: Some(timeout),
+        }
+    }
+
+    fn unavailable(delay: Option<Duration>) -> Status {
+        let details = match delay {
+            Some(delay) => vec![prost_types::Any {
+                type_url: RETRY_INFO_TYPE_URL.to_string(),
+                value: RetryInfo {
+                    retry_delay: Some(delay.try_into().unwrap()),
+                }
+                .encode_to_vec(),
+            }],
+            None => vec![],
+        };
+        let details = StatusDetails {
+            code: Code::Unavailable as i32,
+            message: "unavailable".to_string(),
+            details,
+        };
+        Status::with_details(Code::Unavailable, "unavailable", Bytes::from(details.encode_to_vec()))
+    }
+
+    async fn retry_once(status: Status) -> Duration {
+        let setting = RetrySetting {
+            from_millis: 1,
+            max_delay: Some(Duration::from_millis(1)),
+            ..Default::default()
+        };
+        let count = AtomicUsize::new(0);
+        let start = Instant::now();
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(setting),
+            |status| async {
+                if count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err((status.clone(), status))
+                } else {
+                    Ok(())
+                }
+            },
+            status,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        start.elapsed()
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_deadline_shorter_than_backoff() {
+        let setting = with_timeout(1000, Duration::from_millis(100));
+        let count = AtomicUsize::new(0);
+        let start = Instant::now();
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(setting),
+            |_| async {
+                if count.fetch_add(1, Ordering::SeqCst) < 10 {
+                    Err((Status::new(Code::Aborted, "aborted"), ()))
+                } else {
+                    Ok(())
+                }
+            },
+            (),
+        )
+        .await;
+        assert!(start.elapsed() < Duration::from_millis(500), "elapsed = {:?}", start.elapsed());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        let status = result.unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded);
+        assert_eq!(status.message(), "retry deadline exceeded: last error: aborted");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_deadline_spans_attempts() {
+        // the attempt in progress is stopped
+        let setting = with_timeout(1, Duration::from_millis(100));
+        let start = Instant::now();
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(setting.clone()),
+            |_| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            },
+            (),
+        )
+        .await;
+        assert!(start.elapsed() < Duration::from_millis(500), "elapsed = {:?}", start.elapsed());
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+
+        // the deadline is resolved when the retry starts, so the setting is reusable
+        let result: Result<(), Status> = invoke_fn(None, Some(setting), |_| async { Ok(()) }, ()).await;
+        assert!(result.is_ok());
+
+        // no attempt is started after the deadline
+        let count = AtomicUsize::new(0);
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(with_timeout(1, Duration::ZERO)),
+            |_| async {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+            (),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        // the retry within the deadline is not affected
+        let setting = with_timeout(1, Duration::from_secs(10));
+        let count = AtomicUsize::new(0);
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(setting),
+            |_| async {
+                if count.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err((Status::new(Code::Aborted, "aborted"), ()))
+                } else {
+                    Ok(())
+                }
+            },
+            (),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let status = unavailable(Some(Duration::from_millis(1500)));
+        assert_eq!(retry_delay(&status), Some(Duration::from_millis(1500)));
+        assert_eq!(retry_delay(&unavailable(None)), None);
+        assert_eq!(retry_delay(&Status::new(Code::Unavailable, "unavailable")), None);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_respects_retry_delay() {
+        let elapsed = retry_once(unavailable(Some(Duration::from_millis(300)))).await;
+        assert!(elapsed >= Duration::from_millis(300), "elapsed = {:?}", elapsed);
+
+        let elapsed = retry_once(unavailable(None)).await;
+        assert!(elapsed < Duration::from_millis(300), "elapsed = {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_with_metrics() {
+        let setting = RetrySetting {
+            from_millis: 10,
+            max_delay: Some(Duration::from_millis(10)),
+            codes: vec![Code::Aborted],
+            ..Default::default()
+        };
+        let count = AtomicUsize::new(0);
+        let (result, metrics) = invoke_fn_with_metrics(
+            None,
+            Some(setting),
+            |_| async {
+                if count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err((Status::new(Code::Aborted, "aborted"), ()))
+                } else {
+                    Ok(())
+                }
+            },
+            (),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(metrics.attempts.len(), 2);
+        assert!(metrics.backoff > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_with_backoff_base() {
+        let setting = RetrySetting::default().with_backoff_base(Duration::from_millis(10), Duration::from_millis(500));
+        assert_eq!(setting.from_millis, 10);
+        assert_eq!(setting.backoff_base(), Duration::from_millis(10));
+        assert_eq!(setting.max_delay, Some(Duration::from_millis(500)));
+        assert_eq!(setting.take, RetrySetting::default().take);
+        let backoffs: Vec<Duration> = setting.strategy().collect();
+        assert_eq!(backoffs, [10, 100, 500, 500, 500].map(Duration::from_millis).to_vec());
+
+        // the fraction of a millisecond is truncated.
+        let setting = RetrySetting::default().with_backoff_base(Duration::from_micros(2500), Duration::from_secs(1));
+        assert_eq!(setting.backoff_base(), Duration::from_millis(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "backoff base must be at least 1ms")]
+    fn test_with_backoff_base_under_millisecond() {
+        RetrySetting::default().with_backoff_base(Duration::from_micros(500), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_disabled() {
+        let setting = RetrySetting::disabled();
+        assert!(setting.is_disabled());
+        assert!(!RetrySetting::default().is_disabled());
+
+        let count = AtomicUsize::new(0);
+        let result: Result<(), Status> = invoke_fn(
+            None,
+            Some(setting),
+            |_| async {
+                count.fetch_add(1, Ordering::SeqCst);
+                Err((Status::new(Code::Unavailable, "unavailable"), ()))
+            },
+            (),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    fn connection_reset() -> Status {
+        let error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset");
+        Status::from_error(Box::new(error))
+    }
+
+    async fn fail_once(connection_retry: bool, status: Status) -> (Result<(), Status>, usize) {
+        let count = AtomicUsize::new(0);
+        let result = invoke_fn_with_connection_retry(
+            None,
+            Some(RetrySetting::disabled()),
+            connection_retry,
+            |status| async {
+                if count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err((status.clone(), status))
+                } else {
+                    Ok(())
+                }
+            },
+            status,
+        )
+        .await;
+        (result, count.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn test_is_connection_error() {
+        assert!(is_connection_error(&connection_reset()));
+        assert!(!is_connection_error(&Status::new(Code::Unavailable, "unavailable")));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_fn_with_connection_retry() {
+        let (result, count) = fail_once(true, connection_reset()).await;
+        assert!(result.is_ok());
+        assert_eq!(count, 2);
+
+        let (result, count) = fail_once(false, connection_reset()).await;
+        assert!(result.is_err());
+        assert_eq!(count, 1);
+
+        // the error returned by the server is not retried at connection level.
+        let (result, count) = fail_once(true, Status::new(Code::Unavailable, "unavailable")).await;
+        assert!(result.is_err());
+        assert_eq!(count, 1);
     }
 }
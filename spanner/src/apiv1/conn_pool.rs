@@ -1,7 +1,8 @@
 use google_cloud_gax::conn::{ConnectionManager as GRPCConnectionManager, Environment, Error};
+use google_cloud_gax::grpc::metadata::{Ascii, MetadataValue};
 use google_cloud_googleapis::spanner::v1::spanner_client::SpannerClient;
 
-use crate::apiv1::spanner_client::Client;
+use crate::apiv1::spanner_client::{api_client_header, Client};
 
 pub const AUDIENCE: &str = "https://spanner.googleapis.com/";
 pub const SPANNER: &str = "spanner.googleapis.com";
@@ -12,15 +13,39 @@ pub const SCOPES: [&str; 2] = [
 
 pub struct ConnectionManager {
     inner: GRPCConnectionManager,
+    connection_retry: bool,
+    route_to_leader: bool,
+    api_client: MetadataValue<Ascii>,
 }
 
 impl ConnectionManager {
     pub async fn new(pool_size: usize, environment: &Environment, domain: &str) -> Result<Self, Error> {
         Ok(ConnectionManager {
             inner: GRPCConnectionManager::new(pool_size, domain, AUDIENCE, environment).await?,
+            connection_retry: false,
+            route_to_leader: false,
+            api_client: api_client_header(None).expect("the default x-goog-api-client is valid"),
         })
     }
 
+    /// with_connection_retry enables the clients to retry the request once on the connection error.
+    pub fn with_connection_retry(mut self, connection_retry: bool) -> Self {
+        self.connection_retry = connection_retry;
+        self
+    }
+
+    /// with_route_to_leader enables the clients to route the requests of the read-write transactions to the leader region.
+    pub fn with_route_to_leader(mut self, route_to_leader: bool) -> Self {
+        self.route_to_leader = route_to_leader;
+        self
+    }
+
+    /// with_api_client sets the header `x-goog-api-client` sent by the clients, see api_client_header.
+    pub fn with_api_client(mut self, api_client: MetadataValue<Ascii>) -> Self {
+        self.api_client = api_client;
+        self
+    }
+
     pub fn num(&self) -> usize {
         self.inner.num()
     }
@@ -28,5 +53,16 @@ impl ConnectionManager {
     pub fn conn(&self) -> Client {
         let conn = self.inner.conn();
         Client::new(SpannerClient::new(conn))
+            .with_connection_retry(self.connection_retry)
+            .with_route_to_leader(self.route_to_leader)
+            .with_api_client(self.api_client.clone())
+    }
+
+    pub fn conn_at(&self, index: usize) -> Client {
+        let conn = self.inner.conn_at(index);
+        Client::new(SpannerClient::new(conn))
+            .with_connection_retry(self.connection_retry)
+            .with_route_to_leader(self.route_to_leader)
+            .with_api_client(self.api_client.clone())
     }
 }
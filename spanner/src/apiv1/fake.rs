@@ -0,0 +1,278 @@
+//! fake is the in-process Spanner server for the tests that inspect the requests sent by the client
+//! or inject the errors the emulator cannot return.
+
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ready, Ready};
+use futures_util::stream;
+use parking_lot::Mutex;
+use prost::Message;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::metadata::MetadataMap;
+use tonic::server::{Grpc, NamedService};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Body, Server};
+use tonic::{Request, Response, Status};
+
+use google_cloud_googleapis::spanner::v1::result_set_stats::RowCount;
+use google_cloud_googleapis::spanner::v1::{
+    BatchCreateSessionsRequest, BatchCreateSessionsResponse, BeginTransactionRequest, CommitRequest, CommitResponse,
+    CreateSessionRequest, DeleteSessionRequest, ExecuteBatchDmlRequest, ExecuteBatchDmlResponse, ExecuteSqlRequest,
+    GetSessionRequest, PartialResultSet, Partition, PartitionQueryRequest, PartitionReadRequest, PartitionResponse,
+    ReadRequest, ResultSet, ResultSetStats, RollbackRequest, Session, Transaction,
+};
+
+/// Recorded is a request received by the fake.
+struct Recorded {
+    method: String,
+    metadata: MetadataMap,
+    message: Box<dyn Any + Send>,
+}
+
+#[derive(Default)]
+struct State {
+    requests: Vec<Recorded>,
+    errors: HashMap<String, VecDeque<Status>>,
+    batch_dml: Option<ExecuteBatchDmlResponse>,
+    results: Vec<PartialResultSet>,
+    sessions: usize,
+}
+
+/// FakeSpanner answers every RPC with a minimal successful response unless an error is injected by fail.
+#[derive(Clone, Default)]
+pub(crate) struct FakeSpanner {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeSpanner {
+    /// start serves the fake on a free local port and returns the host to connect as the emulator.
+    pub async fn start(&self) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(self.clone())
+                .serve_with_incoming(incoming),
+        );
+        host
+    }
+
+    /// fail makes the next call of the method return the status.
+    pub fn fail(&self, method: &str, status: Status) {
+        self.state
+            .lock()
+            .errors
+            .entry(method.to_string())
+            .or_default()
+            .push_back(status);
+    }
+
+    /// set_batch_dml sets the response of ExecuteBatchDml.
+    pub fn set_batch_dml(&self, response: ExecuteBatchDmlResponse) {
+        self.state.lock().batch_dml = Some(response);
+    }
+
+    /// set_results sets the partial result sets returned by ExecuteStreamingSql and StreamingRead.
+    pub fn set_results(&self, results: Vec<PartialResultSet>) {
+        self.state.lock().results = results;
+    }
+
+    /// requests returns the metadata and the message of the requests of the method in order.
+    pub fn requests<M: Clone + 'static>(&self, method: &str) -> Vec<(MetadataMap, M)> {
+        self.state
+            .lock()
+            .requests
+            .iter()
+            .filter(|r| r.method == method)
+            .filter_map(|r| Some((r.metadata.clone(), r.message.downcast_ref::<M>()?.clone())))
+            .collect()
+    }
+
+    fn record<M: Clone + Send + 'static>(&self, method: &str, request: &Request<M>) -> Result<(), Box<Status>> {
+        let mut state = self.state.lock();
+        state.requests.push(Recorded {
+            method: method.to_string(),
+            metadata: request.metadata().clone(),
+            message: Box::new(request.get_ref().clone()),
+        });
+        match state.errors.get_mut(method).and_then(VecDeque::pop_front) {
+            Some(status) => Err(Box::new(status)),
+            None => Ok(()),
+        }
+    }
+
+    fn session(&self, database: &str) -> Session {
+        let mut state = self.state.lock();
+        state.sessions += 1;
+        Session {
+            name: format!("{}/sessions/{}", database, state.sessions),
+            ..Default::default()
+        }
+    }
+
+    async fn unary<Req, Resp>(
+        self,
+        method: String,
+        req: http::Request<Body>,
+        f: impl FnOnce(&FakeSpanner, &Req) -> Resp + Send + 'static,
+    ) -> http::Response<BoxBody>
+    where
+        Req: Message + Default + Clone + Send + 'static,
+        Resp: Message + Send + 'static,
+    {
+        let fake = self.clone();
+        let handler = Handler(Some(move |request: Request<Req>| {
+            fake.record(&method, &request)?;
+            Ok(Response::new(f(&fake, request.get_ref())))
+        }));
+        Grpc::new(ProstCodec::<Resp, Req>::default()).unary(handler, req).await
+    }
+
+    async fn streaming<Req>(self, method: String, req: http::Request<Body>) -> http::Response<BoxBody>
+    where
+        Req: Message + Default + Clone + Send + 'static,
+    {
+        let fake = self.clone();
+        let handler = Handler(Some(move |request: Request<Req>| {
+            fake.record(&method, &request)?;
+            let results = fake.state.lock().results.clone();
+            Ok(Response::new(stream::iter(results.into_iter().map(Ok::<_, Status>))))
+        }));
+        Grpc::new(ProstCodec::<PartialResultSet, Req>::default())
+            .server_streaming(handler, req)
+            .await
+    }
+}
+
+impl NamedService for FakeSpanner {
+    const NAME: &'static str = "google.spanner.v1.Spanner";
+}
+
+impl Service<http::Request<Body>> for FakeSpanner {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let fake = self.clone();
+        let method = req.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+        Box::pin(async move {
+            let response = match method.as_str() {
+                "CreateSession" => {
+                    fake.unary(method, req, |f, r: &CreateSessionRequest| f.session(&r.database))
+                        .await
+                }
+                "BatchCreateSessions" => {
+                    fake.unary(method, req, |f, r: &BatchCreateSessionsRequest| BatchCreateSessionsResponse {
+                        session: (0..r.session_count).map(|_| f.session(&r.database)).collect(),
+                    })
+                    .await
+                }
+                "GetSession" => {
+                    fake.unary(method, req, |_, r: &GetSessionRequest| Session {
+                        name: r.name.clone(),
+                        ..Default::default()
+                    })
+                    .await
+                }
+                "DeleteSession" => fake.unary(method, req, |_, _: &DeleteSessionRequest| ()).await,
+                "ExecuteSql" => {
+                    fake.unary(method, req, |_, _: &ExecuteSqlRequest| ResultSet::default())
+                        .await
+                }
+                "Read" => fake.unary(method, req, |_, _: &ReadRequest| ResultSet::default()).await,
+                "ExecuteStreamingSql" => fake.streaming::<ExecuteSqlRequest>(method, req).await,
+                "StreamingRead" => fake.streaming::<ReadRequest>(method, req).await,
+                "ExecuteBatchDml" => {
+                    fake.unary(method, req, |f, r: &ExecuteBatchDmlRequest| {
+                        f.state
+                            .lock()
+                            .batch_dml
+                            .clone()
+                            .unwrap_or_else(|| ExecuteBatchDmlResponse {
+                                result_sets: r
+                                    .statements
+                                    .iter()
+                                    .map(|_| ResultSet {
+                                        stats: Some(ResultSetStats {
+                                            row_count: Some(RowCount::RowCountExact(1)),
+                                            ..Default::default()
+                                        }),
+                                        ..Default::default()
+                                    })
+                                    .collect(),
+                                status: Some(Default::default()),
+                            })
+                    })
+                    .await
+                }
+                "BeginTransaction" => {
+                    fake.unary(method, req, |_, _: &BeginTransactionRequest| Transaction {
+                        id: b"transaction".to_vec(),
+                        read_timestamp: Some(Default::default()),
+                    })
+                    .await
+                }
+                "Commit" => {
+                    fake.unary(method, req, |_, _: &CommitRequest| CommitResponse {
+                        commit_timestamp: Some(Default::default()),
+                        commit_stats: None,
+                    })
+                    .await
+                }
+                "Rollback" => fake.unary(method, req, |_, _: &RollbackRequest| ()).await,
+                "PartitionQuery" => {
+                    fake.unary(method, req, |_, _: &PartitionQueryRequest| partitions())
+                        .await
+                }
+                "PartitionRead" => {
+                    fake.unary(method, req, |_, _: &PartitionReadRequest| partitions())
+                        .await
+                }
+                _ => Status::unimplemented(method).to_http(),
+            };
+            Ok(response)
+        })
+    }
+}
+
+fn partitions() -> PartitionResponse {
+    PartitionResponse {
+        partitions: vec![Partition {
+            partition_token: b"partition".to_vec(),
+        }],
+        transaction: None,
+    }
+}
+
+/// Handler serves one request with the function. The status is boxed by the function to keep its result small.
+struct Handler<F>(Option<F>);
+
+impl<Req, Resp, F> Service<Request<Req>> for Handler<F>
+where
+    F: FnOnce(Request<Req>) -> Result<Response<Resp>, Box<Status>>,
+{
+    type Response = Response<Resp>;
+    type Error = Status;
+    type Future = Ready<Result<Response<Resp>, Status>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        let f = self.0.take().expect("handler is called once");
+        ready(f(request).map_err(|status| *status))
+    }
+}
@@ -1,6 +1,9 @@
 pub mod conn_pool;
 pub mod spanner_client;
 
+#[cfg(test)]
+pub(crate) mod fake;
+
 #[cfg(test)]
 mod tests {
     use prost_types::{value::Kind, ListValue, Value};
@@ -3,17 +3,22 @@ use std::time::Duration;
 use google_cloud_gax::cancel::CancellationToken;
 use google_cloud_gax::conn::Channel;
 use google_cloud_gax::create_request;
-use google_cloud_gax::grpc::{Code, Response, Status, Streaming};
-use google_cloud_gax::retry::{invoke_fn, RetrySetting};
+use google_cloud_gax::grpc::metadata::errors::InvalidMetadataValue;
+use google_cloud_gax::grpc::metadata::{Ascii, MetadataValue};
+use google_cloud_gax::grpc::{Code, Request, Response, Status, Streaming};
+use google_cloud_gax::retry::{invoke_fn_with_connection_retry, RetrySetting};
 use google_cloud_googleapis::spanner::v1 as internal;
 use google_cloud_googleapis::spanner::v1::spanner_client::SpannerClient;
 use google_cloud_googleapis::spanner::v1::{
-    BatchCreateSessionsRequest, BatchCreateSessionsResponse, BeginTransactionRequest, CommitRequest, CommitResponse,
-    CreateSessionRequest, DeleteSessionRequest, ExecuteBatchDmlRequest, ExecuteBatchDmlResponse, ExecuteSqlRequest,
-    GetSessionRequest, ListSessionsRequest, ListSessionsResponse, PartialResultSet, PartitionQueryRequest,
-    PartitionReadRequest, PartitionResponse, ReadRequest, ResultSet, RollbackRequest, Session, Transaction,
+    transaction_options, BatchCreateSessionsRequest, BatchCreateSessionsResponse, BeginTransactionRequest,
+    CommitRequest, CommitResponse, CreateSessionRequest, DeleteSessionRequest, ExecuteBatchDmlRequest,
+    ExecuteBatchDmlResponse, ExecuteSqlRequest, GetSessionRequest, ListSessionsRequest, ListSessionsResponse,
+    PartialResultSet, PartitionQueryRequest, PartitionReadRequest, PartitionResponse, ReadRequest, ResultSet,
+    RollbackRequest, Session, Transaction, TransactionOptions,
 };
 
+use crate::retry::RpcRetrySetting;
+
 pub(crate) fn ping_query_request(session_name: impl Into<String>) -> internal::ExecuteSqlRequest {
     internal::ExecuteSqlRequest {
         session: session_name.into(),
@@ -30,25 +35,126 @@ pub(crate) fn ping_query_request(session_name: impl Into<String>) -> internal::E
     }
 }
 
-fn default_setting() -> RetrySetting {
+/// default_setting is the retry setting of the RPCs whose retry is not specified.
+pub(crate) fn default_setting() -> RetrySetting {
     RetrySetting {
         from_millis: 50,
         max_delay: Some(Duration::from_secs(10)),
         factor: 1u64,
         take: 20,
-        codes: vec![Code::Unavailable, Code::Unknown],
+        // only the transient INTERNAL errors are retried.
+        codes: vec![Code::Unavailable, Code::Unknown, Code::Internal],
+    }
+}
+
+/// ROLLBACK_TIMEOUT bounds the retries of the rollback, so that the cleanup doesn't block the caller
+/// for long while the locks held by the transaction are released promptly on the transient error.
+pub const ROLLBACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// rollback_setting returns the retry setting of the rollback. The rollback is idempotent,
+/// so the retryable codes are retried until ROLLBACK_TIMEOUT.
+fn rollback_setting(retry: Option<RetrySetting>) -> RpcRetrySetting {
+    RpcRetrySetting::new(retry.unwrap_or_else(default_setting)).with_timeout(ROLLBACK_TIMEOUT)
+}
+
+/// ROUTE_TO_LEADER_HEADER is the header to route the read-write requests to the leader region.
+pub const ROUTE_TO_LEADER_HEADER: &str = "x-goog-spanner-route-to-leader";
+
+/// API_CLIENT_HEADER is the header to identify the client library and the application in the server logs.
+pub const API_CLIENT_HEADER: &str = "x-goog-api-client";
+
+/// DEFAULT_API_CLIENT is the value of `x-goog-api-client` identifying this crate and its version.
+const DEFAULT_API_CLIENT: &str = concat!("gl-rust/ gccl/", env!("CARGO_PKG_VERSION"));
+
+/// api_client_header returns the value of `x-goog-api-client` with the user agent of the application
+/// prepended to the identifier of this crate, e.g. `my-app/1.0 gl-rust/ gccl/0.22.0`.
+pub fn api_client_header(user_agent: Option<&str>) -> Result<MetadataValue<Ascii>, InvalidMetadataValue> {
+    match user_agent {
+        Some(user_agent) => MetadataValue::try_from(format!("{user_agent} {DEFAULT_API_CLIENT}")),
+        None => Ok(MetadataValue::from_static(DEFAULT_API_CLIENT)),
     }
 }
 
+/// create_routed_request creates the request with the routing header `x-goog-request-params` and `x-goog-api-client`,
+/// and with `x-goog-spanner-route-to-leader` if route_to_leader is true.
+fn create_routed_request<T>(
+    param_string: String,
+    req: T,
+    route_to_leader: bool,
+    api_client: &MetadataValue<Ascii>,
+) -> Request<T> {
+    let mut request = create_request(param_string, req);
+    request.metadata_mut().insert(API_CLIENT_HEADER, api_client.clone());
+    if route_to_leader {
+        request
+            .metadata_mut()
+            .insert(ROUTE_TO_LEADER_HEADER, MetadataValue::from_static("true"));
+    }
+    request
+}
+
+/// is_read_write returns true if the transaction begins as a read-write or a partitioned DML transaction.
+fn is_read_write(options: &Option<TransactionOptions>) -> bool {
+    matches!(
+        options.as_ref().and_then(|o| o.mode.as_ref()),
+        Some(transaction_options::Mode::ReadWrite(_)) | Some(transaction_options::Mode::PartitionedDml(_))
+    )
+}
+
 #[derive(Clone)]
 pub struct Client {
     inner: SpannerClient<Channel>,
+    connection_retry: bool,
+    route_to_leader: bool,
+    read_write: bool,
+    api_client: MetadataValue<Ascii>,
 }
 
 impl Client {
     /// create new spanner client
     pub fn new(inner: SpannerClient<Channel>) -> Client {
-        Client { inner }
+        Client {
+            inner,
+            connection_retry: false,
+            route_to_leader: false,
+            read_write: false,
+            api_client: MetadataValue::from_static(DEFAULT_API_CLIENT),
+        }
+    }
+
+    /// raw returns the underlying gRPC client to call the RPCs this crate doesn't wrap yet.
+    /// The requests sent with it are neither retried nor given the routing header `x-goog-request-params`.
+    pub fn raw(&mut self) -> &mut SpannerClient<Channel> {
+        &mut self.inner
+    }
+
+    /// with_connection_retry enables to retry the request once on the connection error.
+    pub fn with_connection_retry(mut self, connection_retry: bool) -> Client {
+        self.connection_retry = connection_retry;
+        self
+    }
+
+    /// with_route_to_leader enables to send the header `x-goog-spanner-route-to-leader` with the requests
+    /// of the read-write transactions, i.e. begin_transaction of a read-write or a partitioned DML transaction,
+    /// execute_batch_dml, commit, rollback and the statements executed with the client returned by read_write.
+    /// Routing to the leader region reduces the latency of the read-write transactions in the multi-region instance.
+    pub fn with_route_to_leader(mut self, route_to_leader: bool) -> Client {
+        self.route_to_leader = route_to_leader;
+        self
+    }
+
+    /// with_api_client sets the value of the header `x-goog-api-client` sent with all the requests.
+    pub fn with_api_client(mut self, api_client: MetadataValue<Ascii>) -> Client {
+        self.api_client = api_client;
+        self
+    }
+
+    /// read_write returns the client to execute the statements of the read-write transaction.
+    pub(crate) fn read_write(&self) -> Client {
+        Client {
+            read_write: true,
+            ..self.clone()
+        }
     }
 
     /// create_session creates a new session. A session can be used to perform
@@ -100,11 +206,13 @@ impl Client {
     ) -> Result<Response<Session>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let database = &req.database;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("database={database}"), req.clone());
+                let request = create_routed_request(format!("database={database}"), req.clone(), false, api_client);
                 spanner_client
                     .create_session(request)
                     .await
@@ -149,11 +257,13 @@ impl Client {
     ) -> Result<Response<BatchCreateSessionsResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let database = &req.database;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("database={database}"), req.clone());
+                let request = create_routed_request(format!("database={database}"), req.clone(), false, api_client);
                 spanner_client
                     .batch_create_sessions(request)
                     .await
@@ -196,11 +306,13 @@ impl Client {
     ) -> Result<Response<Session>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let name = &req.name;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("name={name}"), req.clone());
+                let request = create_routed_request(format!("name={name}"), req.clone(), false, api_client);
                 spanner_client
                     .get_session(request)
                     .await
@@ -242,11 +354,13 @@ impl Client {
     ) -> Result<Response<ListSessionsResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let database = &req.database;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("database={database}"), req.clone());
+                let request = create_routed_request(format!("database={database}"), req.clone(), false, api_client);
                 spanner_client
                     .list_sessions(request)
                     .await
@@ -290,11 +404,13 @@ impl Client {
     ) -> Result<Response<()>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let name = &req.name;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("name={name}"), req.clone());
+                let request = create_routed_request(format!("name={name}"), req.clone(), false, api_client);
                 spanner_client
                     .delete_session(request)
                     .await
@@ -346,11 +462,15 @@ impl Client {
     ) -> Result<Response<ResultSet>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader && self.read_write;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client
                     .execute_sql(request)
                     .await
@@ -396,11 +516,15 @@ impl Client {
     ) -> Result<Response<Streaming<PartialResultSet>>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader && self.read_write;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client
                     .execute_streaming_sql(request)
                     .await
@@ -429,7 +553,7 @@ impl Client {
         cancel: Option<CancellationToken>,
         retry: Option<RetrySetting>,
     ) -> Result<Response<ExecuteBatchDmlResponse>, Status> {
-        self._execute_batch_dml(req, cancel, retry).await
+        self._execute_batch_dml(req, cancel, retry, true).await
     }
 
     #[cfg(feature = "trace")]
@@ -440,7 +564,31 @@ impl Client {
         cancel: Option<CancellationToken>,
         retry: Option<RetrySetting>,
     ) -> Result<Response<ExecuteBatchDmlResponse>, Status> {
-        self._execute_batch_dml(req, cancel, retry).await
+        self._execute_batch_dml(req, cancel, retry, true).await
+    }
+
+    /// execute_batch_dml_response is the same as execute_batch_dml, but returns the failed statement in the
+    /// ExecuteBatchDmlResponse.status instead of the error, so that the results of the statements executed
+    /// before it are kept. The failed statement is not retried.
+    #[cfg(not(feature = "trace"))]
+    pub(crate) async fn execute_batch_dml_response(
+        &mut self,
+        req: ExecuteBatchDmlRequest,
+        cancel: Option<CancellationToken>,
+        retry: Option<RetrySetting>,
+    ) -> Result<Response<ExecuteBatchDmlResponse>, Status> {
+        self._execute_batch_dml(req, cancel, retry, false).await
+    }
+
+    #[cfg(feature = "trace")]
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn execute_batch_dml_response(
+        &mut self,
+        req: ExecuteBatchDmlRequest,
+        cancel: Option<CancellationToken>,
+        retry: Option<RetrySetting>,
+    ) -> Result<Response<ExecuteBatchDmlResponse>, Status> {
+        self._execute_batch_dml(req, cancel, retry, false).await
     }
 
     #[inline(always)]
@@ -449,18 +597,23 @@ impl Client {
         req: ExecuteBatchDmlRequest,
         cancel: Option<CancellationToken>,
         retry: Option<RetrySetting>,
+        check_status: bool,
     ) -> Result<Response<ExecuteBatchDmlResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 let result = spanner_client.execute_batch_dml(request).await;
                 match result {
                     Ok(response) => match response.get_ref().status.as_ref() {
-                        Some(s) => {
+                        Some(s) if check_status => {
                             let code = Code::from(s.code);
                             if code == Code::Ok {
                                 Ok(response)
@@ -468,7 +621,7 @@ impl Client {
                                 Err((Status::new(code, s.message.to_string()), spanner_client))
                             }
                         }
-                        None => Ok(response),
+                        _ => Ok(response),
                     },
                     Err(err) => Err((err, spanner_client)),
                 }
@@ -521,11 +674,15 @@ impl Client {
     ) -> Result<Response<ResultSet>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader && self.read_write;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client.read(request).await.map_err(|e| (e, spanner_client))
             },
             &mut self.inner,
@@ -568,11 +725,15 @@ impl Client {
     ) -> Result<Response<Streaming<PartialResultSet>>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader && self.read_write;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client
                     .streaming_read(request)
                     .await
@@ -617,11 +778,15 @@ impl Client {
     ) -> Result<Response<Transaction>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader && is_read_write(&req.options);
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client
                     .begin_transaction(request)
                     .await
@@ -676,11 +841,15 @@ impl Client {
     ) -> Result<Response<CommitResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client.commit(request).await.map_err(|e| (e, spanner_client))
             },
             &mut self.inner,
@@ -724,13 +893,17 @@ impl Client {
         cancel: Option<CancellationToken>,
         retry: Option<RetrySetting>,
     ) -> Result<Response<()>, Status> {
-        let setting = retry.unwrap_or_else(default_setting);
+        let setting = rollback_setting(retry);
         let session = &req.session;
-        invoke_fn(
+        let route_to_leader = self.route_to_leader;
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
             Some(setting),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request =
+                    create_routed_request(format!("session={session}"), req.clone(), route_to_leader, api_client);
                 spanner_client.rollback(request).await.map_err(|e| (e, spanner_client))
             },
             &mut self.inner,
@@ -779,11 +952,13 @@ impl Client {
     ) -> Result<Response<PartitionResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request = create_routed_request(format!("session={session}"), req.clone(), false, api_client);
                 spanner_client
                     .partition_query(request)
                     .await
@@ -837,11 +1012,13 @@ impl Client {
     ) -> Result<Response<PartitionResponse>, Status> {
         let setting = retry.unwrap_or_else(default_setting);
         let session = &req.session;
-        invoke_fn(
+        let api_client = &self.api_client;
+        invoke_fn_with_connection_retry(
             cancel,
-            Some(setting),
+            Some(RpcRetrySetting::new(setting)),
+            self.connection_retry,
             |spanner_client| async {
-                let request = create_request(format!("session={session}"), req.clone());
+                let request = create_routed_request(format!("session={session}"), req.clone(), false, api_client);
                 spanner_client
                     .partition_read(request)
                     .await
@@ -852,3 +1029,87 @@ impl Client {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use google_cloud_gax::grpc::metadata::MetadataValue;
+    use google_cloud_gax::grpc::{Code, Status};
+    use google_cloud_gax::retry::{invoke_fn, Retry, RetrySetting};
+    use google_cloud_googleapis::spanner::v1::{transaction_options, CommitRequest, TransactionOptions};
+
+    use crate::apiv1::spanner_client::{
+        api_client_header, create_routed_request, is_read_write, rollback_setting, API_CLIENT_HEADER, ROLLBACK_TIMEOUT,
+        ROUTE_TO_LEADER_HEADER,
+    };
+
+    #[test]
+    fn test_create_routed_request() {
+        let api_client = MetadataValue::from_static("gccl/test");
+        let request = create_routed_request("session=s1".to_string(), CommitRequest::default(), true, &api_client);
+        assert_eq!(request.metadata().get(ROUTE_TO_LEADER_HEADER).unwrap(), "true");
+        assert_eq!(request.metadata().get("x-goog-request-params").unwrap(), "session=s1");
+
+        let request = create_routed_request("session=s1".to_string(), CommitRequest::default(), false, &api_client);
+        assert!(request.metadata().get(ROUTE_TO_LEADER_HEADER).is_none());
+        assert_eq!(request.metadata().get("x-goog-request-params").unwrap(), "session=s1");
+    }
+
+    #[test]
+    fn test_api_client_header() {
+        let version = env!("CARGO_PKG_VERSION");
+        let header = api_client_header(None).unwrap();
+        assert_eq!(header, format!("gl-rust/ gccl/{version}").as_str());
+
+        // the configured user agent is sent in the outgoing metadata.
+        let header = api_client_header(Some("my-app/1.0")).unwrap();
+        let request = create_routed_request("session=s1".to_string(), CommitRequest::default(), false, &header);
+        assert_eq!(
+            request.metadata().get(API_CLIENT_HEADER).unwrap(),
+            format!("my-app/1.0 gl-rust/ gccl/{version}").as_str()
+        );
+
+        assert!(api_client_header(Some("my-app\n")).is_err());
+    }
+
+    #[test]
+    fn test_is_read_write() {
+        let options = |mode| Some(TransactionOptions { mode: Some(mode) });
+        assert!(is_read_write(&options(transaction_options::Mode::ReadWrite(
+            transaction_options::ReadWrite::default()
+        ))));
+        assert!(is_read_write(&options(transaction_options::Mode::PartitionedDml(
+            transaction_options::PartitionedDml::default()
+        ))));
+        assert!(!is_read_write(&options(transaction_options::Mode::ReadOnly(
+            transaction_options::ReadOnly::default()
+        ))));
+        assert!(!is_read_write(&None));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_setting() {
+        // transient UNAVAILABLE is retried and the rollback succeeds.
+        let setting = rollback_setting(None);
+        let result: Result<usize, Status> = invoke_fn(
+            None,
+            Some(setting),
+            |attempt| async move {
+                if attempt == 0 {
+                    Err((Status::new(Code::Unavailable, "unavailable"), attempt + 1))
+                } else {
+                    Ok(attempt + 1)
+                }
+            },
+            0,
+        )
+        .await;
+        assert_eq!(result.unwrap(), 2);
+
+        // bounded by ROLLBACK_TIMEOUT regardless of the retry.
+        assert_eq!(rollback_setting(None).timeout(), Some(ROLLBACK_TIMEOUT));
+        assert_eq!(
+            rollback_setting(Some(RetrySetting::default())).timeout(),
+            Some(ROLLBACK_TIMEOUT)
+        );
+    }
+}
@@ -2,28 +2,47 @@ use std::env::var;
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use google_cloud_gax::cancel::CancellationToken;
 use google_cloud_gax::conn::Environment;
 use google_cloud_gax::grpc::{Code, Status};
-use google_cloud_gax::retry::{invoke_fn, TryAs};
-use google_cloud_googleapis::spanner::v1::{commit_request, transaction_options, Mutation, TransactionOptions};
+use google_cloud_gax::retry::{invoke_fn, invoke_fn_with_metrics, RetryMetrics, RetrySetting, TryAs};
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+use google_cloud_googleapis::spanner::v1::request_options::Priority;
+use google_cloud_googleapis::spanner::v1::transaction_options::read_write::ReadLockMode;
+use google_cloud_googleapis::spanner::v1::{
+    commit_request, transaction_options, BeginTransactionRequest, Mutation, TransactionOptions,
+};
 use google_cloud_token::NopeTokenSourceProvider;
+use parking_lot::RwLock;
 
 use crate::apiv1::conn_pool::{ConnectionManager, SPANNER};
+use crate::apiv1::spanner_client::{self, api_client_header, ping_query_request};
+use crate::information_schema;
+use crate::mutation;
 use crate::retry::TransactionRetrySetting;
 use crate::session::{ManagedSession, SessionConfig, SessionError, SessionManager};
 use crate::statement::Statement;
-use crate::transaction::{CallOptions, QueryOptions};
+use crate::transaction::{CallOptions, QueryOptions, Transaction};
 use crate::transaction_ro::{BatchReadOnlyTransaction, ReadOnlyTransaction};
-use crate::transaction_rw::{commit, CommitOptions, ReadWriteTransaction};
-use crate::value::{Timestamp, TimestampBound};
+use crate::transaction_rw::{
+    commit, read_write_transaction_options, BeginRetrySession, CommitOptions, CommitResult, ReadWriteTransaction,
+    RowCount, TransactionError, TransactionHook,
+};
+use crate::value::TimestampBound;
+
+/// PARTITIONED_DML_TIMEOUT is the default timeout of the partitioned DML, which can take hours on a large table.
+pub const PARTITIONED_DML_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
 
 #[derive(Clone, Default)]
 pub struct PartitionedUpdateOption {
     pub begin_options: CallOptions,
     pub query_options: Option<QueryOptions>,
+    /// timeout bounds the whole partitioned DML including the retries. PARTITIONED_DML_TIMEOUT is used if None.
+    pub timeout: Option<Duration>,
 }
 
 #[derive(Clone)]
@@ -41,21 +60,96 @@ impl Default for ReadOnlyTransactionOption {
     }
 }
 
+/// PriorityStrategy returns the priority of the next attempt of the read-write transaction
+/// from the configured priority and the number of the aborts so far.
+pub type PriorityStrategy = Arc<dyn Fn(Option<Priority>, usize) -> Option<Priority> + Send + Sync>;
+
 #[derive(Clone, Default)]
 pub struct ReadWriteTransactionOption {
     pub begin_options: CallOptions,
     pub commit_options: CommitOptions,
+    /// read_lock_mode is the read lock mode of the transaction.
+    /// ClientConfig::default_read_lock_mode is used when it is unspecified.
+    pub read_lock_mode: ReadLockMode,
+    /// seqno_base is the seqno of the first DML statement of each attempt of the transaction.
+    /// See ReadWriteTransaction::set_seqno_base.
+    pub seqno_base: i64,
+    /// pessimistic_after_aborts escalates the optimistic read lock to the pessimistic one after the transaction
+    /// is retried this many times, so that the transaction conflicting frequently can still succeed.
+    /// It is used only when the read_lock_mode is optimistic. The default None never escalates.
+    pub pessimistic_after_aborts: Option<usize>,
+    /// priority_strategy adjusts the priority of the begin and the commit of each attempt by the number of the aborts.
+    /// The priority of the statements in the transaction is specified by the function f.
+    /// The default None keeps the priority unchanged.
+    pub priority_strategy: Option<PriorityStrategy>,
+    /// hook observes the lifecycle of each attempt of the transaction. The default None observes nothing.
+    pub hook: Option<Arc<dyn TransactionHook>>,
+    /// begin_retry_session decides the session of the retried begin after the begin fails.
+    /// ClientConfig::default_begin_retry_session is used when it is None.
+    pub begin_retry_session: Option<BeginRetrySession>,
+}
+
+impl ReadWriteTransactionOption {
+    /// optimistic returns the option to use the optimistic read lock.
+    /// Locks for the reads are not acquired on read. Instead, they are acquired on commit
+    /// to validate that the read data has not changed since the transaction started.
+    /// This can reduce the contention for read-heavy transactions that rarely conflict.
+    pub fn optimistic() -> Self {
+        ReadWriteTransactionOption {
+            read_lock_mode: ReadLockMode::Optimistic,
+            ..Default::default()
+        }
+    }
+
+    /// with_priority_strategy sets the function to adjust the priority of each attempt by the number of the aborts.
+    /// ```
+    /// use google_cloud_googleapis::spanner::v1::request_options::Priority;
+    /// use google_cloud_spanner::client::ReadWriteTransactionOption;
+    ///
+    /// // back off to the low priority after 3 aborts to let the other writers go first.
+    /// let option = ReadWriteTransactionOption::default()
+    ///     .with_priority_strategy(|priority, aborts| if aborts >= 3 { Some(Priority::Low) } else { priority });
+    /// ```
+    pub fn with_priority_strategy(
+        mut self,
+        f: impl Fn(Option<Priority>, usize) -> Option<Priority> + Send + Sync + 'static,
+    ) -> Self {
+        self.priority_strategy = Some(Arc::new(f));
+        self
+    }
+
+    /// with_hook sets the hook observing the lifecycle of each attempt of the transaction.
+    pub fn with_hook(mut self, hook: impl TransactionHook + 'static) -> Self {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// pessimistic returns the option to use the pessimistic read lock.
+    /// Read locks are acquired immediately on read.
+    pub fn pessimistic() -> Self {
+        ReadWriteTransactionOption {
+            read_lock_mode: ReadLockMode::Pessimistic,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ChannelConfig {
     /// num_channels is the number of gRPC channels.
     pub num_channels: usize,
+    /// connection_retry enables the connection-level retry. When the gRPC channel is broken,
+    /// the request is retried once immediately so that the channel is re-established,
+    /// apart from the retry setting of each request.
+    pub connection_retry: bool,
 }
 
 impl Default for ChannelConfig {
     fn default() -> Self {
-        ChannelConfig { num_channels: 4 }
+        ChannelConfig {
+            num_channels: 4,
+            connection_retry: false,
+        }
     }
 }
 
@@ -70,6 +164,47 @@ pub struct ClientConfig {
     pub endpoint: String,
     /// Runtime project
     pub environment: Environment,
+    /// default_commit_options is used when the commit options are not specified.
+    pub default_commit_options: CommitOptions,
+    /// dialect is the dialect of the database. The parameters are bound with the types of the dialect,
+    /// e.g. NUMERIC parameters are annotated with PG_NUMERIC for the PostgreSQL-dialect database.
+    /// The dialect is not detected: the transactions bind with this value even if Client::database_dialect
+    /// detects another one, so set the detected dialect here to create the client binding with it.
+    pub dialect: DatabaseDialect,
+    /// route_to_leader sends the header `x-goog-spanner-route-to-leader` with the requests of the read-write transactions
+    /// to route them to the leader region, which reduces the commit latency in the multi-region instance.
+    /// Disable it only if the topology of the instance prefers the nearest replica. The default is true.
+    pub route_to_leader: bool,
+    /// user_agent identifies the application in the header `x-goog-api-client` sent with all the requests,
+    /// followed by the identifier and the version of this crate, e.g. `my-app/1.0 gl-rust/ gccl/0.22.0`,
+    /// so that the requests can be attributed in the server logs when diagnosing the issues with Google support.
+    pub user_agent: Option<String>,
+    /// default_read_lock_mode is the read lock mode of the read-write transactions whose
+    /// ReadWriteTransactionOption::read_lock_mode is unspecified. The read lock mode of the option overrides it.
+    /// The default is unspecified, with which Cloud Spanner uses the pessimistic read lock.
+    pub default_read_lock_mode: ReadLockMode,
+    /// emulator_unsupported_options is the handling of the options the emulator may not support,
+    /// such as CommitOptions::return_commit_stats, when the environment is the emulator.
+    /// The default sends the options as is to the emulator.
+    pub emulator_unsupported_options: EmulatorUnsupportedPolicy,
+    /// default_begin_retry_session decides the session of the retried begin of the read-write transactions
+    /// whose ReadWriteTransactionOption::begin_retry_session is None. The default reuses the same session
+    /// because the wound-wait priority of the retried transaction is scoped to the session.
+    pub default_begin_retry_session: BeginRetrySession,
+}
+
+/// EmulatorUnsupportedPolicy decides how to send the options the Cloud Spanner emulator may not support,
+/// e.g. the older versions of the emulator do not return the commit stats.
+/// The options applied to Cloud Spanner are sent as is regardless of the policy.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum EmulatorUnsupportedPolicy {
+    /// Send sends the options as is, and the emulator not supporting them may return an error.
+    #[default]
+    Send,
+    /// WarnAndIgnore logs a warning and sends the request without the unsupported options,
+    /// so that the same code runs against any version of the emulator and Cloud Spanner.
+    /// The CommitResult has no commit stats.
+    WarnAndIgnore,
 }
 
 impl Default for ClientConfig {
@@ -82,6 +217,13 @@ impl Default for ClientConfig {
                 Some(v) => Environment::Emulator(v),
                 None => Environment::GoogleCloud(Box::new(NopeTokenSourceProvider {})),
             },
+            default_commit_options: CommitOptions::default(),
+            dialect: DatabaseDialect::GoogleStandardSql,
+            route_to_leader: true,
+            user_agent: None,
+            default_read_lock_mode: ReadLockMode::Unspecified,
+            emulator_unsupported_options: EmulatorUnsupportedPolicy::default(),
+            default_begin_retry_session: BeginRetrySession::default(),
         };
         config.session_config.min_opened = config.channel_config.num_channels * 4;
         config.session_config.max_opened = config.channel_config.num_channels * 100;
@@ -89,10 +231,70 @@ impl Default for ClientConfig {
     }
 }
 
+/// The builder methods take all the time settings as Duration.
+/// ```
+/// use std::time::Duration;
+/// use google_cloud_spanner::client::ClientConfig;
+///
+/// let config = ClientConfig::default()
+///     .with_session_get_timeout(Duration::from_secs(3))
+///     .with_idle_timeout(Duration::from_secs(10 * 60))
+///     .with_commit_retry_backoff_base(Duration::from_millis(10), Duration::from_secs(5));
+/// ```
+impl ClientConfig {
+    /// with_session_get_timeout sets the maximum time to wait for a session from the session pool.
+    pub fn with_session_get_timeout(mut self, timeout: Duration) -> Self {
+        self.session_config.session_get_timeout = timeout;
+        self
+    }
+
+    /// with_session_acquire_timeout sets the maximum time to wait for a session to be returned to the exhausted session pool.
+    pub fn with_session_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.session_config.acquire_timeout = Some(timeout);
+        self
+    }
+
+    /// with_idle_timeout sets the time before discarding an idle session.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.session_config.idle_timeout = timeout;
+        self
+    }
+
+    /// with_session_alive_trust_duration sets the time a session is regarded as alive without the health check.
+    pub fn with_session_alive_trust_duration(mut self, duration: Duration) -> Self {
+        self.session_config.session_alive_trust_duration = duration;
+        self
+    }
+
+    /// with_refresh_interval sets the interval of the cleanup and the health check of the sessions.
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.session_config.refresh_interval = interval;
+        self
+    }
+
+    /// with_commit_retry_backoff_base sets the backoff of the retry of the commit requests.
+    /// The n-th backoff is base^n milliseconds capped at max as RetrySetting::with_backoff_base.
+    /// The retried codes and the number of the attempts are kept, the ones of the commit RPC if the retry is not set.
+    pub fn with_commit_retry_backoff_base(mut self, base: Duration, max: Duration) -> Self {
+        let retry = self
+            .default_commit_options
+            .call_options
+            .retry
+            .take()
+            .unwrap_or_else(spanner_client::default_setting);
+        self.default_commit_options.call_options.retry = Some(retry.with_backoff_base(base, max));
+        self
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
-    GRPC(#[from] Status),
+    GRPC(Status),
+
+    /// Transaction is the error of the commit or the rollback with the transaction and the session it was for.
+    #[error(transparent)]
+    Transaction(Box<TransactionError>),
 
     #[error(transparent)]
     InvalidSession(#[from] SessionError),
@@ -111,38 +313,128 @@ impl TryAs<Status> for Error {
     fn try_as(&self) -> Option<&Status> {
         match self {
             Error::GRPC(e) => Some(e),
+            Error::Transaction(e) => Some(e.status()),
             _ => None,
         }
     }
 }
 
+impl From<TransactionError> for Error {
+    fn from(err: TransactionError) -> Self {
+        Error::Transaction(Box::new(err))
+    }
+}
+
+/// The status converted from the TransactionError is returned as Error::Transaction.
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        match TransactionError::from_status(&status) {
+            Some(err) => err.copy().into(),
+            None => Error::GRPC(status),
+        }
+    }
+}
+
 /// Client is a client for reading and writing data to a Cloud Spanner database.
 /// A client is safe to use concurrently, except for its Close method.
 #[derive(Clone)]
 pub struct Client {
     sessions: Arc<SessionManager>,
+    default_commit_options: CommitOptions,
+    dialect: DatabaseDialect,
+    default_read_lock_mode: ReadLockMode,
+    emulator: bool,
+    emulator_unsupported_options: EmulatorUnsupportedPolicy,
+    default_begin_retry_session: BeginRetrySession,
+    detected_dialect: Arc<RwLock<Option<DatabaseDialect>>>,
 }
 
 impl Client {
     /// new creates a client to a database. A valid database name has
     /// the form projects/PROJECT_ID/instances/INSTANCE_ID/databases/DATABASE_ID.
     pub async fn new(database: impl Into<String>, config: ClientConfig) -> Result<Self, Error> {
-        if config.session_config.max_opened > config.channel_config.num_channels * 100 {
+        let max_sessions_per_channel = config.session_config.max_sessions_per_channel;
+        if config.session_config.max_opened > config.channel_config.num_channels * max_sessions_per_channel {
             return Err(Error::InvalidConfig(format!(
-                "max session size is {} because max session size is 100 per gRPC connection",
-                config.channel_config.num_channels * 100
+                "max session size is {} because max session size is {} per gRPC connection",
+                config.channel_config.num_channels * max_sessions_per_channel,
+                max_sessions_per_channel
             )));
         }
 
+        let api_client = api_client_header(config.user_agent.as_deref())
+            .map_err(|_| Error::InvalidConfig(format!("invalid user_agent: {:?}", config.user_agent)))?;
+
+        let emulator = matches!(config.environment, Environment::Emulator(_));
         let pool_size = config.channel_config.num_channels;
-        let conn_pool = ConnectionManager::new(pool_size, &config.environment, config.endpoint.as_str()).await?;
+        let conn_pool = ConnectionManager::new(pool_size, &config.environment, config.endpoint.as_str())
+            .await?
+            .with_connection_retry(config.channel_config.connection_retry)
+            .with_route_to_leader(config.route_to_leader)
+            .with_api_client(api_client);
         let session_manager = SessionManager::new(database, conn_pool, config.session_config).await?;
 
         Ok(Client {
             sessions: session_manager,
+            default_commit_options: supported_commit_options(
+                config.default_commit_options,
+                emulator,
+                config.emulator_unsupported_options,
+            ),
+            dialect: config.dialect,
+            default_read_lock_mode: config.default_read_lock_mode,
+            emulator,
+            emulator_unsupported_options: config.emulator_unsupported_options,
+            default_begin_retry_session: config.default_begin_retry_session,
+            detected_dialect: Arc::new(RwLock::new(None)),
         })
     }
 
+    /// is_emulator returns true if the client is connected to the Cloud Spanner emulator.
+    pub fn is_emulator(&self) -> bool {
+        self.emulator
+    }
+
+    /// supports_commit_stats returns false if CommitOptions::return_commit_stats is ignored by
+    /// EmulatorUnsupportedPolicy::WarnAndIgnore on the emulator, with which CommitResult::commit_stats is None.
+    pub fn supports_commit_stats(&self) -> bool {
+        !(self.emulator && self.emulator_unsupported_options == EmulatorUnsupportedPolicy::WarnAndIgnore)
+    }
+
+    /// database_dialect returns the dialect of the database detected from INFORMATION_SCHEMA.DATABASE_OPTIONS,
+    /// so that the libraries supporting both dialects can select the placeholders and the types.
+    /// The dialect is cached after the first call. The detection is independent of ClientConfig::dialect,
+    /// which the transactions keep binding the parameters with, so a client created with the default
+    /// GoogleStandardSql still binds the GoogleSQL types for the detected PostgreSQL-dialect database.
+    /// ```
+    /// use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+    /// use google_cloud_spanner::client::{Client, ClientConfig, Error};
+    ///
+    /// async fn run(database: &str) -> Result<Client, Error> {
+    ///     let client = Client::new(database, ClientConfig::default()).await?;
+    ///     let dialect = client.database_dialect().await?;
+    ///     if dialect == DatabaseDialect::GoogleStandardSql {
+    ///         return Ok(client);
+    ///     }
+    ///     client.close().await;
+    ///     // recreate the client binding the parameters with the detected dialect.
+    ///     let config = ClientConfig {
+    ///         dialect,
+    ///         ..Default::default()
+    ///     };
+    ///     Client::new(database, config).await
+    /// }
+    /// ```
+    pub async fn database_dialect(&self) -> Result<DatabaseDialect, Error> {
+        if let Some(dialect) = *self.detected_dialect.read() {
+            return Ok(dialect);
+        }
+        let mut tx = self.single().await?;
+        let dialect = information_schema::detect_dialect(&mut tx).await?;
+        *self.detected_dialect.write() = Some(dialect);
+        Ok(dialect)
+    }
+
     /// Close closes all the sessions gracefully.
     /// This method can be called only once.
     pub async fn close(self) {
@@ -175,7 +467,8 @@ impl Client {
     /// using read_only_transaction for a single read or query.
     pub async fn single_with_timestamp_bound(&self, tb: TimestampBound) -> Result<ReadOnlyTransaction, Error> {
         let session = self.get_session().await?;
-        let result = ReadOnlyTransaction::single(session, tb).await?;
+        let mut result = ReadOnlyTransaction::single(session, tb).await?;
+        result.dialect = self.dialect;
         Ok(result)
     }
 
@@ -226,10 +519,52 @@ impl Client {
         options: ReadOnlyTransactionOption,
     ) -> Result<ReadOnlyTransaction, Error> {
         let session = self.get_session().await?;
-        let result = ReadOnlyTransaction::begin(session, options.timestamp_bound, options.call_options).await?;
+        let mut result = ReadOnlyTransaction::begin(session, options.timestamp_bound, options.call_options).await?;
+        result.dialect = self.dialect;
         Ok(result)
     }
 
+    /// run_read_only begins a read-only transaction with the specified timestamp bound
+    /// and calls the function f with it. All the reads in f see the same snapshot of the database.
+    /// The session is released after f completes.
+    ///
+    /// Unlike read_write_transaction, the function f is called only once because a read-only
+    /// transaction never aborts.
+    /// ```
+    /// use google_cloud_spanner::client::{Client, Error};
+    /// use google_cloud_spanner::key::Key;
+    /// use google_cloud_spanner::reader::AsyncIterator;
+    /// use google_cloud_spanner::value::TimestampBound;
+    ///
+    /// async fn run(client: Client) -> Result<(), Error> {
+    ///     client.run_read_only(TimestampBound::strong_read(), |tx| {
+    ///         Box::pin(async move {
+    ///             let mut reader = tx.read("Guild", &["GuildID"], Key::new(&"pk1")).await?;
+    ///             while let Some(row) = reader.next().await? {
+    ///                 let guild_id = row.column_by_name::<String>("GuildID")?;
+    ///             }
+    ///             let mut reader = tx.read("User", &["UserID"], Key::new(&"pk2")).await?;
+    ///             while let Some(row) = reader.next().await? {
+    ///                 let user_id = row.column_by_name::<String>("UserID")?;
+    ///             }
+    ///             Ok(())
+    ///         })
+    ///     }).await
+    /// }
+    /// ```
+    pub async fn run_read_only<T, E, F>(&self, tb: TimestampBound, f: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: for<'tx> FnOnce(&'tx mut ReadOnlyTransaction) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'tx>>,
+    {
+        let options = ReadOnlyTransactionOption {
+            timestamp_bound: tb,
+            ..Default::default()
+        };
+        let mut tx = self.read_only_transaction_with_option(options).await?;
+        f(&mut tx).await
+    }
+
     /// batch_read_only_transaction returns a BatchReadOnlyTransaction that can be used
     /// for partitioned reads or queries from a snapshot of the database. This is
     /// useful in batch processing pipelines where one wants to divide the work of
@@ -248,7 +583,9 @@ impl Client {
         options: ReadOnlyTransactionOption,
     ) -> Result<BatchReadOnlyTransaction, Error> {
         let session = self.get_session().await?;
-        let result = BatchReadOnlyTransaction::begin(session, options.timestamp_bound, options.call_options).await?;
+        let mut result =
+            BatchReadOnlyTransaction::begin(session, options.timestamp_bound, options.call_options).await?;
+        result.dialect = self.dialect;
         Ok(result)
     }
 
@@ -273,31 +610,50 @@ impl Client {
     ///
     /// PartitionedUpdate returns an estimated count of the number of rows affected.
     /// The actual number of affected rows may be greater than the estimate.
+    ///
+    /// The statement is executed again only on the transient INTERNAL error, and not on ABORTED,
+    /// since the partitions already committed would be modified twice. The whole partitioned DML is bounded
+    /// by the timeout of the options, PARTITIONED_DML_TIMEOUT by default.
     pub async fn partitioned_update_with_option(
         &self,
         stmt: Statement,
         options: PartitionedUpdateOption,
     ) -> Result<i64, Error> {
-        let ro = TransactionRetrySetting::new(vec![Code::Aborted, Code::Internal]);
+        Ok(self.partitioned_update_row_count(stmt, options).await?.value())
+    }
+
+    /// partitioned_update_row_count is the same as partitioned_update_with_option, but returns the
+    /// typed row count. Cloud Spanner returns RowCount::LowerBound for the partitioned DML, it means
+    /// at least the number of rows were modified.
+    pub async fn partitioned_update_row_count(
+        &self,
+        stmt: Statement,
+        options: PartitionedUpdateOption,
+    ) -> Result<RowCount, Error> {
         let session = Some(self.get_session().await?);
 
         // reuse session
-        invoke_fn(
-            options.begin_options.cancel.clone(),
-            Some(ro),
+        partitioned_update_with_policy(
+            &options,
             |session| async {
+                let session = match session {
+                    Some(session) => session,
+                    None => self.get_session().await.map_err(|e| (Error::from(e), None))?,
+                };
                 let mut tx =
-                    match ReadWriteTransaction::begin_partitioned_dml(session.unwrap(), options.begin_options.clone())
-                        .await
-                    {
+                    match ReadWriteTransaction::begin_partitioned_dml(session, options.begin_options.clone()).await {
                         Ok(tx) => tx,
-                        Err(e) => return Err((Error::GRPC(e.status), Some(e.session))),
+                        Err(e) => {
+                            let (status, session) = e.into_retryable(self.default_begin_retry_session);
+                            return Err((Error::GRPC(status), session));
+                        }
                     };
+                tx.dialect = self.dialect;
                 let qo = match options.query_options.clone() {
                     Some(o) => o,
                     None => QueryOptions::default(),
                 };
-                tx.update_with_option(stmt.clone(), qo)
+                tx.update_row_count_with_option(stmt.clone(), qo)
                     .await
                     .map_err(|e| (Error::GRPC(e), tx.take_session()))
             },
@@ -315,8 +671,9 @@ impl Client {
     /// apply's default replay protection may require an additional RPC.  So this
     /// method may be appropriate for latency sensitive and/or high throughput blind
     /// writing.
-    pub async fn apply_at_least_once(&self, ms: Vec<Mutation>) -> Result<Option<Timestamp>, Error> {
-        self.apply_at_least_once_with_option(ms, CommitOptions::default()).await
+    pub async fn apply_at_least_once(&self, ms: Vec<Mutation>) -> Result<CommitResult, Error> {
+        self.apply_at_least_once_with_option(ms, self.default_commit_options.clone())
+            .await
     }
 
     /// apply_at_least_once may attempt to apply mutations more than once; if
@@ -327,13 +684,14 @@ impl Client {
     /// this option.  However, apply_at_least_once requires only a single RPC, whereas
     /// apply's default replay protection may require an additional RPC.  So this
     /// method may be appropriate for latency sensitive and/or high throughput blind
-    /// writing.
+    /// writing. See apply_deduplicated for the commit retried without applying the mutations twice.
     pub async fn apply_at_least_once_with_option(
         &self,
         ms: Vec<Mutation>,
         options: CommitOptions,
-    ) -> Result<Option<Timestamp>, Error> {
-        let ro = TransactionRetrySetting::default();
+    ) -> Result<CommitResult, Error> {
+        let options = self.supported_commit_options(options);
+        let ro = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &options.call_options);
         let mut session = self.get_session().await?;
 
         invoke_fn(
@@ -344,7 +702,51 @@ impl Client {
                     mode: Some(transaction_options::Mode::ReadWrite(transaction_options::ReadWrite::default())),
                 });
                 match commit(session, ms.clone(), tx, options.clone()).await {
-                    Ok(s) => Ok(s.commit_timestamp.map(|s| s.into())),
+                    Ok(s) => Ok(s.into()),
+                    Err(e) => Err((Error::GRPC(e), session)),
+                }
+            },
+            &mut session,
+        )
+        .await
+    }
+
+    /// apply_deduplicated applies the mutations like apply_at_least_once, but with the id of the transaction begun
+    /// explicitly instead of the single-use transaction. Every commit RPC retried by the
+    /// CommitOptions.call_options.retry, such as on UNAVAILABLE, carries the same transaction id, so that
+    /// Cloud Spanner recognizes the duplicate commit of the already committed transaction rather than applying
+    /// the mutations again. The ABORTED commit is retried with a new transaction because the aborted transaction
+    /// was never applied.
+    ///
+    /// This is the tradeoff between the two: apply_at_least_once requires only a single RPC, but the retried commit
+    /// may apply the mutations twice. apply_deduplicated requires the additional BeginTransaction RPC,
+    /// but the mutations are applied exactly once within the retry of the commit.
+    pub async fn apply_deduplicated(&self, ms: Vec<Mutation>, options: CommitOptions) -> Result<CommitResult, Error> {
+        let options = self.supported_commit_options(options);
+        let ro = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &options.call_options);
+        let mut session = self.get_session().await?;
+
+        invoke_fn(
+            options.call_options.cancel.clone(),
+            Some(ro),
+            |session| async {
+                let request = BeginTransactionRequest {
+                    session: session.session.name.to_string(),
+                    options: Some(read_write_transaction_options(ReadLockMode::Unspecified)),
+                    request_options: Transaction::create_request_options(options.call_options.priority),
+                };
+                let result = session
+                    .spanner_client
+                    .begin_transaction(request, options.call_options.cancel.clone(), options.call_options.retry.clone())
+                    .await;
+                let tx = match session.invalidate_if_needed(result).await {
+                    Ok(response) => response.into_inner(),
+                    Err(e) => return Err((Error::GRPC(e), session)),
+                };
+                // the retries of the commit RPC reuse the transaction id.
+                let tx_id = commit_request::Transaction::TransactionId(tx.id);
+                match commit(session, ms.clone(), tx_id, options.clone()).await {
+                    Ok(s) => Ok(s.into()),
                     Err(e) => Err((Error::GRPC(e), session)),
                 }
             },
@@ -365,21 +767,27 @@ impl Client {
     /// async fn run(client: Client) -> Result<(), Error>{
     ///     let m1 = delete("Guild", all_keys());
     ///     let m2 = insert("Guild", &["GuildID", "OwnerUserID", "UpdatedAt"], &[&"1", &"2", &CommitTimestamp::new()]);
-    ///     let commit_timestamp = client.apply(vec![m1,m2]).await?;
+    ///     let commit_result = client.apply(vec![m1,m2]).await?;
     ///     Ok(())
     /// }
     /// ```
-    pub async fn apply(&self, ms: Vec<Mutation>) -> Result<Option<Timestamp>, Error> {
-        self.apply_with_option(ms, ReadWriteTransactionOption::default()).await
+    pub async fn apply(&self, ms: Vec<Mutation>) -> Result<CommitResult, Error> {
+        self.apply_with_option(ms, self.default_read_write_transaction_option())
+            .await
     }
 
     /// Apply applies a list of mutations atomically to the database.
+    ///
+    /// The mutations are committed with the id of the read-write transaction, so the commit RPC retried by
+    /// the CommitOptions.call_options.retry, such as on UNAVAILABLE, is recognized by Cloud Spanner as the duplicate
+    /// commit of the same transaction and the mutations are applied exactly once. The ABORTED transaction
+    /// is retried with a new transaction because the aborted one was never applied.
     pub async fn apply_with_option(
         &self,
         ms: Vec<Mutation>,
         options: ReadWriteTransactionOption,
-    ) -> Result<Option<Timestamp>, Error> {
-        let result: Result<(Option<Timestamp>, ()), Error> = self
+    ) -> Result<CommitResult, Error> {
+        let result: Result<(CommitResult, ()), Error> = self
             .read_write_transaction_sync_with_option(
                 |tx, _cancel| {
                     tx.buffer_write(ms.to_vec());
@@ -391,6 +799,37 @@ impl Client {
         Ok(result?.0)
     }
 
+    /// apply_chunked splits the mutations into chunks whose estimated mutation count is
+    /// less than or equal to max_per_commit and applies each chunk with apply.
+    /// Returns the commit result of each chunk.
+    ///
+    /// Each chunk is committed in a separate transaction, so the mutations are NOT
+    /// applied atomically as a whole. If an error occurs, the chunks committed before
+    /// the error remain applied. This is intended for backfills and bulk loads where
+    /// the mutations are idempotent and can be re-applied.
+    /// See <https://cloud.google.com/spanner/quotas#limits_for_creating_reading_updating_and_deleting_data>
+    /// for the limit of the mutations per commit.
+    pub async fn apply_chunked(&self, ms: Vec<Mutation>, max_per_commit: usize) -> Result<Vec<CommitResult>, Error> {
+        let mut commit_results = vec![];
+        for chunk in mutation::chunk(ms, max_per_commit) {
+            commit_results.push(self.apply(chunk).await?);
+        }
+        Ok(commit_results)
+    }
+
+    /// apply_adaptive applies the mutations with apply, and splits the mutations in half to apply each half
+    /// again when the commit fails for exceeding the mutation limit, until every commit succeeds.
+    /// Returns the commit result of each commit in the order of the mutations.
+    /// This is the safety net for the pipelines that can't estimate the mutation count in advance,
+    /// and apply_chunked is cheaper if the mutation count is known.
+    ///
+    /// Like apply_chunked, the mutations are NOT applied atomically as a whole. If a single mutation exceeds
+    /// the limit, FAILED_PRECONDITION is returned with the table of the mutation, and the mutations committed
+    /// before the error remain applied.
+    pub async fn apply_adaptive(&self, ms: Vec<Mutation>) -> Result<Vec<CommitResult>, Error> {
+        apply_with_split(ms, |ms| self.apply(ms)).await
+    }
+
     /// ReadWriteTransaction executes a read-write transaction, with retries as
     /// necessary.
     ///
@@ -412,13 +851,13 @@ impl Client {
     /// ```
     /// use google_cloud_spanner::mutation::update;
     /// use google_cloud_spanner::key::{Key, all_keys};
-    /// use google_cloud_spanner::value::Timestamp;
+    /// use google_cloud_spanner::transaction_rw::CommitResult;
     /// use google_cloud_spanner::client::Error;
     /// use google_cloud_spanner::client::Client;
     /// use google_cloud_spanner::reader::AsyncIterator;
     ///
     /// #[tokio::main]
-    /// async fn run(client: Client) ->  Result<(Option<Timestamp>,()), Error>{
+    /// async fn run(client: Client) ->  Result<(CommitResult,()), Error>{
     ///     client.read_write_transaction(|tx, _| {
     ///         Box::pin(async move {
     ///             // The transaction function will be called again if the error code
@@ -441,7 +880,7 @@ impl Client {
     ///         })
     ///     }).await
     /// }
-    pub async fn read_write_transaction<'a, T, E, F>(&self, f: F) -> Result<(Option<Timestamp>, T), E>
+    pub async fn read_write_transaction<'a, T, E, F>(&self, f: F) -> Result<(CommitResult, T), E>
     where
         E: TryAs<Status> + From<SessionError> + From<Status>,
         F: for<'tx> Fn(
@@ -449,7 +888,7 @@ impl Client {
             Option<CancellationToken>,
         ) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'tx>>,
     {
-        self.read_write_transaction_with_option(f, ReadWriteTransactionOption::default())
+        self.read_write_transaction_with_option(f, self.default_read_write_transaction_option())
             .await
     }
 
@@ -475,7 +914,7 @@ impl Client {
         &'a self,
         f: F,
         options: ReadWriteTransactionOption,
-    ) -> Result<(Option<Timestamp>, T), E>
+    ) -> Result<(CommitResult, T), E>
     where
         E: TryAs<Status> + From<SessionError> + From<Status>,
         F: for<'tx> Fn(
@@ -483,20 +922,51 @@ impl Client {
             Option<CancellationToken>,
         ) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'tx>>,
     {
-        let (bo, co) = Client::split_read_write_transaction_option(options);
+        self.read_write_transaction_with_metrics(f, options).await.0
+    }
 
-        let ro = TransactionRetrySetting::default();
-        let session = Some(self.get_session().await?);
+    /// read_write_transaction_with_metrics is the same as read_write_transaction_with_option,
+    /// but also returns the elapsed time of each attempt and the time spent in the backoff
+    /// between the attempts. This is useful to quantify the cost of the aborted transactions.
+    pub async fn read_write_transaction_with_metrics<'a, T, E, F>(
+        &'a self,
+        f: F,
+        options: ReadWriteTransactionOption,
+    ) -> (Result<(CommitResult, T), E>, RetryMetrics)
+    where
+        E: TryAs<Status> + From<SessionError> + From<Status>,
+        F: for<'tx> Fn(
+            &'tx mut ReadWriteTransaction,
+            Option<CancellationToken>,
+        ) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'tx>>,
+    {
+        let escalation = options.pessimistic_after_aborts;
+        let priority_strategy = options.priority_strategy.clone();
+        let hook = options.hook.clone();
+        let begin_retry = options.begin_retry_session.unwrap_or(self.default_begin_retry_session);
+        let (bo, co, lock, seqno_base) = self.split_read_write_transaction_option(options);
+        let attempts = AtomicUsize::new(0);
+
+        let ro = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &bo);
+        let session = match self.get_session().await {
+            Ok(session) => Some(session),
+            Err(e) => return (Err(e.into()), RetryMetrics::default()),
+        };
         let cancel = bo.cancel.clone();
         // must reuse session
-        invoke_fn(
+        invoke_fn_with_metrics(
             cancel.clone(),
             Some(ro),
             |session| async {
                 let cancel = cancel.clone().map(|v| v.child_token());
-                let mut tx = self.create_read_write_transaction::<E>(session, bo.clone()).await?;
+                let aborts = attempts.fetch_add(1, Ordering::Relaxed);
+                let lock = read_lock_mode_for_attempt(lock, escalation, aborts);
+                let (bo, co) = options_for_attempt(priority_strategy.as_ref(), bo.clone(), co.clone(), aborts);
+                let mut tx = self
+                    .create_read_write_transaction::<E>(session, lock, seqno_base, bo, hook.clone(), begin_retry)
+                    .await?;
                 let result = f(&mut tx, cancel).await;
-                tx.finish(result, Some(co.clone())).await
+                tx.finish(result, Some(co)).await
             },
             session,
         )
@@ -524,7 +994,7 @@ impl Client {
     ///
     ///         // try to commit or rollback transaction.
     ///         match tx.end(result, None).await {
-    ///             Ok((_commit_timestamp, success)) => return Ok(success),
+    ///             Ok((_commit_result, success)) => return Ok(success),
     ///             Err(err) => retry.next(err).await? // check retry
     ///         }
     ///     }
@@ -547,9 +1017,35 @@ impl Client {
     /// ```
     pub async fn begin_read_write_transaction(&self) -> Result<ReadWriteTransaction, Error> {
         let session = self.get_session().await?;
-        ReadWriteTransaction::begin(session, ReadWriteTransactionOption::default().begin_options)
-            .await
-            .map_err(|e| e.status.into())
+        let mut tx = ReadWriteTransaction::begin_with_read_lock_mode(
+            session,
+            self.default_read_lock_mode,
+            ReadWriteTransactionOption::default().begin_options,
+        )
+        .await
+        .map_err(|e| e.status)?;
+        tx.set_default_commit_options(self.default_commit_options.clone());
+        tx.dialect = self.dialect;
+        Ok(tx)
+    }
+
+    /// checkout_session takes a session from the session pool to call the RPCs the high-level API doesn't wrap yet
+    /// with the raw gRPC client. The session is returned to the pool when the ManagedSession is dropped.
+    /// ```
+    /// use google_cloud_googleapis::spanner::v1::GetSessionRequest;
+    /// use google_cloud_spanner::client::{Client, Error};
+    ///
+    /// async fn run(client: Client) -> Result<(), Error> {
+    ///     let mut session = client.checkout_session().await?;
+    ///     let request = GetSessionRequest {
+    ///         name: session.session.name.to_string(),
+    ///     };
+    ///     let response = session.spanner_client.raw().get_session(request).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn checkout_session(&self) -> Result<ManagedSession, Error> {
+        Ok(self.get_session().await?)
     }
 
     /// Get open session count.
@@ -557,17 +1053,49 @@ impl Client {
         self.sessions.num_opened()
     }
 
+    /// check_connection executes `SELECT 1` with a pooled session without retry to probe the connectivity
+    /// to Cloud Spanner, such as for the readiness endpoint of the service. It returns the latency of the probe,
+    /// or DEADLINE_EXCEEDED if the probe including the session acquisition does not complete within the timeout.
+    /// ```
+    /// use std::time::Duration;
+    /// use google_cloud_spanner::client::{Client, Error};
+    ///
+    /// async fn healthz(client: Client) -> Result<(), Error> {
+    ///     let latency = client.check_connection(Duration::from_secs(1)).await?;
+    ///     tracing::info!("spanner is reachable in {}ms", latency.as_millis());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn check_connection(&self, timeout: Duration) -> Result<Duration, Error> {
+        probe(timeout, async {
+            let mut session = self.get_session().await?;
+            let request = ping_query_request(session.session.name.to_string());
+            let result = session
+                .spanner_client
+                .execute_sql(request, None, Some(RetrySetting::disabled()))
+                .await;
+            session.invalidate_if_needed(result).await?;
+            Ok(())
+        })
+        .await
+    }
+
     async fn read_write_transaction_sync_with_option<T, E>(
         &self,
         f: impl Fn(&mut ReadWriteTransaction, Option<CancellationToken>) -> Result<T, E>,
         options: ReadWriteTransactionOption,
-    ) -> Result<(Option<Timestamp>, T), E>
+    ) -> Result<(CommitResult, T), E>
     where
         E: TryAs<Status> + From<SessionError> + From<Status>,
     {
-        let (bo, co) = Client::split_read_write_transaction_option(options);
+        let escalation = options.pessimistic_after_aborts;
+        let priority_strategy = options.priority_strategy.clone();
+        let hook = options.hook.clone();
+        let begin_retry = options.begin_retry_session.unwrap_or(self.default_begin_retry_session);
+        let (bo, co, lock, seqno_base) = self.split_read_write_transaction_option(options);
+        let attempts = AtomicUsize::new(0);
 
-        let ro = TransactionRetrySetting::default();
+        let ro = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &bo);
         let session = Some(self.get_session().await?);
 
         // reuse session
@@ -577,9 +1105,14 @@ impl Client {
             Some(ro),
             |session| async {
                 let cancel = cancel.clone().map(|v| v.child_token());
-                let mut tx = self.create_read_write_transaction::<E>(session, bo.clone()).await?;
+                let aborts = attempts.fetch_add(1, Ordering::Relaxed);
+                let lock = read_lock_mode_for_attempt(lock, escalation, aborts);
+                let (bo, co) = options_for_attempt(priority_strategy.as_ref(), bo.clone(), co.clone(), aborts);
+                let mut tx = self
+                    .create_read_write_transaction::<E>(session, lock, seqno_base, bo, hook.clone(), begin_retry)
+                    .await?;
                 let result = f(&mut tx, cancel);
-                tx.finish(result, Some(co.clone())).await
+                tx.finish(result, Some(co)).await
             },
             session,
         )
@@ -589,21 +1122,680 @@ impl Client {
     async fn create_read_write_transaction<E>(
         &self,
         session: Option<ManagedSession>,
+        read_lock_mode: ReadLockMode,
+        seqno_base: i64,
         bo: CallOptions,
+        hook: Option<Arc<dyn TransactionHook>>,
+        begin_retry: BeginRetrySession,
     ) -> Result<ReadWriteTransaction, (E, Option<ManagedSession>)>
     where
         E: TryAs<Status> + From<SessionError> + From<Status>,
     {
-        ReadWriteTransaction::begin(session.unwrap(), bo)
+        // the session poisoned or replaced by the previous begin is discarded, and the new session is used.
+        let session = match session {
+            Some(session) => session,
+            None => self.get_session().await.map_err(|e| (E::from(e), None))?,
+        };
+        let mut tx = ReadWriteTransaction::begin_with_read_lock_mode(session, read_lock_mode, bo)
             .await
-            .map_err(|e| (E::from(e.status), Some(e.session)))
+            .map_err(|e| {
+                let (status, session) = e.into_retryable(begin_retry);
+                (E::from(status), session)
+            })?;
+        tx.set_seqno_base(seqno_base);
+        tx.dialect = self.dialect;
+        tx.attach_hook(hook);
+        Ok(tx)
     }
 
     async fn get_session(&self) -> Result<ManagedSession, SessionError> {
         self.sessions.get().await
     }
 
-    fn split_read_write_transaction_option(options: ReadWriteTransactionOption) -> (CallOptions, CommitOptions) {
-        (options.begin_options, options.commit_options)
+    fn default_read_write_transaction_option(&self) -> ReadWriteTransactionOption {
+        ReadWriteTransactionOption {
+            commit_options: self.default_commit_options.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn split_read_write_transaction_option(
+        &self,
+        options: ReadWriteTransactionOption,
+    ) -> (CallOptions, CommitOptions, ReadLockMode, i64) {
+        (
+            options.begin_options,
+            self.supported_commit_options(options.commit_options),
+            resolve_read_lock_mode(options.read_lock_mode, self.default_read_lock_mode),
+            options.seqno_base,
+        )
+    }
+
+    fn supported_commit_options(&self, options: CommitOptions) -> CommitOptions {
+        supported_commit_options(options, self.emulator, self.emulator_unsupported_options)
+    }
+}
+
+/// supported_commit_options removes the options the emulator does not support if the policy says so.
+fn supported_commit_options(
+    mut options: CommitOptions,
+    emulator: bool,
+    policy: EmulatorUnsupportedPolicy,
+) -> CommitOptions {
+    if emulator && policy == EmulatorUnsupportedPolicy::WarnAndIgnore && options.return_commit_stats {
+        tracing::warn!("return_commit_stats is ignored because the emulator does not support the commit stats");
+        options.return_commit_stats = false;
+    }
+    options
+}
+
+/// probe returns the elapsed time of the check, or DEADLINE_EXCEEDED if it does not complete within the timeout.
+async fn probe(timeout: Duration, check: impl Future<Output = Result<(), Error>>) -> Result<Duration, Error> {
+    let start = Instant::now();
+    match tokio::time::timeout(timeout, check).await {
+        Ok(result) => result.map(|_| start.elapsed()),
+        Err(_) => Err(Status::new(Code::DeadlineExceeded, "connection check timed out").into()),
+    }
+}
+
+/// apply_with_split commits the mutations, and commits each half of the mutations instead
+/// if the commit exceeds the mutation limit. The halves are committed in the order of the mutations.
+async fn apply_with_split<F, Fut>(ms: Vec<Mutation>, commit: F) -> Result<Vec<CommitResult>, Error>
+where
+    F: Fn(Vec<Mutation>) -> Fut,
+    Fut: Future<Output = Result<CommitResult, Error>>,
+{
+    let mut commit_results = vec![];
+    // the last one is committed first.
+    let mut pending = vec![ms];
+    while let Some(mut ms) = pending.pop() {
+        match commit(ms.clone()).await {
+            Ok(result) => commit_results.push(result),
+            Err(e) => {
+                let status = match e.try_as() {
+                    Some(status) if is_mutation_limit_exceeded(status) => status,
+                    _ => return Err(e),
+                };
+                if ms.len() < 2 {
+                    return Err(mutation_too_large(&ms, status).into());
+                }
+                tracing::debug!("split {} mutations exceeding the mutation limit", ms.len());
+                let second = ms.split_off(ms.len() / 2);
+                pending.push(second);
+                pending.push(ms);
+            }
+        }
+    }
+    Ok(commit_results)
+}
+
+/// partitioned_update_with_policy runs the partitioned DML retried only on the transient INTERNAL error
+/// and bounded by the timeout of the options.
+async fn partitioned_update_with_policy<V, F, Fut>(
+    options: &PartitionedUpdateOption,
+    f: F,
+    v: V,
+) -> Result<RowCount, Error>
+where
+    F: FnMut(V) -> Fut,
+    Fut: Future<Output = Result<RowCount, (Error, V)>>,
+{
+    let retry = TransactionRetrySetting::with_call_options(vec![Code::Internal], &options.begin_options);
+    let timeout = options.timeout.unwrap_or(PARTITIONED_DML_TIMEOUT);
+    let cancel = options.begin_options.cancel.clone();
+    match tokio::time::timeout(timeout, invoke_fn(cancel, Some(retry), f, v)).await {
+        Ok(result) => result,
+        Err(_) => Err(Error::GRPC(Status::new(
+            Code::DeadlineExceeded,
+            format!("partitioned DML timed out after {timeout:?}"),
+        ))),
+    }
+}
+
+/// is_mutation_limit_exceeded returns true if the commit failed for having too many mutations.
+fn is_mutation_limit_exceeded(status: &Status) -> bool {
+    matches!(status.code(), Code::InvalidArgument | Code::FailedPrecondition)
+        && status.message().contains("too many mutations")
+}
+
+/// mutation_too_large is the error of the single mutation that can't be split to fit into the mutation limit.
+fn mutation_too_large(ms: &[Mutation], status: &Status) -> Status {
+    let table = ms
+        .first()
+        .and_then(|m| m.operation.as_ref())
+        .map(mutation::table)
+        .unwrap_or_default();
+    Status::new(
+        Code::FailedPrecondition,
+        format!(
+            "a single mutation exceeds the mutation limit and can't be split: table={table}, {}",
+            status.message()
+        ),
+    )
+}
+
+/// resolve_read_lock_mode returns the read lock mode of the transaction, or the default of the client if it is unspecified.
+fn resolve_read_lock_mode(read_lock_mode: ReadLockMode, default: ReadLockMode) -> ReadLockMode {
+    match read_lock_mode {
+        ReadLockMode::Unspecified => default,
+        _ => read_lock_mode,
+    }
+}
+
+/// read_lock_mode_for_attempt returns the read lock mode of the attempt after the aborts.
+/// The optimistic read lock is escalated to the pessimistic one when the aborts reach the threshold.
+fn read_lock_mode_for_attempt(read_lock_mode: ReadLockMode, threshold: Option<usize>, aborts: usize) -> ReadLockMode {
+    match threshold {
+        Some(threshold) if read_lock_mode == ReadLockMode::Optimistic && aborts >= threshold => {
+            ReadLockMode::Pessimistic
+        }
+        _ => read_lock_mode,
+    }
+}
+
+/// options_for_attempt returns the options of the begin and the commit with the priority adjusted by the strategy.
+fn options_for_attempt(
+    strategy: Option<&PriorityStrategy>,
+    mut begin_options: CallOptions,
+    mut commit_options: CommitOptions,
+    aborts: usize,
+) -> (CallOptions, CommitOptions) {
+    if let Some(f) = strategy {
+        begin_options.priority = f(begin_options.priority, aborts);
+        commit_options.call_options.priority = f(commit_options.call_options.priority, aborts);
+    }
+    (begin_options, commit_options)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use parking_lot::Mutex;
+
+    use google_cloud_gax::conn::Environment;
+    use google_cloud_gax::grpc::{Code, Status};
+    use google_cloud_googleapis::spanner::v1::commit_request::Transaction;
+    use google_cloud_googleapis::spanner::v1::request_options::Priority;
+    use google_cloud_googleapis::spanner::v1::transaction_options::read_write::ReadLockMode;
+    use google_cloud_googleapis::spanner::v1::transaction_options::{Mode, ReadWrite};
+    use google_cloud_googleapis::spanner::v1::{
+        BeginTransactionRequest, CommitRequest, ExecuteSqlRequest, Mutation, RollbackRequest,
+    };
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::apiv1::spanner_client;
+    use crate::client::{
+        apply_with_split, options_for_attempt, partitioned_update_with_policy, probe, read_lock_mode_for_attempt,
+        resolve_read_lock_mode, supported_commit_options, Client, ClientConfig, EmulatorUnsupportedPolicy, Error,
+        PartitionedUpdateOption, ReadWriteTransactionOption,
+    };
+    use crate::mutation::{self, insert};
+    use crate::statement::Statement;
+    use crate::transaction::CallOptions;
+    use crate::transaction_rw::{
+        read_write_transaction_options, BeginRetrySession, CommitOptions, CommitResult, RowCount,
+    };
+
+    #[tokio::test]
+    async fn test_probe() {
+        // healthy
+        let latency = probe(Duration::from_secs(1), async { Ok(()) }).await.unwrap();
+        assert!(latency < Duration::from_secs(1));
+
+        // unreachable
+        let start = std::time::Instant::now();
+        let result = probe(Duration::from_millis(100), async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+        .await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        match result {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::DeadlineExceeded),
+            other => panic!("must be deadline exceeded: {other:?}"),
+        }
+
+        // failed
+        let result = probe(Duration::from_secs(1), async {
+            Err(Status::new(Code::Unavailable, "unavailable").into())
+        })
+        .await;
+        match result {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::Unavailable),
+            other => panic!("must be unavailable: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_read_lock_mode() {
+        let assert_mode = |option: ReadWriteTransactionOption, expected: ReadLockMode| {
+            let options = read_write_transaction_options(option.read_lock_mode);
+            assert_eq!(
+                options.mode,
+                Some(Mode::ReadWrite(ReadWrite {
+                    read_lock_mode: expected.into()
+                }))
+            );
+        };
+        assert_mode(ReadWriteTransactionOption::default(), ReadLockMode::Unspecified);
+        assert_mode(ReadWriteTransactionOption::optimistic(), ReadLockMode::Optimistic);
+        assert_mode(ReadWriteTransactionOption::pessimistic(), ReadLockMode::Pessimistic);
+    }
+
+    #[test]
+    fn test_resolve_read_lock_mode() {
+        // the default of the client applies to the transaction without the read lock mode.
+        let option = ReadWriteTransactionOption::default();
+        let lock = resolve_read_lock_mode(option.read_lock_mode, ReadLockMode::Optimistic);
+        assert_eq!(lock, ReadLockMode::Optimistic);
+        assert_eq!(
+            read_write_transaction_options(lock).mode,
+            Some(Mode::ReadWrite(ReadWrite {
+                read_lock_mode: ReadLockMode::Optimistic.into()
+            }))
+        );
+        // the read lock mode of the transaction overrides the default.
+        let option = ReadWriteTransactionOption::pessimistic();
+        assert_eq!(
+            resolve_read_lock_mode(option.read_lock_mode, ReadLockMode::Optimistic),
+            ReadLockMode::Pessimistic
+        );
+        assert_eq!(
+            resolve_read_lock_mode(ReadLockMode::Unspecified, ReadLockMode::Unspecified),
+            ReadLockMode::Unspecified
+        );
+        assert_eq!(ClientConfig::default().default_read_lock_mode, ReadLockMode::Unspecified);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_update_with_policy() {
+        let options = PartitionedUpdateOption::default();
+        let attempts = Mutex::new(0);
+        let run = |results: Vec<Result<RowCount, Status>>| {
+            *attempts.lock() = 0;
+            let results = Mutex::new(results.into_iter());
+            let options = &options;
+            let attempts = &attempts;
+            async move {
+                partitioned_update_with_policy(
+                    options,
+                    |_| {
+                        *attempts.lock() += 1;
+                        let result = results.lock().next().unwrap();
+                        async move { result.map_err(|e| (Error::GRPC(e), ())) }
+                    },
+                    (),
+                )
+                .await
+            }
+        };
+
+        // the lower bound of the partitioned DML is returned.
+        match run(vec![Ok(RowCount::LowerBound(100))]).await.unwrap() {
+            RowCount::LowerBound(count) => assert_eq!(count, 100),
+            RowCount::Exact(_) => panic!("must be lower bound"),
+        }
+
+        // the transient INTERNAL error is retried.
+        let transient = Status::new(Code::Internal, "stream terminated by RST_STREAM");
+        let result = run(vec![Err(transient), Ok(RowCount::LowerBound(3))]).await;
+        assert_eq!(result.unwrap().value(), 3);
+        assert_eq!(*attempts.lock(), 2);
+
+        // ABORTED is not retried.
+        let aborted = Status::new(Code::Aborted, "aborted");
+        match run(vec![Err(aborted), Ok(RowCount::LowerBound(3))]).await {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::Aborted),
+            _ => panic!("must be aborted"),
+        }
+        assert_eq!(*attempts.lock(), 1);
+
+        // bounded by the timeout.
+        let options = PartitionedUpdateOption {
+            timeout: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let result = partitioned_update_with_policy(
+            &options,
+            |_| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(RowCount::LowerBound(1))
+            },
+            (),
+        )
+        .await;
+        match result {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::DeadlineExceeded),
+            _ => panic!("must be deadline exceeded"),
+        }
+    }
+
+    const DATABASE: &str = "projects/local-project/instances/test-instance/databases/local-database";
+
+    async fn fake_client(fake: &FakeSpanner) -> Client {
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        Client::new(DATABASE, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_apply_deduplicated_retry() {
+        let fake = FakeSpanner::default();
+        let client = fake_client(&fake).await;
+        let ms = || vec![insert("User", &["UserId"], &[&"user"])];
+
+        // the commit retried on UNAVAILABLE carries the transaction begun once,
+        // so Cloud Spanner applies the mutations of the retried commit only once.
+        fake.fail("Commit", Status::new(Code::Unavailable, "unavailable"));
+        let result = client.apply_deduplicated(ms(), CommitOptions::default()).await.unwrap();
+        assert!(result.timestamp.is_some());
+        assert_eq!(fake.requests::<BeginTransactionRequest>("BeginTransaction").len(), 1);
+        let commits = fake.requests::<CommitRequest>("Commit");
+        assert_eq!(commits.len(), 2);
+        for (_, commit) in &commits {
+            assert_eq!(commit.transaction, Some(Transaction::TransactionId(b"transaction".to_vec())));
+            assert_eq!(commit.mutations, ms());
+        }
+
+        // apply_at_least_once retries the single-use transaction, which has no identity to deduplicate.
+        fake.fail("Commit", Status::new(Code::Unavailable, "unavailable"));
+        client.apply_at_least_once(ms()).await.unwrap();
+        assert_eq!(fake.requests::<BeginTransactionRequest>("BeginTransaction").len(), 1);
+        let commits = fake.requests::<CommitRequest>("Commit");
+        assert_eq!(commits.len(), 4);
+        assert!(commits[2..]
+            .iter()
+            .all(|(_, commit)| matches!(commit.transaction, Some(Transaction::SingleUseTransaction(_)))));
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_check_connection() {
+        // healthy
+        let fake = FakeSpanner::default();
+        let client = fake_client(&fake).await;
+        let latency = client.check_connection(Duration::from_secs(1)).await.unwrap();
+        assert!(latency < Duration::from_secs(1));
+        let probes = fake.requests::<ExecuteSqlRequest>("ExecuteSql");
+        assert_eq!(probes.len(), 1);
+        assert_eq!(probes[0].1.sql, "SELECT 1");
+        client.close().await;
+
+        // closed: the client connects the channels on creation, so it fails before the check.
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config = ClientConfig {
+            environment: Environment::Emulator(closed.to_string()),
+            ..Default::default()
+        };
+        assert!(Client::new(DATABASE, config).await.is_err());
+
+        // unreachable: the port accepts the connection but never answers.
+        let unresponsive = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = ClientConfig {
+            environment: Environment::Emulator(unresponsive.local_addr().unwrap().to_string()),
+            ..Default::default()
+        };
+        // the client starts without the sessions, which would wait for the answer.
+        config.session_config.min_opened = 0;
+        let client = Client::new(DATABASE, config).await.unwrap();
+        let start = std::time::Instant::now();
+        let result = client.check_connection(Duration::from_millis(500)).await;
+        assert!(start.elapsed() < Duration::from_secs(2), "elapsed = {:?}", start.elapsed());
+        match result {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::DeadlineExceeded),
+            other => panic!("must be deadline exceeded: {other:?}"),
+        }
+        // the client is dropped without close, which would wait for the session creation never answered.
+    }
+
+    #[tokio::test]
+    async fn test_read_write_transaction_dml_retry() {
+        let fake = FakeSpanner::default();
+        let client = fake_client(&fake).await;
+
+        // the UNAVAILABLE of the DML statement is not re-sent within the attempt,
+        // but retries the whole transaction since the statement may have been applied.
+        fake.fail("ExecuteSql", Status::new(Code::Unavailable, "unavailable"));
+        let calls = AtomicUsize::new(0);
+        let (result, _) = client
+            .read_write_transaction(|tx, _| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    tx.update(Statement::new("UPDATE User SET Name = 'name' WHERE UserId = 'user'"))
+                        .await?;
+                    Ok::<(), Error>(())
+                })
+            })
+            .await
+            .unwrap();
+        assert!(result.timestamp.is_some());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fake.requests::<BeginTransactionRequest>("BeginTransaction").len(), 2);
+        // one statement failed in the first attempt and one succeeded in the second.
+        assert_eq!(fake.requests::<ExecuteSqlRequest>("ExecuteSql").len(), 2);
+        // the first attempt is rolled back and the second one is committed.
+        assert_eq!(fake.requests::<RollbackRequest>("Rollback").len(), 1);
+        assert_eq!(fake.requests::<CommitRequest>("Commit").len(), 1);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_begin_retry_session() {
+        let fake = FakeSpanner::default();
+        let client = fake_client(&fake).await;
+        let begin = |policy: BeginRetrySession| {
+            fake.fail("BeginTransaction", Status::new(Code::Aborted, "aborted"));
+            let options = ReadWriteTransactionOption {
+                begin_retry_session: Some(policy),
+                ..Default::default()
+            };
+            client.read_write_transaction_with_option(|_, _| Box::pin(async { Ok::<(), Error>(()) }), options)
+        };
+        let sessions = || {
+            fake.requests::<BeginTransactionRequest>("BeginTransaction")
+                .into_iter()
+                .map(|(_, r)| r.session)
+                .collect::<Vec<String>>()
+        };
+
+        // the aborted begin is retried on the same session.
+        begin(BeginRetrySession::Reuse).await.unwrap();
+        let reused = sessions();
+        assert_eq!(reused.len(), 2);
+        assert_eq!(reused[0], reused[1]);
+
+        // the aborted begin is retried on the session taken from the pool.
+        begin(BeginRetrySession::Replace).await.unwrap();
+        let replaced = sessions()[2..].to_vec();
+        assert_eq!(replaced.len(), 2);
+        assert_ne!(replaced[0], replaced[1]);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_apply_with_split() {
+        // the commit fails if the mutations exceed the limit of 4.
+        let commits = Mutex::new(vec![]);
+        let commit = |ms: Vec<Mutation>| {
+            let count: usize = ms.iter().map(mutation::mutation_count).sum();
+            commits.lock().push(count);
+            async move {
+                if count > 4 {
+                    return Err(
+                        Status::new(Code::InvalidArgument, "The transaction contains too many mutations.").into(),
+                    );
+                }
+                Ok(CommitResult {
+                    mutation_count: Some(count as i64),
+                    ..Default::default()
+                })
+            }
+        };
+        let ms: Vec<Mutation> = (0..10_i64).map(|i| insert("Guild", &["GuildId"], &[&i])).collect();
+        let results = apply_with_split(ms, commit).await.unwrap();
+        let counts: Vec<i64> = results.iter().map(|r| r.mutation_count.unwrap()).collect();
+        assert_eq!(counts, vec![2, 3, 2, 3]);
+        assert_eq!(*commits.lock(), vec![10, 5, 2, 3, 5, 2, 3]);
+
+        // the single mutation exceeding the limit can't be split.
+        let ms = vec![
+            insert("Guild", &["GuildId"], &[&1]),
+            insert("User", &["A", "B", "C", "D", "E"], &[&1, &2, &3, &4, &5]),
+        ];
+        match apply_with_split(ms, commit).await {
+            Err(Error::GRPC(status)) => {
+                assert_eq!(status.code(), Code::FailedPrecondition);
+                assert!(status.message().contains("table=User"), "{}", status.message());
+            }
+            _ => panic!("must be failed precondition"),
+        }
+
+        // the other error is returned without the split.
+        let failed = |_ms: Vec<Mutation>| async { Err(Status::new(Code::PermissionDenied, "denied").into()) };
+        let ms: Vec<Mutation> = (0..10_i64).map(|i| insert("Guild", &["GuildId"], &[&i])).collect();
+        match apply_with_split(ms, failed).await {
+            Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::PermissionDenied),
+            _ => panic!("must be permission denied"),
+        }
+    }
+
+    #[test]
+    fn test_supported_commit_options() {
+        let options = CommitOptions {
+            return_commit_stats: true,
+            transaction_tag: Some("tag".to_string()),
+            ..Default::default()
+        };
+
+        // the commit stats are ignored by the emulator-mode client with WarnAndIgnore.
+        let supported = supported_commit_options(options.clone(), true, EmulatorUnsupportedPolicy::WarnAndIgnore);
+        assert!(!supported.return_commit_stats);
+        assert_eq!(supported.transaction_tag.as_deref(), Some("tag"));
+
+        // sent as is to the emulator by default.
+        let policy = ClientConfig::default().emulator_unsupported_options;
+        assert_eq!(policy, EmulatorUnsupportedPolicy::Send);
+        let supported = supported_commit_options(options.clone(), true, policy);
+        assert!(supported.return_commit_stats);
+
+        // sent as is to Cloud Spanner regardless of the policy.
+        let supported = supported_commit_options(options, false, EmulatorUnsupportedPolicy::WarnAndIgnore);
+        assert!(supported.return_commit_stats);
+    }
+
+    #[test]
+    fn test_read_lock_mode_for_attempt() {
+        let option = ReadWriteTransactionOption {
+            pessimistic_after_aborts: Some(2),
+            ..ReadWriteTransactionOption::optimistic()
+        };
+        let modes: Vec<ReadLockMode> = (0..4)
+            .map(|aborts| read_lock_mode_for_attempt(option.read_lock_mode, option.pessimistic_after_aborts, aborts))
+            .collect();
+        assert_eq!(
+            modes,
+            vec![
+                ReadLockMode::Optimistic,
+                ReadLockMode::Optimistic,
+                ReadLockMode::Pessimistic,
+                ReadLockMode::Pessimistic
+            ]
+        );
+        // the re-begun transaction uses the pessimistic read lock
+        let options = read_write_transaction_options(modes[2]);
+        assert_eq!(
+            options.mode,
+            Some(Mode::ReadWrite(ReadWrite {
+                read_lock_mode: ReadLockMode::Pessimistic.into()
+            }))
+        );
+
+        // never escalated by default
+        let optimistic = ReadWriteTransactionOption::optimistic();
+        assert_eq!(
+            read_lock_mode_for_attempt(optimistic.read_lock_mode, optimistic.pessimistic_after_aborts, 100),
+            ReadLockMode::Optimistic
+        );
+        // only the optimistic read lock is escalated
+        assert_eq!(
+            read_lock_mode_for_attempt(ReadLockMode::Unspecified, Some(0), 1),
+            ReadLockMode::Unspecified
+        );
+    }
+
+    #[test]
+    fn test_options_for_attempt() {
+        let option = ReadWriteTransactionOption {
+            begin_options: CallOptions {
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // the priority is unchanged by default
+        let (bo, co) = options_for_attempt(None, option.begin_options.clone(), option.commit_options.clone(), 5);
+        assert_eq!(bo.priority, Some(Priority::High));
+        assert_eq!(co.call_options.priority, None);
+
+        let option = option.with_priority_strategy(|priority, aborts| match aborts {
+            0 | 1 => priority,
+            2 => Some(Priority::Medium),
+            _ => Some(Priority::Low),
+        });
+        let priorities: Vec<(Option<Priority>, Option<Priority>)> = (0..4)
+            .map(|aborts| {
+                let (bo, co) = options_for_attempt(
+                    option.priority_strategy.as_ref(),
+                    option.begin_options.clone(),
+                    option.commit_options.clone(),
+                    aborts,
+                );
+                (bo.priority, co.call_options.priority)
+            })
+            .collect();
+        assert_eq!(
+            priorities,
+            vec![
+                (Some(Priority::High), None),
+                (Some(Priority::High), None),
+                (Some(Priority::Medium), Some(Priority::Medium)),
+                (Some(Priority::Low), Some(Priority::Low)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = ClientConfig::default()
+            .with_session_get_timeout(Duration::from_secs(3))
+            .with_session_acquire_timeout(Duration::from_secs(10))
+            .with_idle_timeout(Duration::from_secs(600))
+            .with_session_alive_trust_duration(Duration::from_secs(1800))
+            .with_refresh_interval(Duration::from_secs(60))
+            .with_commit_retry_backoff_base(Duration::from_millis(10), Duration::from_secs(5));
+        assert_eq!(config.session_config.session_get_timeout, Duration::from_secs(3));
+        assert_eq!(config.session_config.acquire_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.session_config.idle_timeout, Duration::from_secs(600));
+        assert_eq!(config.session_config.session_alive_trust_duration, Duration::from_secs(1800));
+        assert_eq!(config.session_config.refresh_interval, Duration::from_secs(60));
+
+        let retry = config.default_commit_options.call_options.retry.unwrap();
+        assert_eq!(retry.from_millis, 10);
+        assert_eq!(retry.max_delay, Some(Duration::from_secs(5)));
+        let commit_setting = spanner_client::default_setting();
+        assert_eq!(retry.codes, commit_setting.codes);
+        assert_eq!(retry.take, commit_setting.take);
+
+        // the other settings are kept.
+        let default = ClientConfig::default();
+        assert_eq!(config.session_config.max_opened, default.session_config.max_opened);
+        assert!(default.default_commit_options.call_options.retry.is_none());
     }
 }
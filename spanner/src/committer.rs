@@ -0,0 +1,247 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
+
+use google_cloud_googleapis::spanner::v1::Mutation;
+
+use crate::client::{Client, Error};
+use crate::transaction_rw::CommitResult;
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum BatchError {
+    /// Commit is the error of the commit shared by all the producers in the batch.
+    #[error("failed to commit the batch: {0}")]
+    Commit(Arc<Error>),
+    #[error("committer is closed")]
+    Closed,
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchingCommitterConfig {
+    /// window is the maximum time to wait for the following mutations after the first mutation of the batch arrives.
+    pub window: Duration,
+    /// max_mutations is the maximum number of the mutations committed at once.
+    /// The mutations submitted by one apply are never split, so that a batch exceeds this value
+    /// only when the apply has more mutations than this value.
+    pub max_mutations: usize,
+}
+
+impl Default for BatchingCommitterConfig {
+    fn default() -> Self {
+        BatchingCommitterConfig {
+            window: Duration::from_millis(10),
+            max_mutations: 1000,
+        }
+    }
+}
+
+struct Request {
+    mutations: Vec<Mutation>,
+    sender: oneshot::Sender<Result<CommitResult, BatchError>>,
+}
+
+/// BatchingCommitter collects the mutations applied by the concurrent producers within the window
+/// and commits them with one Client::apply_at_least_once, so that the commit RPC is shared by the producers.
+/// Every producer in the batch receives the same commit result.
+/// Like apply_at_least_once, the mutations may be applied more than once, so use this only for the idempotent blind writes.
+/// ```
+/// use google_cloud_spanner::client::Client;
+/// use google_cloud_spanner::committer::{BatchError, BatchingCommitter, BatchingCommitterConfig};
+/// use google_cloud_spanner::mutation::insert_or_update;
+///
+/// async fn run(client: Client) -> Result<(), BatchError> {
+///     let committer = BatchingCommitter::new(client, BatchingCommitterConfig::default());
+///     let ms = vec![insert_or_update("Guild", &["GuildId", "OwnerUserID"], &[&"guild1", &"user1"])];
+///     let result = committer.apply(ms).await?;
+///     committer.close().await;
+///     Ok(())
+/// }
+/// ```
+pub struct BatchingCommitter {
+    sender: UnboundedSender<Request>,
+    task: JoinHandle<()>,
+}
+
+impl BatchingCommitter {
+    pub fn new(client: Client, config: BatchingCommitterConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            let mut batcher = Batcher::new(receiver, config);
+            while let Some(requests) = batcher.next().await {
+                let mutations = requests.iter().flat_map(|r| r.mutations.clone()).collect();
+                tracing::trace!("commit batch producers={}", requests.len());
+                let result = client
+                    .apply_at_least_once(mutations)
+                    .await
+                    .map_err(|e| BatchError::Commit(Arc::new(e)));
+                for request in requests {
+                    let _ = request.sender.send(result.clone());
+                }
+            }
+        });
+        BatchingCommitter { sender, task }
+    }
+
+    /// apply submits the mutations to the next batch and waits for the commit of the batch.
+    pub async fn apply(&self, mutations: Vec<Mutation>) -> Result<CommitResult, BatchError> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Request { mutations, sender })
+            .map_err(|_| BatchError::Closed)?;
+        receiver.await.map_err(|_| BatchError::Closed)?
+    }
+
+    /// close commits the mutations already submitted and stops the committer.
+    pub async fn close(self) {
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+/// Batcher groups the requests by the window and the mutation count.
+struct Batcher {
+    receiver: UnboundedReceiver<Request>,
+    config: BatchingCommitterConfig,
+    // the request that did not fit into the previous batch.
+    pending: Option<Request>,
+}
+
+impl Batcher {
+    fn new(receiver: UnboundedReceiver<Request>, config: BatchingCommitterConfig) -> Self {
+        Batcher {
+            receiver,
+            config,
+            pending: None,
+        }
+    }
+
+    /// next waits for the first request and collects the following requests until the window elapses
+    /// or the mutations reach max_mutations. Returns None if all the senders are dropped.
+    async fn next(&mut self) -> Option<Vec<Request>> {
+        let first = match self.pending.take() {
+            Some(request) => request,
+            None => self.receiver.recv().await?,
+        };
+        let deadline = Instant::now() + self.config.window;
+        let mut mutation_count = first.mutations.len();
+        let mut requests = vec![first];
+        while mutation_count < self.config.max_mutations {
+            let request = match timeout_at(deadline, self.receiver.recv()).await {
+                Ok(Some(request)) => request,
+                _ => break,
+            };
+            if mutation_count + request.mutations.len() > self.config.max_mutations {
+                self.pending = Some(request);
+                break;
+            }
+            mutation_count += request.mutations.len();
+            requests.push(request);
+        }
+        Some(requests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::{mpsc, oneshot};
+
+    use google_cloud_gax::conn::Environment;
+    use google_cloud_googleapis::spanner::v1::{CommitRequest, Mutation};
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::client::{Client, ClientConfig};
+    use crate::committer::{Batcher, BatchingCommitter, BatchingCommitterConfig, Request};
+    use crate::mutation::insert;
+
+    fn request(mutation_count: usize) -> Request {
+        Request {
+            mutations: vec![Mutation::default(); mutation_count],
+            sender: oneshot::channel().0,
+        }
+    }
+
+    fn sizes(requests: &[Request]) -> Vec<usize> {
+        requests.iter().map(|r| r.mutations.len()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_batcher() {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let config = BatchingCommitterConfig {
+            window: Duration::from_millis(100),
+            max_mutations: 5,
+        };
+        let mut batcher = Batcher::new(receiver, config);
+
+        // within the window
+        for count in [1, 2, 1] {
+            sender.send(request(count)).unwrap();
+        }
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![1, 2, 1]);
+
+        // bounded by the mutation count
+        for count in [3, 2, 4, 6] {
+            sender.send(request(count)).unwrap();
+        }
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![3, 2]);
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![4]);
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![6]);
+
+        // bounded by the window
+        sender.send(request(1)).unwrap();
+        let delayed = sender.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            delayed.send(request(2)).unwrap();
+        });
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![1]);
+        assert_eq!(sizes(&batcher.next().await.unwrap()), vec![2]);
+
+        drop(sender);
+        assert!(batcher.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_committer_shares_commit() {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let database = "projects/local-project/instances/test-instance/databases/local-database";
+        let client = Client::new(database, config).await.unwrap();
+        let committer = BatchingCommitter::new(
+            client.clone(),
+            BatchingCommitterConfig {
+                window: Duration::from_millis(100),
+                ..Default::default()
+            },
+        );
+
+        // the producers submitting within the window share one commit.
+        let ms = |user_id: &str| vec![insert("User", &["UserId"], &[&user_id])];
+        let (r1, r2, r3) = tokio::join!(
+            committer.apply(ms("user1")),
+            committer.apply(ms("user2")),
+            committer.apply(ms("user3"))
+        );
+        for result in [r1, r2, r3] {
+            assert!(result.unwrap().timestamp.is_some());
+        }
+
+        let commits = fake.requests::<CommitRequest>("Commit");
+        assert_eq!(commits.len(), 1);
+        let mut mutations = commits[0].1.mutations.clone();
+        mutations.sort_by_key(|m| format!("{m:?}"));
+        assert_eq!(mutations, [ms("user1"), ms("user2"), ms("user3")].concat());
+
+        committer.close().await;
+        client.close().await;
+    }
+}
@@ -0,0 +1,253 @@
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+
+use crate::client::Error;
+use crate::mutation::CommitTimestampValidator;
+use crate::reader::AsyncIterator;
+use crate::row::Row;
+use crate::statement::Statement;
+use crate::transaction::Transaction;
+
+/// TableInfo is a table defined in the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableInfo {
+    pub name: String,
+    /// The name of the parent table if the table is interleaved.
+    pub parent_table_name: Option<String>,
+}
+
+/// ColumnInfo is a column of the table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// The type of the column in the dialect of the database. e.g. `STRING(36)`, `ARRAY<INT64>`
+    pub spanner_type: String,
+    pub is_nullable: bool,
+    pub ordinal_position: i64,
+}
+
+/// IndexInfo is an index of the table. The primary key is listed as `PRIMARY_KEY` index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexInfo {
+    pub name: String,
+    pub index_type: String,
+    pub is_unique: bool,
+    pub is_null_filtered: bool,
+}
+
+/// InformationSchema provides typed queries to the INFORMATION_SCHEMA of the database.
+/// ```
+/// use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+/// use google_cloud_spanner::client::{Client, Error};
+/// use google_cloud_spanner::information_schema::InformationSchema;
+///
+/// async fn run(client: Client) -> Result<(), Error> {
+///     let schema = InformationSchema::new(DatabaseDialect::GoogleStandardSql);
+///     let mut tx = client.read_only_transaction().await?;
+///     for table in schema.tables(&mut tx).await? {
+///         let columns = schema.columns(&mut tx, &table.name).await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct InformationSchema {
+    dialect: DatabaseDialect,
+}
+
+impl InformationSchema {
+    pub fn new(dialect: DatabaseDialect) -> Self {
+        InformationSchema { dialect }
+    }
+
+    /// tables lists the user tables of the database.
+    pub async fn tables(&self, tx: &mut Transaction) -> Result<Vec<TableInfo>, Error> {
+        let stmt = match self.dialect {
+            DatabaseDialect::Postgresql => Statement::new(
+                "SELECT table_name, parent_table_name FROM information_schema.tables \
+                WHERE table_schema = 'public' ORDER BY table_name",
+            ),
+            _ => Statement::new(
+                "SELECT TABLE_NAME, PARENT_TABLE_NAME FROM INFORMATION_SCHEMA.TABLES \
+                WHERE TABLE_CATALOG = '' AND TABLE_SCHEMA = '' ORDER BY TABLE_NAME",
+            ),
+        };
+        query(tx, stmt, |row| {
+            Ok(TableInfo {
+                name: row.column(0)?,
+                parent_table_name: row.column(1)?,
+            })
+        })
+        .await
+    }
+
+    /// columns lists the columns of the table in the order of the definition.
+    pub async fn columns(&self, tx: &mut Transaction, table: &str) -> Result<Vec<ColumnInfo>, Error> {
+        let stmt = match self.dialect {
+            DatabaseDialect::Postgresql => self.table_statement(
+                "SELECT column_name, spanner_type, is_nullable = 'YES', ordinal_position \
+                FROM information_schema.columns \
+                WHERE table_schema = 'public' AND table_name = $1 ORDER BY ordinal_position",
+                table,
+            ),
+            _ => self.table_statement(
+                "SELECT COLUMN_NAME, SPANNER_TYPE, IS_NULLABLE = 'YES', ORDINAL_POSITION \
+                FROM INFORMATION_SCHEMA.COLUMNS \
+                WHERE TABLE_CATALOG = '' AND TABLE_SCHEMA = '' AND TABLE_NAME = @p1 ORDER BY ORDINAL_POSITION",
+                table,
+            ),
+        };
+        query(tx, stmt, |row| {
+            Ok(ColumnInfo {
+                name: row.column(0)?,
+                spanner_type: row.column(1)?,
+                is_nullable: row.column(2)?,
+                ordinal_position: row.column(3)?,
+            })
+        })
+        .await
+    }
+
+    /// indexes lists the indexes of the table including the primary key.
+    pub async fn indexes(&self, tx: &mut Transaction, table: &str) -> Result<Vec<IndexInfo>, Error> {
+        let stmt = match self.dialect {
+            DatabaseDialect::Postgresql => self.table_statement(
+                "SELECT index_name, index_type, is_unique = 'YES', is_null_filtered = 'YES' \
+                FROM information_schema.indexes \
+                WHERE table_schema = 'public' AND table_name = $1 ORDER BY index_name",
+                table,
+            ),
+            _ => self.table_statement(
+                "SELECT INDEX_NAME, INDEX_TYPE, IS_UNIQUE, IS_NULL_FILTERED \
+                FROM INFORMATION_SCHEMA.INDEXES \
+                WHERE TABLE_CATALOG = '' AND TABLE_SCHEMA = '' AND TABLE_NAME = @p1 ORDER BY INDEX_NAME",
+                table,
+            ),
+        };
+        query(tx, stmt, |row| {
+            Ok(IndexInfo {
+                name: row.column(0)?,
+                index_type: row.column(1)?,
+                is_unique: row.column(2)?,
+                is_null_filtered: row.column(3)?,
+            })
+        })
+        .await
+    }
+
+    /// commit_timestamp_validator loads the columns with the `allow_commit_timestamp=true` option of all the tables.
+    pub async fn commit_timestamp_validator(&self, tx: &mut Transaction) -> Result<CommitTimestampValidator, Error> {
+        let stmt = match self.dialect {
+            DatabaseDialect::Postgresql => Statement::new(
+                "SELECT table_name, column_name FROM information_schema.column_options \
+                WHERE table_schema = 'public' AND option_name = 'allow_commit_timestamp' AND option_value = 'TRUE'",
+            ),
+            _ => Statement::new(
+                "SELECT TABLE_NAME, COLUMN_NAME FROM INFORMATION_SCHEMA.COLUMN_OPTIONS \
+                WHERE TABLE_CATALOG = '' AND TABLE_SCHEMA = '' \
+                AND OPTION_NAME = 'allow_commit_timestamp' AND OPTION_VALUE = 'TRUE'",
+            ),
+        };
+        let columns = query(tx, stmt, |row| Ok((row.column::<String>(0)?, row.column::<String>(1)?))).await?;
+        Ok(columns
+            .iter()
+            .fold(CommitTimestampValidator::default(), |validator, (table, column)| {
+                validator.allow(table, &[column])
+            }))
+    }
+
+    /// table_statement binds the table name as `p1`, which is referred as `@p1` in GoogleSQL and `$1` in PostgreSQL.
+    fn table_statement(&self, sql: &str, table: &str) -> Statement {
+        let mut stmt = Statement::new(sql);
+        stmt.add_param("p1", &table);
+        stmt
+    }
+}
+
+/// detect_dialect returns the dialect of the database from the `database_dialect` option of the database.
+/// The statement is valid in both GoogleSQL and PostgreSQL, because PostgreSQL folds the unquoted names to lowercase.
+pub async fn detect_dialect(tx: &mut Transaction) -> Result<DatabaseDialect, Error> {
+    let stmt = Statement::new(
+        "SELECT OPTION_VALUE FROM INFORMATION_SCHEMA.DATABASE_OPTIONS WHERE OPTION_NAME = 'database_dialect'",
+    );
+    let values = query(tx, stmt, |row| row.column::<String>(0)).await?;
+    Ok(values
+        .first()
+        .map(|v| parse_dialect(v))
+        .unwrap_or(DatabaseDialect::GoogleStandardSql))
+}
+
+/// parse_dialect parses the value of the `database_dialect` option such as `POSTGRESQL`.
+fn parse_dialect(value: &str) -> DatabaseDialect {
+    DatabaseDialect::from_str_name(value).unwrap_or(DatabaseDialect::Unspecified)
+}
+
+async fn query<T>(
+    tx: &mut Transaction,
+    stmt: Statement,
+    f: impl Fn(&Row) -> Result<T, crate::row::Error>,
+) -> Result<Vec<T>, Error> {
+    let mut iter = tx.query(stmt).await?;
+    let mut result = vec![];
+    while let Some(row) = iter.next().await? {
+        result.push(f(&row)?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::value::Kind;
+    use prost_types::Value;
+
+    use google_cloud_gax::conn::Environment;
+    use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+    use google_cloud_googleapis::spanner::v1::struct_type::Field;
+    use google_cloud_googleapis::spanner::v1::{ExecuteSqlRequest, PartialResultSet, ResultSetMetadata, StructType};
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::client::{Client, ClientConfig};
+    use crate::information_schema::parse_dialect;
+
+    #[test]
+    fn test_parse_dialect() {
+        // the option values of the GoogleSQL and the PostgreSQL database
+        assert_eq!(parse_dialect("GOOGLE_STANDARD_SQL"), DatabaseDialect::GoogleStandardSql);
+        assert_eq!(parse_dialect("POSTGRESQL"), DatabaseDialect::Postgresql);
+        assert_eq!(parse_dialect("UNKNOWN"), DatabaseDialect::Unspecified);
+    }
+
+    #[tokio::test]
+    async fn test_detect_dialect_postgresql() {
+        let fake = FakeSpanner::default();
+        // the option row of the PostgreSQL database
+        fake.set_results(vec![PartialResultSet {
+            metadata: Some(ResultSetMetadata {
+                row_type: Some(StructType {
+                    fields: vec![Field {
+                        name: "OPTION_VALUE".to_string(),
+                        r#type: None,
+                    }],
+                }),
+                ..Default::default()
+            }),
+            values: vec![Value {
+                kind: Some(Kind::StringValue("POSTGRESQL".to_string())),
+            }],
+            ..Default::default()
+        }]);
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let database = "projects/local-project/instances/test-instance/databases/local-database";
+        let client = Client::new(database, config).await.unwrap();
+        assert_eq!(client.database_dialect().await.unwrap(), DatabaseDialect::Postgresql);
+        // the detected dialect is cached.
+        assert_eq!(client.database_dialect().await.unwrap(), DatabaseDialect::Postgresql);
+
+        let queries = fake.requests::<ExecuteSqlRequest>("ExecuteStreamingSql");
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].1.sql.contains("INFORMATION_SCHEMA.DATABASE_OPTIONS"));
+        client.close().await;
+    }
+}
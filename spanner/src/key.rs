@@ -1,8 +1,13 @@
+use std::cmp::Ordering;
+
+use base64::prelude::*;
+use prost_types::value::Kind;
 use prost_types::{ListValue, Value};
 
 use google_cloud_googleapis::spanner::v1::key_range::{EndKeyType, StartKeyType};
 use google_cloud_googleapis::spanner::v1::KeyRange as InternalKeyRange;
 use google_cloud_googleapis::spanner::v1::KeySet as InternalKeySet;
+use google_cloud_googleapis::spanner::v1::TypeCode;
 
 use crate::statement::ToKind;
 
@@ -304,6 +309,113 @@ impl From<Vec<Key>> for KeySet {
     }
 }
 
+impl KeySet {
+    /// sorted returns the key set with the keys sorted, so that the concurrent transactions acquire the locks
+    /// of the keys in the same order to reduce the deadlocks and the aborts. key_types are the types of the
+    /// key columns in order, and the composite keys are compared component by component with them.
+    /// The order is the ascending primary key order for the INT64, BOOL, FLOAT64, BYTES, DATE, TIMESTAMP
+    /// and STRING keys. The keys of the other types and of the columns without the type are sorted
+    /// deterministically. The ranges are not reordered.
+    pub fn sorted(mut self, key_types: &[TypeCode]) -> KeySet {
+        self.inner.keys.sort_by(|a, b| compare_key(a, b, key_types));
+        self
+    }
+
+    /// split splits the key set into the key sets with at most max_keys keys and ranges each,
+    /// so that a large key set fits into the size limit of a request.
+    /// The keys come first in their order followed by the ranges. The key set of all the keys
+    /// and the key set within the max_keys are returned as is. max_keys 0 is treated as 1.
+    pub fn split(self, max_keys: usize) -> Vec<KeySet> {
+        let max_keys = max_keys.max(1);
+        if self.inner.all || self.inner.keys.len() + self.inner.ranges.len() <= max_keys {
+            return vec![self];
+        }
+        let mut key_sets = vec![];
+        let mut current = InternalKeySet::default();
+        let full = |current: &InternalKeySet| current.keys.len() + current.ranges.len() == max_keys;
+        for key in self.inner.keys {
+            if full(&current) {
+                key_sets.push(KeySet {
+                    inner: std::mem::take(&mut current),
+                });
+            }
+            current.keys.push(key);
+        }
+        for range in self.inner.ranges {
+            if full(&current) {
+                key_sets.push(KeySet {
+                    inner: std::mem::take(&mut current),
+                });
+            }
+            current.ranges.push(range);
+        }
+        key_sets.push(KeySet { inner: current });
+        key_sets
+    }
+
+    /// len returns the number of the keys and the ranges.
+    pub fn len(&self) -> usize {
+        self.inner.keys.len() + self.inner.ranges.len()
+    }
+
+    /// is_empty returns true if the key set has neither a key nor a range and is not all the keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0 && !self.inner.all
+    }
+}
+
+/// compare_key compares the keys component by component. The shorter key is less if it is a prefix.
+fn compare_key(a: &ListValue, b: &ListValue, key_types: &[TypeCode]) -> Ordering {
+    for (i, (a, b)) in a.values.iter().zip(b.values.iter()).enumerate() {
+        let ordering = compare_value(a, b, key_types.get(i).copied());
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.values.len().cmp(&b.values.len())
+}
+
+fn compare_value(a: &Value, b: &Value, code: Option<TypeCode>) -> Ordering {
+    // NULL is the smallest in the ascending order like Cloud Spanner.
+    fn rank(kind: Option<&Kind>) -> u8 {
+        match kind {
+            None | Some(Kind::NullValue(_)) => 0,
+            Some(Kind::BoolValue(_)) => 1,
+            Some(Kind::NumberValue(_)) => 2,
+            Some(Kind::StringValue(_)) => 3,
+            Some(Kind::ListValue(_)) => 4,
+            Some(Kind::StructValue(_)) => 5,
+        }
+    }
+    match (a.kind.as_ref(), b.kind.as_ref()) {
+        (Some(Kind::BoolValue(a)), Some(Kind::BoolValue(b))) => a.cmp(b),
+        (Some(Kind::NumberValue(a)), Some(Kind::NumberValue(b))) => a.total_cmp(b),
+        (Some(Kind::StringValue(a)), Some(Kind::StringValue(b))) => compare_string(a, b, code),
+        (Some(Kind::ListValue(a)), Some(Kind::ListValue(b))) => compare_key(a, b, &[]),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// compare_string compares the values encoded as the string by the type of the column.
+/// INT64 is encoded as the decimal string and BYTES as base64, so they are compared by the decoded values.
+/// The other strings such as STRING, DATE and TIMESTAMP are compared as bytes like Cloud Spanner.
+fn compare_string(a: &str, b: &str, code: Option<TypeCode>) -> Ordering {
+    match code {
+        Some(TypeCode::Int64) => {
+            if let (Ok(a), Ok(b)) = (a.parse::<i64>(), b.parse::<i64>()) {
+                return a.cmp(&b);
+            }
+        }
+        Some(TypeCode::Bytes) => {
+            if let (Ok(a), Ok(b)) = (BASE64_STANDARD.decode(a), BASE64_STANDARD.decode(b)) {
+                return a.cmp(&b);
+            }
+        }
+        _ => {}
+    }
+    a.cmp(b)
+}
+
 #[cfg(test)]
 mod tests {
     use prost_types::value::Kind;
@@ -361,4 +473,96 @@ mod tests {
             _ => panic!("invalid end key trype"),
         }
     }
+
+    fn keys(key_set: KeySet) -> Vec<String> {
+        key_set.inner.keys.iter().map(|k| format!("{:?}", k.values)).collect()
+    }
+
+    #[test]
+    fn test_key_set_split() {
+        let mut key_set = KeySet::from((0..10).map(|i| Key::new(&i)).collect::<Vec<Key>>());
+        key_set.inner.ranges = (0..3)
+            .map(|i| KeyRange::new(Key::new(&(i * 100)), Key::new(&(i * 100 + 50)), RangeKind::ClosedOpen).into())
+            .collect();
+        let key_sets = key_set.clone().split(4);
+        assert_eq!(key_sets.iter().map(|k| k.len()).collect::<Vec<usize>>(), vec![4, 4, 4, 1]);
+        assert_eq!(key_sets[2].inner.keys.len(), 2);
+        assert_eq!(key_sets[2].inner.ranges.len(), 2);
+        let split_keys: Vec<String> = key_sets.into_iter().flat_map(keys).collect();
+        assert_eq!(split_keys, keys(key_set.clone()));
+
+        // not split
+        assert_eq!(key_set.clone().split(13).len(), 1);
+        assert_eq!(key_set.split(0).len(), 13);
+        assert_eq!(all_keys().split(1).len(), 1);
+        assert!(KeySet::from(Vec::<Key>::new()).is_empty());
+        assert!(!all_keys().is_empty());
+    }
+
+    #[test]
+    fn test_key_set_sorted() {
+        let key_set = KeySet::from(vec![
+            Key::composite(&[&"user2", &10]),
+            Key::composite(&[&"user1", &10]),
+            Key::composite(&[&"user2", &9]),
+            Key::composite(&[&"user1", &-1]),
+            Key::composite(&[&"user1", &None::<i64>]),
+            Key::new(&"user2"),
+        ]);
+        let expected = keys(KeySet::from(vec![
+            Key::composite(&[&"user1", &None::<i64>]),
+            Key::composite(&[&"user1", &-1]),
+            Key::composite(&[&"user1", &10]),
+            Key::new(&"user2"),
+            Key::composite(&[&"user2", &9]),
+            Key::composite(&[&"user2", &10]),
+        ]));
+        let key_types = [TypeCode::String, TypeCode::Int64];
+        assert_eq!(keys(key_set.clone().sorted(&key_types)), expected);
+
+        // deterministic regardless of the input order
+        let mut reversed = key_set;
+        reversed.inner.keys.reverse();
+        assert_eq!(keys(reversed.sorted(&key_types)), expected);
+
+        // the numeric-looking STRING keys are in the byte order.
+        let key_set = KeySet::from(vec![Key::new(&"9"), Key::new(&"10"), Key::new(&"a")]);
+        let expected = keys(KeySet::from(vec![Key::new(&"10"), Key::new(&"9"), Key::new(&"a")]));
+        assert_eq!(keys(key_set.sorted(&[TypeCode::String])), expected);
+    }
+
+    #[test]
+    fn test_compare_value() {
+        let value = |kind: Kind| Value { kind: Some(kind) };
+        let string = |s: &str| value(Kind::StringValue(s.to_string()));
+        let int64 = Some(TypeCode::Int64);
+        assert_eq!(compare_value(&string("9"), &string("10"), int64), Ordering::Less);
+        assert_eq!(compare_value(&string("-1"), &string("-10"), int64), Ordering::Greater);
+
+        // STRING is compared as bytes.
+        let string_type = Some(TypeCode::String);
+        assert_eq!(compare_value(&string("9"), &string("10"), string_type), Ordering::Greater);
+        assert_eq!(compare_value(&string("10"), &string("a"), string_type), Ordering::Less);
+        assert_eq!(compare_value(&string("b"), &string("a"), string_type), Ordering::Greater);
+        assert_eq!(compare_value(&string("b"), &string("a"), None), Ordering::Greater);
+
+        // BYTES is compared by the decoded bytes rather than base64.
+        let bytes = |b: &[u8]| string(&BASE64_STANDARD.encode(b));
+        let bytes_type = Some(TypeCode::Bytes);
+        assert_eq!(compare_value(&bytes(&[0xff]), &bytes(&[0x00, 0x01]), None), Ordering::Less);
+        assert_eq!(
+            compare_value(&bytes(&[0xff]), &bytes(&[0x00, 0x01]), bytes_type),
+            Ordering::Greater
+        );
+
+        assert_eq!(
+            compare_value(&value(Kind::NumberValue(-1.5)), &value(Kind::NumberValue(0.5)), None),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_value(&value(Kind::BoolValue(true)), &value(Kind::BoolValue(false)), None),
+            Ordering::Greater
+        );
+        assert_eq!(compare_value(&value(Kind::NullValue(0)), &string("a"), None), Ordering::Less);
+    }
 }
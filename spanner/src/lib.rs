@@ -33,7 +33,7 @@
 //!
 //!     // Insert or update
 //!     let mutation = insert_or_update("Guild", &["GuildId", "OwnerUserID", "UpdatedAt"], &[&"guildId", &"ownerId", &CommitTimestamp::new()]);
-//!     let commit_timestamp = client.apply(vec![mutation]).await?;
+//!     let commit_result = client.apply(vec![mutation]).await?;
 //!
 //!     // Read with query
 //!     let mut stmt = Statement::new("SELECT GuildId FROM Guild WHERE OwnerUserID = @OwnerUserID");
@@ -131,7 +131,7 @@
 //!
 //! async fn run(client: Client) -> Result<(), Error>{
 //!     let mutation = insert_or_update("Guild", &["GuildId", "OwnerUserID", "UpdatedAt"], &[&"guildId1", &"ownerId1", &CommitTimestamp::new()]);
-//!     let commit_timestamp = client.apply(vec![mutation]).await?;
+//!     let commit_result = client.apply(vec![mutation]).await?;
 //!
 //!     let mut tx = client.single().await?;
 //!     let row = tx.read_row( "Guild", &["GuildId", "OwnerUserID", "UpdatedAt"], Key::new(&"guildId1")).await?;
@@ -469,7 +469,7 @@
 //! async fn run(client: Client) -> Result<(), Error>{
 //!     let m1 = delete("Guild", all_keys());
 //!     let m2 = insert("Guild", &["GuildID", "OwnerUserID", "UpdatedAt"], &[&"1", &"2", &CommitTimestamp::new()]);
-//!     let commit_timestamp = client.apply(vec![m1,m2]).await?;
+//!     let commit_result = client.apply(vec![m1,m2]).await?;
 //!     Ok(())
 //! }
 //! ```
@@ -479,12 +479,12 @@
 //! ```
 //! use google_cloud_spanner::mutation::update;
 //! use google_cloud_spanner::key::Key;
-//! use google_cloud_spanner::value::Timestamp;
+//! use google_cloud_spanner::transaction_rw::CommitResult;
 //! use google_cloud_spanner::client::Error;
 //! use google_cloud_spanner::client::Client;
 //! use google_cloud_spanner::reader::AsyncIterator;
 //!
-//! async fn run(client: Client) ->Result<(Option<Timestamp>,()), Error> {
+//! async fn run(client: Client) ->Result<(CommitResult,()), Error> {
 //!     client.read_write_transaction(|tx, _| {
 //!         Box::pin(async move {
 //!             // The transaction function will be called again if the error code
@@ -569,7 +569,7 @@
 //!
 //!         // try to commit or rollback transaction.
 //!         match tx.end(result, None).await {
-//!             Ok((_commit_timestamp, success)) => return Ok(success),
+//!             Ok((_commit_result, success)) => return Ok(success),
 //!             Err(err) => retry.next(err).await? // check retry
 //!         }
 //!     }
@@ -609,13 +609,18 @@
 pub mod admin;
 pub mod apiv1;
 pub mod client;
+pub mod committer;
+pub mod information_schema;
 pub mod key;
 pub mod mutation;
+pub mod plan;
 pub mod reader;
 pub mod retry;
 pub mod row;
 pub mod session;
 pub mod statement;
+#[cfg(feature = "test-util")]
+pub mod testing;
 pub mod transaction;
 pub mod transaction_ro;
 pub mod transaction_rw;
@@ -1,7 +1,10 @@
+use std::collections::{HashMap, HashSet};
+
+use prost_types::value::Kind;
 use prost_types::{ListValue, Value};
 
 use google_cloud_googleapis::spanner::v1::mutation::{Delete, Operation, Write};
-use google_cloud_googleapis::spanner::v1::Mutation;
+use google_cloud_googleapis::spanner::v1::{Mutation, TypeCode};
 
 use crate::key::KeySet;
 use crate::statement::{ToKind, ToStruct};
@@ -177,6 +180,228 @@ pub fn delete(table: &str, key_set: impl Into<KeySet>) -> Mutation {
     }
 }
 
+/// delete_ordered is the same as delete, but sorts the keys of the KeySet so that the concurrent deletes
+/// acquire the locks in the same order. key_types are the types of the primary key columns in order.
+/// See KeySet::sorted for the order.
+pub fn delete_ordered(table: &str, key_set: impl Into<KeySet>, key_types: &[TypeCode]) -> Mutation {
+    delete(table, key_set.into().sorted(key_types))
+}
+
+/// delete_split is the same as delete, but splits the KeySet with more keys and ranges than max_keys
+/// into the multiple delete mutations, so that each mutation fits into the size limit of a request.
+/// The split does not change the mutation count of the deletes, so use chunk to commit the mutations
+/// exceeding the mutation limit of a commit in the multiple commits.
+pub fn delete_split(table: &str, key_set: impl Into<KeySet>, max_keys: usize) -> Vec<Mutation> {
+    key_set
+        .into()
+        .split(max_keys)
+        .into_iter()
+        .map(|key_set| delete(table, key_set))
+        .collect()
+}
+
+/// table returns the table of the mutation.
+pub(crate) fn table(operation: &Operation) -> &str {
+    match operation {
+        Operation::Insert(w) | Operation::Update(w) | Operation::InsertOrUpdate(w) | Operation::Replace(w) => &w.table,
+        Operation::Delete(d) => &d.table,
+    }
+}
+
+/// mutation_count estimates how many mutations the Mutation counts toward the commit limit.
+/// A write counts one per column per row and a delete counts one per key or key range.
+/// Secondary indexes also count toward the limit in Cloud Spanner, so this is the lower bound.
+pub fn mutation_count(mutation: &Mutation) -> usize {
+    match mutation.operation.as_ref() {
+        Some(Operation::Insert(w))
+        | Some(Operation::Update(w))
+        | Some(Operation::InsertOrUpdate(w))
+        | Some(Operation::Replace(w)) => w.columns.len() * w.values.len(),
+        Some(Operation::Delete(d)) => match d.key_set.as_ref() {
+            Some(ks) if !ks.all => ks.keys.len() + ks.ranges.len(),
+            _ => 1,
+        },
+        None => 0,
+    }
+}
+
+/// chunk splits the mutations into chunks whose mutation_count total is less than or equal to max_per_commit.
+/// A mutation exceeding max_per_commit by itself is put in a chunk alone.
+pub fn chunk(ms: Vec<Mutation>, max_per_commit: usize) -> Vec<Vec<Mutation>> {
+    let mut chunks = vec![];
+    let mut current = vec![];
+    let mut current_count = 0;
+    for m in ms {
+        let count = mutation_count(&m);
+        if !current.is_empty() && current_count + count > max_per_commit {
+            chunks.push(std::mem::take(&mut current));
+            current_count = 0;
+        }
+        current_count += count;
+        current.push(m);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// coalesce merges the single row writes targeting the same table and primary key into one mutation.
+/// primary_keys is the list of the table name and its primary key columns such as `&[("User", &["UserId"])]`.
+///
+/// A later `update` or `insert_or_update` is merged into the previous write of the same row, and the later value
+/// wins per column. The merged mutation keeps the operation of the previous write, so an `insert` followed by
+/// an `update` is still an `insert`. Deletes, `insert`, `replace` and multi row writes are never merged, and the
+/// writes before them are not merged with the writes after them.
+pub fn coalesce(ms: Vec<Mutation>, primary_keys: &[(&str, &[&str])]) -> Vec<Mutation> {
+    let mut result: Vec<Mutation> = Vec::with_capacity(ms.len());
+    // (table, key) -> index of the mergeable mutation in result
+    let mut rows: HashMap<(String, String), usize> = HashMap::new();
+    for m in ms {
+        let (table, merge) = match m.operation.as_ref() {
+            Some(Operation::Update(w)) | Some(Operation::InsertOrUpdate(w)) => (w.table.clone(), true),
+            Some(Operation::Insert(w)) | Some(Operation::Replace(w)) => (w.table.clone(), false),
+            Some(Operation::Delete(d)) => (d.table.clone(), false),
+            None => {
+                result.push(m);
+                continue;
+            }
+        };
+        let key = match primary_keys.iter().find(|(t, _)| *t == table) {
+            Some((_, key_columns)) => row_key(&m, key_columns),
+            None => {
+                result.push(m);
+                continue;
+            }
+        };
+        let key = match key {
+            Some(key) => (table, key),
+            None => {
+                // the affected rows are unknown
+                rows.retain(|(t, _), _| *t != table);
+                result.push(m);
+                continue;
+            }
+        };
+        if merge {
+            if let Some(index) = rows.get(&key) {
+                merge_write(&mut result[*index], m);
+                continue;
+            }
+        }
+        rows.insert(key, result.len());
+        result.push(m);
+    }
+    result
+}
+
+/// row_key returns the primary key of the single row write.
+fn row_key(m: &Mutation, key_columns: &[&str]) -> Option<String> {
+    let w = match m.operation.as_ref() {
+        Some(Operation::Insert(w))
+        | Some(Operation::Update(w))
+        | Some(Operation::InsertOrUpdate(w))
+        | Some(Operation::Replace(w)) => w,
+        _ => return None,
+    };
+    if w.values.len() != 1 {
+        return None;
+    }
+    let row = &w.values[0].values;
+    let mut key = Vec::with_capacity(key_columns.len());
+    for key_column in key_columns {
+        let index = w.columns.iter().position(|c| c == key_column)?;
+        key.push(row.get(index)?);
+    }
+    Some(format!("{key:?}"))
+}
+
+fn merge_write(dst: &mut Mutation, src: Mutation) {
+    let src = match src.operation {
+        Some(Operation::Update(w)) | Some(Operation::InsertOrUpdate(w)) => w,
+        _ => return,
+    };
+    let dst = match dst.operation.as_mut() {
+        Some(Operation::Insert(w))
+        | Some(Operation::Update(w))
+        | Some(Operation::InsertOrUpdate(w))
+        | Some(Operation::Replace(w)) => w,
+        _ => return,
+    };
+    let src_row = src.values.into_iter().next().map(|v| v.values).unwrap_or_default();
+    let dst_row = &mut dst.values[0].values;
+    for (column, value) in src.columns.into_iter().zip(src_row) {
+        match dst.columns.iter().position(|c| *c == column) {
+            Some(index) => dst_row[index] = value,
+            None => {
+                dst.columns.push(column);
+                dst_row.push(value);
+            }
+        }
+    }
+}
+
+/// COMMIT_TIMESTAMP is the placeholder replaced with the commit timestamp by Cloud Spanner.
+const COMMIT_TIMESTAMP: &str = "spanner.commit_timestamp()";
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum MutationError {
+    #[error("commit timestamp is not allowed: table={0}, column={1}, the column must have allow_commit_timestamp=true option")]
+    CommitTimestampNotAllowed(String, String),
+}
+
+/// CommitTimestampValidator checks that the commit timestamp is written only to the columns
+/// with the `allow_commit_timestamp=true` option, so that the misuse fails before the commit with a clear error.
+/// InformationSchema::commit_timestamp_validator loads the columns from the schema.
+/// ```
+/// use google_cloud_spanner::mutation::{insert, CommitTimestampValidator};
+/// use google_cloud_spanner::value::CommitTimestamp;
+///
+/// let validator = CommitTimestampValidator::default().allow("User", &["UpdatedAt"]);
+/// let m = validator.validate(insert("User", &["UserId", "UpdatedAt"], &[&"user1", &CommitTimestamp::new()]));
+/// assert!(m.is_ok());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CommitTimestampValidator {
+    // table name in lower case -> column names in lower case
+    columns: HashMap<String, HashSet<String>>,
+}
+
+impl CommitTimestampValidator {
+    /// allow adds the columns of the table allowing the commit timestamp.
+    pub fn allow(mut self, table: &str, columns: &[&str]) -> Self {
+        self.columns
+            .entry(table.to_lowercase())
+            .or_default()
+            .extend(columns.iter().map(|c| c.to_lowercase()));
+        self
+    }
+
+    /// validate returns the mutation as is if the commit timestamp is written only to the allowed columns.
+    pub fn validate(&self, mutation: Mutation) -> Result<Mutation, MutationError> {
+        let w = match mutation.operation.as_ref() {
+            Some(Operation::Insert(w))
+            | Some(Operation::Update(w))
+            | Some(Operation::InsertOrUpdate(w))
+            | Some(Operation::Replace(w)) => w,
+            _ => return Ok(mutation),
+        };
+        let allowed = self.columns.get(&w.table.to_lowercase());
+        for (index, column) in w.columns.iter().enumerate() {
+            let is_commit_timestamp = w.values.iter().any(|row| {
+                matches!(row.values.get(index).and_then(|v| v.kind.as_ref()), Some(Kind::StringValue(v)) if v == COMMIT_TIMESTAMP)
+            });
+            if is_commit_timestamp && !allowed.is_some_and(|c| c.contains(&column.to_lowercase())) {
+                return Err(MutationError::CommitTimestampNotAllowed(
+                    w.table.to_string(),
+                    column.to_string(),
+                ));
+            }
+        }
+        Ok(mutation)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use prost_types::value::Kind;
@@ -387,6 +612,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_delete_ordered() {
+        let mutation = delete_ordered("Guild", vec![Key::new(&"guild2"), Key::new(&"guild1")], &[v1::TypeCode::String]);
+        match mutation.operation.unwrap() {
+            v1::mutation::Operation::Delete(w) => {
+                let keys: Vec<_> = w.key_set.unwrap().keys.into_iter().map(|k| k.values).collect();
+                assert_eq!(keys, vec![Key::new(&"guild1").values.values, Key::new(&"guild2").values.values]);
+            }
+            _ => panic!("invalid operation"),
+        }
+    }
+
     fn assert_struct(mut w: Write) {
         assert_eq!("Guild", w.table);
         assert_eq!("StructField", w.columns.pop().unwrap());
@@ -398,4 +635,134 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_mutation_count() {
+        let m = insert("Guild", &["GuildId", "UserId"], &[&"1", &"2"]);
+        assert_eq!(2, mutation_count(&m));
+        let m = delete("Guild", all_keys());
+        assert_eq!(1, mutation_count(&m));
+        let m = delete("Guild", vec![Key::new(&"1"), Key::new(&"2")]);
+        assert_eq!(2, mutation_count(&m));
+    }
+
+    #[test]
+    fn test_delete_split() {
+        let keys: Vec<Key> = (0..25_000_i64).map(|i| Key::new(&i)).collect();
+        let ms = delete_split("Guild", keys, 10_000);
+        assert_eq!(
+            vec![10_000, 10_000, 5_000],
+            ms.iter().map(mutation_count).collect::<Vec<usize>>()
+        );
+        for m in ms {
+            match m.operation.unwrap() {
+                v1::mutation::Operation::Delete(d) => assert_eq!(d.table, "Guild"),
+                _ => panic!("must be delete"),
+            }
+        }
+        assert_eq!(1, delete_split("Guild", all_keys(), 1).len());
+    }
+
+    #[test]
+    fn test_chunk() {
+        let ms: Vec<Mutation> = (0..250_000_i64)
+            .map(|i| insert("Guild", &["GuildId", "UserId"], &[&i, &"user"]))
+            .collect();
+        let chunks = chunk(ms, 80_000);
+        assert_eq!(7, chunks.len());
+        for c in chunks.iter().take(6) {
+            assert_eq!(40_000, c.len());
+        }
+        assert_eq!(10_000, chunks[6].len());
+
+        // too large mutation is committed alone.
+        let ms = vec![
+            insert("Guild", &["GuildId", "UserId"], &[&"1", &"2"]),
+            insert(
+                "Guild",
+                &["GuildId", "UserId", "UpdatedAt"],
+                &[&"3", &"4", &CommitTimestamp::new()],
+            ),
+            insert("Guild", &["GuildId"], &[&"5"]),
+        ];
+        let chunks = chunk(ms, 2);
+        assert_eq!(vec![1, 1, 1], chunks.iter().map(|c| c.len()).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let primary_keys: &[(&str, &[&str])] = &[("User", &["UserId"])];
+        let ms = vec![
+            update("User", &["UserId", "Name", "Level"], &[&"1", &"a", &1]),
+            update("User", &["UserId", "Level", "Rank"], &[&"1", &2, &3]),
+        ];
+        let ms = coalesce(ms, primary_keys);
+        assert_eq!(1, ms.len());
+        match ms[0].operation.as_ref().unwrap() {
+            v1::mutation::Operation::Update(w) => {
+                assert_eq!(vec!["UserId", "Name", "Level", "Rank"], w.columns);
+                assert_eq!(1, w.values.len());
+                let values = &w.values[0].values;
+                assert_eq!(4, values.len());
+                assert_eq!(Some(&Kind::StringValue("a".to_string())), values[1].kind.as_ref());
+                assert_eq!(Some(&Kind::StringValue("2".to_string())), values[2].kind.as_ref());
+                assert_eq!(Some(&Kind::StringValue("3".to_string())), values[3].kind.as_ref());
+            }
+            _ => panic!("must be update"),
+        }
+
+        // different keys, unknown tables and deletes are not merged.
+        let ms = vec![
+            insert_or_update("User", &["UserId", "Level"], &[&"1", &1]),
+            insert_or_update("User", &["UserId", "Level"], &[&"2", &1]),
+            insert_or_update("Guild", &["GuildId", "Level"], &[&"1", &1]),
+            insert_or_update("Guild", &["GuildId", "Level"], &[&"1", &2]),
+            delete("User", Key::new(&"1")),
+            insert_or_update("User", &["UserId", "Level"], &[&"1", &2]),
+            insert_or_update("User", &["UserId", "Level"], &[&"2", &2]),
+        ];
+        assert_eq!(7, coalesce(ms, primary_keys).len());
+
+        // insert followed by update is still insert.
+        let ms = vec![
+            insert("User", &["UserId", "Level"], &[&"1", &1]),
+            update("User", &["UserId", "Level"], &[&"1", &2]),
+        ];
+        let ms = coalesce(ms, primary_keys);
+        assert_eq!(1, ms.len());
+        assert!(matches!(ms[0].operation, Some(v1::mutation::Operation::Insert(_))));
+    }
+
+    #[test]
+    fn test_commit_timestamp_validator() {
+        let validator = CommitTimestampValidator::default().allow("UserItem", &["UpdatedAt"]);
+        let columns = ["UserId", "ItemId", "UpdatedAt"];
+
+        let m = insert("UserItem", &columns, &[&"user1", &1, &CommitTimestamp::new()]);
+        assert!(validator.validate(m).is_ok());
+        let m = update(
+            "useritem",
+            &["UserId", "ItemId", "updatedat"],
+            &[&"user1", &1, &CommitTimestamp::new()],
+        );
+        assert!(validator.validate(m).is_ok(), "table and column names are case insensitive");
+        let m = insert("UserItem", &["UserId", "ItemId", "Quantity"], &[&"user1", &1, &1]);
+        assert!(validator.validate(m).is_ok());
+        assert!(validator.validate(delete("UserItem", Key::new(&"user1"))).is_ok());
+
+        let m = insert(
+            "UserItem",
+            &["UserId", "ItemId", "Quantity"],
+            &[&"user1", &1, &CommitTimestamp::new()],
+        );
+        assert_eq!(
+            validator.validate(m).unwrap_err(),
+            MutationError::CommitTimestampNotAllowed("UserItem".to_string(), "Quantity".to_string())
+        );
+        let m = insert_or_update("User", &["UserId", "UpdatedAt"], &[&"user1", &CommitTimestamp::new()]);
+        assert_eq!(
+            validator.validate(m).unwrap_err(),
+            MutationError::CommitTimestampNotAllowed("User".to_string(), "UpdatedAt".to_string())
+        );
+    }
 }
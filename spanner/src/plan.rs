@@ -0,0 +1,263 @@
+use std::time::Duration;
+
+use prost_types::value::Kind;
+
+use google_cloud_googleapis::spanner::v1::{plan_node, PlanNode, QueryPlan};
+
+/// Plan is the query plan returned by Transaction::plan.
+/// It has the helpers to assert the properties of the plan, such as whether the query scans a whole table,
+/// so that the tests can detect the query plan regressions.
+/// ```
+/// use google_cloud_spanner::client::{Client, Error};
+/// use google_cloud_spanner::statement::Statement;
+///
+/// async fn run(client: Client) -> Result<(), Error> {
+///     let mut tx = client.single().await?;
+///     let plan = tx.plan(Statement::new("SELECT * FROM Guild WHERE OwnerUserID = 'user1'")).await?;
+///     assert!(!plan.uses_full_scan());
+///     assert!(plan.uses_index("GuildByOwnerUserID"));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Plan {
+    nodes: Vec<PlanNode>,
+}
+
+impl From<QueryPlan> for Plan {
+    fn from(value: QueryPlan) -> Self {
+        Plan {
+            nodes: value.plan_nodes,
+        }
+    }
+}
+
+impl Plan {
+    /// nodes returns the plan nodes in pre-order starting with the root.
+    pub fn nodes(&self) -> &[PlanNode] {
+        &self.nodes
+    }
+
+    /// scans returns the relational nodes scanning a table or an index.
+    pub fn scans(&self) -> impl Iterator<Item = &PlanNode> {
+        self.nodes
+            .iter()
+            .filter(|n| n.kind == plan_node::Kind::Relational as i32 && n.display_name == "Scan")
+    }
+
+    /// uses_full_scan returns true if the query scans all the rows of a table or an index.
+    pub fn uses_full_scan(&self) -> bool {
+        self.scans().any(|n| metadata(n, "Full scan") == Some("true"))
+    }
+
+    /// uses_index returns true if the query scans the secondary index.
+    pub fn uses_index(&self, name: &str) -> bool {
+        self.scans()
+            .any(|n| metadata(n, "scan_type") == Some("IndexScan") && metadata(n, "scan_target") == Some(name))
+    }
+
+    /// scans_table returns true if the query scans the base table.
+    pub fn scans_table(&self, name: &str) -> bool {
+        self.scans()
+            .any(|n| metadata(n, "scan_type") == Some("TableScan") && metadata(n, "scan_target") == Some(name))
+    }
+}
+
+/// QueryStats is the execution statistics of the query executed with QueryMode::Profile.
+/// The fields are parsed from ResultSetStats.query_stats, and None if the key is not returned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    pub elapsed_time: Option<Duration>,
+    pub cpu_time: Option<Duration>,
+    pub query_plan_creation_time: Option<Duration>,
+    pub rows_returned: Option<i64>,
+    pub rows_scanned: Option<i64>,
+    pub deleted_rows_scanned: Option<i64>,
+    pub bytes_returned: Option<i64>,
+    pub optimizer_version: Option<String>,
+    pub optimizer_statistics_package: Option<String>,
+    pub query_text: Option<String>,
+}
+
+impl From<&prost_types::Struct> for QueryStats {
+    fn from(value: &prost_types::Struct) -> Self {
+        let string = |key: &str| match value.fields.get(key)?.kind.as_ref()? {
+            Kind::StringValue(v) => Some(v.to_string()),
+            Kind::NumberValue(v) => Some(v.to_string()),
+            _ => None,
+        };
+        let number = |key: &str| string(key)?.parse::<i64>().ok();
+        let duration = |key: &str| parse_duration(&string(key)?);
+        QueryStats {
+            elapsed_time: duration("elapsed_time"),
+            cpu_time: duration("cpu_time"),
+            query_plan_creation_time: duration("query_plan_creation_time"),
+            rows_returned: number("rows_returned"),
+            rows_scanned: number("rows_scanned"),
+            deleted_rows_scanned: number("deleted_rows_scanned"),
+            bytes_returned: number("bytes_returned"),
+            optimizer_version: string("optimizer_version"),
+            optimizer_statistics_package: string("optimizer_statistics_package"),
+            query_text: string("query_text"),
+        }
+    }
+}
+
+/// parse_duration parses the duration formatted by Cloud Spanner such as "1.23 msecs".
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (amount, unit) = value.trim().split_once(' ')?;
+    let amount = amount.parse::<f64>().ok().filter(|v| v.is_finite() && *v >= 0.0)?;
+    let nanos_per_unit = match unit {
+        "usecs" => 1e3,
+        "msecs" => 1e6,
+        "secs" => 1e9,
+        "mins" => 60e9,
+        _ => return None,
+    };
+    Some(Duration::from_nanos((amount * nanos_per_unit).round() as u64))
+}
+
+/// metadata returns the string value of the metadata of the node.
+fn metadata<'a>(node: &'a PlanNode, key: &str) -> Option<&'a str> {
+    match node.metadata.as_ref()?.fields.get(key)?.kind.as_ref()? {
+        Kind::StringValue(v) => Some(v),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::value::Kind;
+    use prost_types::{Struct, Value};
+
+    use google_cloud_googleapis::spanner::v1::{plan_node, PlanNode, QueryPlan};
+
+    use std::time::Duration;
+
+    use crate::plan::{parse_duration, Plan, QueryStats};
+
+    fn node(index: i32, kind: plan_node::Kind, display_name: &str, metadata: &[(&str, &str)]) -> PlanNode {
+        PlanNode {
+            index,
+            kind: kind.into(),
+            display_name: display_name.to_string(),
+            child_links: vec![],
+            short_representation: None,
+            metadata: Some(Struct {
+                fields: metadata
+                    .iter()
+                    .map(|(k, v)| {
+                        (
+                            k.to_string(),
+                            Value {
+                                kind: Some(Kind::StringValue(v.to_string())),
+                            },
+                        )
+                    })
+                    .collect(),
+            }),
+            execution_stats: None,
+        }
+    }
+
+    fn plan(scan: PlanNode) -> Plan {
+        Plan::from(QueryPlan {
+            plan_nodes: vec![
+                node(0, plan_node::Kind::Relational, "Distributed Union", &[]),
+                node(1, plan_node::Kind::Relational, "Serialize Result", &[]),
+                scan,
+                node(3, plan_node::Kind::Scalar, "Reference", &[("scan_type", "IndexScan")]),
+            ],
+        })
+    }
+
+    #[test]
+    fn test_full_table_scan() {
+        let plan = plan(node(
+            2,
+            plan_node::Kind::Relational,
+            "Scan",
+            &[
+                ("scan_type", "TableScan"),
+                ("scan_target", "User"),
+                ("Full scan", "true"),
+            ],
+        ));
+        assert_eq!(plan.nodes().len(), 4);
+        assert_eq!(plan.scans().count(), 1);
+        assert!(plan.uses_full_scan());
+        assert!(plan.scans_table("User"));
+        assert!(!plan.scans_table("Item"));
+        assert!(!plan.uses_index("UserByName"));
+    }
+
+    #[test]
+    fn test_index_scan() {
+        let plan = plan(node(
+            2,
+            plan_node::Kind::Relational,
+            "Scan",
+            &[("scan_type", "IndexScan"), ("scan_target", "UserByName")],
+        ));
+        assert!(!plan.uses_full_scan());
+        assert!(plan.uses_index("UserByName"));
+        assert!(!plan.uses_index("UserByAge"));
+        assert!(!plan.scans_table("User"));
+    }
+
+    #[test]
+    fn test_empty_plan() {
+        let plan = Plan::default();
+        assert!(!plan.uses_full_scan());
+        assert!(!plan.uses_index("UserByName"));
+    }
+
+    #[test]
+    fn test_query_stats() {
+        // query_stats of the ResultSetStats returned in QueryMode::Profile
+        let stats = Struct {
+            fields: [
+                ("elapsed_time", Kind::StringValue("1.85 msecs".to_string())),
+                ("cpu_time", Kind::StringValue("1.5 msecs".to_string())),
+                ("query_plan_creation_time", Kind::StringValue("0.25 msecs".to_string())),
+                ("rows_returned", Kind::StringValue("3".to_string())),
+                ("rows_scanned", Kind::StringValue("10".to_string())),
+                ("deleted_rows_scanned", Kind::StringValue("0".to_string())),
+                ("bytes_returned", Kind::NumberValue(120.0)),
+                ("optimizer_version", Kind::StringValue("5".to_string())),
+                (
+                    "optimizer_statistics_package",
+                    Kind::StringValue("auto_20230101_00_00_00UTC".to_string()),
+                ),
+                ("query_text", Kind::StringValue("SELECT * FROM User".to_string())),
+                ("remote_server_calls", Kind::StringValue("0/0".to_string())),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), Value { kind: Some(v) }))
+            .collect(),
+        };
+        let stats = QueryStats::from(&stats);
+        assert_eq!(stats.elapsed_time, Some(Duration::from_micros(1850)));
+        assert_eq!(stats.cpu_time, Some(Duration::from_micros(1500)));
+        assert_eq!(stats.query_plan_creation_time, Some(Duration::from_micros(250)));
+        assert_eq!(stats.rows_returned, Some(3));
+        assert_eq!(stats.rows_scanned, Some(10));
+        assert_eq!(stats.deleted_rows_scanned, Some(0));
+        assert_eq!(stats.bytes_returned, Some(120));
+        assert_eq!(stats.optimizer_version.as_deref(), Some("5"));
+        assert_eq!(stats.optimizer_statistics_package.as_deref(), Some("auto_20230101_00_00_00UTC"));
+        assert_eq!(stats.query_text.as_deref(), Some("SELECT * FROM User"));
+
+        assert_eq!(QueryStats::from(&Struct::default()), QueryStats::default());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("12 usecs"), Some(Duration::from_micros(12)));
+        assert_eq!(parse_duration("1.5 secs"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2 mins"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5"), None);
+        assert_eq!(parse_duration("1.5 hours"), None);
+        assert_eq!(parse_duration("-1 secs"), None);
+    }
+}
@@ -2,12 +2,17 @@ use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures_util::Stream;
 use prost_types::{value::Kind, Value};
 
 use google_cloud_gax::grpc::{Code, Response, Status, Streaming};
+use google_cloud_googleapis::spanner::v1::request_options::Priority;
 use google_cloud_googleapis::spanner::v1::struct_type::Field;
-use google_cloud_googleapis::spanner::v1::{ExecuteSqlRequest, PartialResultSet, ReadRequest, ResultSetMetadata};
+use google_cloud_googleapis::spanner::v1::{
+    ExecuteSqlRequest, PartialResultSet, ReadRequest, RequestOptions, ResultSetMetadata, ResultSetStats, Transaction,
+};
 
+use crate::plan::QueryStats;
 use crate::row::Row;
 use crate::session::SessionHandle;
 use crate::transaction::CallOptions;
@@ -29,10 +34,21 @@ pub trait Reader {
     fn update_token(&mut self, resume_token: Vec<u8>);
 
     fn can_retry(&self) -> bool;
+
+    /// set_priority overrides the priority of the request, such as the priority of the partition
+    /// executed by BatchReadOnlyTransaction::execute. The request tag is kept.
+    fn set_priority(&mut self, _priority: Priority) {}
+}
+
+/// set_request_priority sets the priority of the RequestOptions keeping the tags.
+fn set_request_priority(request_options: &mut Option<RequestOptions>, priority: Priority) {
+    request_options.get_or_insert_with(RequestOptions::default).priority = priority.into();
 }
 
 pub struct StatementReader {
     pub request: ExecuteSqlRequest,
+    /// read_write is true if the statement is executed in the read-write transaction.
+    pub(crate) read_write: bool,
 }
 
 #[async_trait]
@@ -43,7 +59,10 @@ impl Reader for StatementReader {
         option: Option<CallOptions>,
     ) -> Result<Response<Streaming<PartialResultSet>>, Status> {
         let option = option.unwrap_or_default();
-        let client = &mut session.spanner_client;
+        let mut client = session.spanner_client.clone();
+        if self.read_write {
+            client = client.read_write();
+        }
         let result = client
             .execute_streaming_sql(self.request.clone(), option.cancel, option.retry)
             .await;
@@ -57,10 +76,16 @@ impl Reader for StatementReader {
     fn can_retry(&self) -> bool {
         !self.request.resume_token.is_empty()
     }
+
+    fn set_priority(&mut self, priority: Priority) {
+        set_request_priority(&mut self.request.request_options, priority);
+    }
 }
 
 pub struct TableReader {
     pub request: ReadRequest,
+    /// read_write is true if the rows are read in the read-write transaction.
+    pub(crate) read_write: bool,
 }
 
 #[async_trait]
@@ -71,7 +96,10 @@ impl Reader for TableReader {
         option: Option<CallOptions>,
     ) -> Result<Response<Streaming<PartialResultSet>>, Status> {
         let option = option.unwrap_or_default();
-        let client = &mut session.spanner_client;
+        let mut client = session.spanner_client.clone();
+        if self.read_write {
+            client = client.read_write();
+        }
         let result = client
             .streaming_read(self.request.clone(), option.cancel, option.retry)
             .await;
@@ -85,13 +113,108 @@ impl Reader for TableReader {
     fn can_retry(&self) -> bool {
         !self.request.resume_token.is_empty()
     }
+
+    fn set_priority(&mut self, priority: Priority) {
+        set_request_priority(&mut self.request.request_options, priority);
+    }
+}
+
+/// ResumableStream receives the PartialResultSet of the streaming RPC.
+/// When the stream fails, the RPC is re-issued with the last resume token received,
+/// unless no resume token has been received yet.
+struct ResumableStream<'a> {
+    streaming: Streaming<PartialResultSet>,
+    session: &'a mut SessionHandle,
+    reader: Box<dyn Reader + Sync + Send>,
+}
+
+impl<'a> ResumableStream<'a> {
+    async fn new(
+        session: &'a mut SessionHandle,
+        reader: Box<dyn Reader + Sync + Send>,
+        option: Option<CallOptions>,
+    ) -> Result<ResumableStream<'a>, Status> {
+        let streaming = reader.read(session, option).await?.into_inner();
+        Ok(Self {
+            streaming,
+            session,
+            reader,
+        })
+    }
+
+    /// restart starts the streaming RPC of the next reader on the same session.
+    async fn restart(
+        &mut self,
+        reader: Box<dyn Reader + Sync + Send>,
+        option: Option<CallOptions>,
+    ) -> Result<(), Status> {
+        self.streaming = reader.read(self.session, option).await?.into_inner();
+        self.reader = reader;
+        Ok(())
+    }
+
+    async fn message(&mut self, option: Option<CallOptions>) -> Result<Option<PartialResultSet>, Status> {
+        let maybe_result_set = match self.streaming.message().await {
+            Ok(s) => s,
+            Err(e) => {
+                if !self.reader.can_retry() {
+                    return Err(e);
+                }
+                tracing::debug!("streaming error: {}. resume reading by resume_token", e);
+                let result = self.reader.read(self.session, option).await?;
+                self.streaming = result.into_inner();
+                self.streaming.message().await?
+            }
+        };
+        if let Some(result_set) = &maybe_result_set {
+            //if resume_token changes set new resume_token
+            if !result_set.values.is_empty() && !result_set.resume_token.is_empty() {
+                self.reader.update_token(result_set.resume_token.clone());
+            }
+        }
+        Ok(maybe_result_set)
+    }
 }
 
+/// raw_stream returns the PartialResultSets of the streaming RPC as they are received,
+/// resumed with the resume token like RowIterator.
+pub(crate) async fn raw_stream<'a>(
+    session: &'a mut SessionHandle,
+    reader: Box<dyn Reader + Sync + Send>,
+    option: Option<CallOptions>,
+) -> Result<impl Stream<Item = Result<PartialResultSet, Status>> + 'a, Status> {
+    let stream = ResumableStream::new(session, reader, option.clone()).await?;
+    Ok(futures_util::stream::unfold(Some((stream, option)), |state| async move {
+        let (mut stream, option) = state?;
+        match stream.message(option.clone()).await {
+            Ok(Some(result_set)) => Some((Ok(result_set), Some((stream, option)))),
+            Ok(None) => None,
+            // the stream ends after the error.
+            Err(e) => Some((Err(e), None)),
+        }
+    }))
+}
+
+/// DEFAULT_MAX_CHUNKED_VALUE_SIZE is the default limit of the size of a value reassembled from the chunks.
+pub const DEFAULT_MAX_CHUNKED_VALUE_SIZE: usize = 128 * 1024 * 1024;
+
 pub struct ResultSet {
     fields: Arc<Vec<Field>>,
     index: Arc<HashMap<String, usize>>,
     rows: VecDeque<Value>,
     chunked_value: bool,
+    /// size of the value being reassembled from the chunks.
+    chunked_value_size: usize,
+    max_chunked_value_size: usize,
+    /// limit of the size of a row, unlimited if None.
+    max_row_size: Option<usize>,
+    /// size of the row being assembled and the column index of the next value of the row.
+    row_size: usize,
+    row_column: usize,
+    /// transaction begun by the statement, sent only in the metadata of the first response.
+    transaction: Option<Transaction>,
+    /// stats sent only in the last response.
+    stats: Option<ResultSetStats>,
 }
 
 impl ResultSet {
@@ -113,10 +236,27 @@ impl ResultSet {
         None
     }
 
+    /// capture_transaction keeps the transaction in the metadata of the first response.
+    /// The metadata of the first response is kept even if it has no values.
+    fn capture_transaction(&mut self, metadata: Option<&ResultSetMetadata>) {
+        if self.transaction.is_none() {
+            self.transaction = metadata.and_then(|m| m.transaction.clone());
+        }
+    }
+
     /// Merge tries to combine two protobuf Values if possible.
     fn merge(previous_last: Value, current_first: Value) -> Result<Value, Status> {
-        match previous_last.kind.unwrap() {
-            Kind::StringValue(last) => match current_first.kind.unwrap() {
+        let (previous_last, current_first) = match (previous_last.kind, current_first.kind) {
+            (Some(previous_last), Some(current_first)) => (previous_last, current_first),
+            _ => {
+                return Err(Status::new(
+                    Code::Internal,
+                    "unsupported value kind: the chunked value has no kind or an unknown kind",
+                ))
+            }
+        };
+        match previous_last {
+            Kind::StringValue(last) => match current_first {
                 Kind::StringValue(first) => {
                     tracing::trace!("previous_last={}, current_first={}", &last, first);
                     Ok(Value {
@@ -128,8 +268,8 @@ impl ResultSet {
                     "chunks kind mismatch: current_first must be StringKind",
                 )),
             },
-            Kind::ListValue(mut last) => match current_first.kind.unwrap() {
-                Kind::ListValue(mut first) => {
+            Kind::ListValue(mut last) => match current_first {
+                Kind::ListValue(mut first) if !first.values.is_empty() => {
                     let first_value_of_current = first.values.remove(0);
                     let merged = match last.values.pop() {
                         Some(last_value_of_previous) => {
@@ -178,24 +318,88 @@ impl ResultSet {
             }
         }
 
+        let mut last_is_merged = false;
         if self.chunked_value {
             tracing::trace!("now chunked value found previous={}, current={}", self.rows.len(), values.len());
+            let current_first = values.remove(0);
+            let size = value_size(&current_first);
+            self.chunked_value_size += size;
+            if self.chunked_value_size > self.max_chunked_value_size {
+                return Err(Status::new(
+                    Code::ResourceExhausted,
+                    format!(
+                        "chunked value exceeds the limit: size={}, max={}",
+                        self.chunked_value_size, self.max_chunked_value_size
+                    ),
+                ));
+            }
+            // the chunk belongs to the last column of the previous values.
+            self.row_size += size;
+            if let Some(err) = self.row_size_error(self.row_column.saturating_sub(1)) {
+                return Err(err);
+            }
             //merge when the chunked value is found.
-            let merged = ResultSet::merge(self.rows.pop_back().unwrap(), values.remove(0))?;
+            let merged = ResultSet::merge(self.rows.pop_back().unwrap(), current_first)?;
             self.rows.push_back(merged);
+            last_is_merged = values.is_empty();
+        }
+        for value in values.iter() {
+            if self.row_column >= self.fields.len() {
+                self.row_column = 0;
+                self.row_size = 0;
+            }
+            self.row_size += value_size(value);
+            if let Some(err) = self.row_size_error(self.row_column) {
+                return Err(err);
+            }
+            self.row_column += 1;
         }
         self.rows.extend(values);
         self.chunked_value = chunked_value;
+        if !chunked_value {
+            self.chunked_value_size = 0;
+        } else if !last_is_merged {
+            self.chunked_value_size = self.rows.back().map(value_size).unwrap_or_default();
+        }
         Ok(true)
     }
+
+    /// row_size_error returns RESOURCE_EXHAUSTED error with the column name
+    /// if the row being assembled exceeds max_row_size by the value of the column.
+    fn row_size_error(&self, column: usize) -> Option<Status> {
+        let max = self.max_row_size.filter(|max| self.row_size > *max)?;
+        let name = self.fields.get(column).map(|f| f.name.as_str()).unwrap_or_default();
+        Some(Status::new(
+            Code::ResourceExhausted,
+            format!("row exceeds the limit at column {}: size={}, max={}", name, self.row_size, max),
+        ))
+    }
+}
+
+/// value_size returns the approximate size of the value in memory.
+fn value_size(value: &Value) -> usize {
+    match &value.kind {
+        Some(Kind::StringValue(s)) => s.len(),
+        Some(Kind::ListValue(l)) => l.values.iter().map(value_size).sum(),
+        Some(Kind::StructValue(s)) => s.fields.iter().map(|(k, v)| k.len() + value_size(v)).sum(),
+        Some(Kind::NumberValue(_)) => 8,
+        Some(Kind::BoolValue(_)) => 1,
+        Some(Kind::NullValue(_)) | None => 0,
+    }
 }
 
+/// RowIterator reads the rows of the streaming result.
+/// It doesn't read ahead: the next PartialResultSet is received from the stream only after the rows
+/// of the previous one are consumed by next, so the rows are fetched at the pace of the consumer.
+/// The PartialResultSet the server has already sent within the HTTP/2 flow control window is buffered by the transport.
+/// The read split by ReadOptions::max_keys_per_request starts the following requests after the rows of the
+/// previous one are consumed, so that the rows of all the requests are returned in sequence.
 pub struct RowIterator<'a> {
-    streaming: Streaming<PartialResultSet>,
-    session: &'a mut SessionHandle,
-    reader: Box<dyn Reader + Sync + Send>,
+    stream: ResumableStream<'a>,
     rs: ResultSet,
     reader_option: Option<CallOptions>,
+    pending_readers: VecDeque<Box<dyn Reader + Sync + Send>>,
+    pending_option: Option<CallOptions>,
 }
 
 impl<'a> RowIterator<'a> {
@@ -204,50 +408,95 @@ impl<'a> RowIterator<'a> {
         reader: Box<dyn Reader + Sync + Send>,
         option: Option<CallOptions>,
     ) -> Result<RowIterator<'a>, Status> {
-        let streaming = reader.read(session, option).await?.into_inner();
+        let stream = ResumableStream::new(session, reader, option).await?;
         let rs = ResultSet {
             fields: Arc::new(vec![]),
             index: Arc::new(HashMap::new()),
             rows: VecDeque::new(),
             chunked_value: false,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         Ok(Self {
-            streaming,
-            session,
-            reader,
+            stream,
             rs,
             reader_option: None,
+            pending_readers: VecDeque::new(),
+            pending_option: None,
         })
     }
 
+    /// queue_readers adds the readers started after the rows of the current one are consumed.
+    pub(crate) fn queue_readers(
+        &mut self,
+        readers: impl IntoIterator<Item = Box<dyn Reader + Sync + Send>>,
+        option: Option<CallOptions>,
+    ) {
+        self.pending_readers.extend(readers);
+        self.pending_option = option;
+    }
+
     pub fn set_call_options(&mut self, option: CallOptions) {
         self.reader_option = Some(option);
     }
 
+    /// set_max_chunked_value_size sets the limit of the size of a value reassembled from the chunks
+    /// of the PartialResultSet. The next returns RESOURCE_EXHAUSTED error when the limit is exceeded.
+    /// The default is DEFAULT_MAX_CHUNKED_VALUE_SIZE.
+    pub fn set_max_chunked_value_size(&mut self, size: usize) {
+        self.rs.max_chunked_value_size = size;
+    }
+
+    /// set_max_row_size sets the limit of the size of a row, so that a row with the unexpectedly large values
+    /// does not exhaust the memory. The next returns RESOURCE_EXHAUSTED error with the name of the column
+    /// whose value makes the row exceed the limit. The row size is unlimited by default.
+    pub fn set_max_row_size(&mut self, size: usize) {
+        self.rs.max_row_size = Some(size);
+    }
+
+    /// transaction returns the transaction begun by the statement executed with TransactionSelector::Begin.
+    /// The transaction is available after the first response of the stream is received.
+    pub fn transaction(&self) -> Option<&Transaction> {
+        self.rs.transaction.as_ref()
+    }
+
+    /// columns returns the name and the complete Type of each column,
+    /// including the element type of the array and the field types of the struct.
+    /// The columns are available after the first response of the stream is received.
+    pub fn columns(&self) -> &[Field] {
+        &self.rs.fields
+    }
+
+    /// stats returns the query plan and the execution statistics of the query executed with QueryMode::Plan
+    /// or QueryMode::Profile. The stats are available after all the rows are read.
+    pub fn stats(&self) -> Option<&ResultSetStats> {
+        self.rs.stats.as_ref()
+    }
+
+    /// query_stats returns the execution statistics of the query executed with QueryMode::Profile.
+    /// The stats are available after all the rows are read.
+    pub fn query_stats(&self) -> Option<QueryStats> {
+        self.rs.stats.as_ref()?.query_stats.as_ref().map(QueryStats::from)
+    }
+
     async fn try_recv(&mut self, option: Option<CallOptions>) -> Result<bool, Status> {
         // try getting records from server
-        let maybe_result_set = match self.streaming.message().await {
-            Ok(s) => s,
-            Err(e) => {
-                if !self.reader.can_retry() {
-                    return Err(e);
-                }
-                tracing::debug!("streaming error: {}. resume reading by resume_token", e);
-                let result = self.reader.read(self.session, option).await?;
-                self.streaming = result.into_inner();
-                self.streaming.message().await?
-            }
-        };
+        let maybe_result_set = self.stream.message(option).await?;
 
         match maybe_result_set {
             Some(result_set) => {
+                self.rs.capture_transaction(result_set.metadata.as_ref());
+                if result_set.stats.is_some() {
+                    self.rs.stats = result_set.stats;
+                }
                 if result_set.values.is_empty() {
                     return Ok(false);
                 }
-                //if resume_token changes set new resume_token
-                if !result_set.resume_token.is_empty() {
-                    self.reader.update_token(result_set.resume_token);
-                }
                 self.rs
                     .add(result_set.metadata, result_set.values, result_set.chunked_value)
             }
@@ -276,7 +525,14 @@ impl<'a> AsyncIterator for RowIterator<'a> {
         }
         // no data found or record chunked.
         if !self.try_recv(self.reader_option.clone()).await? {
-            return Ok(None);
+            // the rows of the current reader are consumed, so start the next one.
+            return match self.pending_readers.pop_front() {
+                Some(reader) => {
+                    self.stream.restart(reader, self.pending_option.clone()).await?;
+                    self.next().await
+                }
+                None => Ok(None),
+            };
         }
         return self.next().await;
     }
@@ -285,17 +541,31 @@ impl<'a> AsyncIterator for RowIterator<'a> {
 #[cfg(test)]
 mod tests {
     use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
+    use std::time::Instant;
 
+    use async_trait::async_trait;
     use prost_types::value::Kind;
-    use prost_types::Value;
+    use prost_types::{ListValue, Value};
 
+    use google_cloud_gax::conn::Environment;
+    use google_cloud_gax::grpc::{Code, Response, Status, Streaming};
     use google_cloud_googleapis::spanner::v1::struct_type::Field;
-    use google_cloud_googleapis::spanner::v1::{ResultSetMetadata, StructType};
-
-    use crate::reader::ResultSet;
+    use google_cloud_googleapis::spanner::v1::{
+        ExecuteSqlRequest, PartialResultSet, ResultSetMetadata, Session, StructType, Transaction, Type,
+        TypeAnnotationCode, TypeCode,
+    };
+
+    use crate::apiv1::conn_pool::ConnectionManager;
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::reader::{
+        AsyncIterator, Reader, ResultSet, RowIterator, StatementReader, DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+    };
     use crate::row::{Row, TryFromValue};
+    use crate::session::SessionHandle;
     use crate::statement::ToKind;
+    use crate::transaction::CallOptions;
 
     fn empty_rs() -> ResultSet {
         ResultSet {
@@ -303,6 +573,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: Default::default(),
             chunked_value: false,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         }
     }
 
@@ -359,6 +636,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: Default::default(),
             chunked_value: false,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         assert!(rs.next().is_none());
     }
@@ -370,6 +654,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: VecDeque::from(values),
             chunked_value: false,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         let mut rs1 = rs(vec![value("value1")]);
         assert!(rs1.next().is_none());
@@ -384,6 +675,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: VecDeque::from(vec![value("value1"), value("value2")]),
             chunked_value,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         assert!(rs(true).next().is_none());
         assert_eq!(rs(false).next().unwrap().column::<String>(0).unwrap(), "value1".to_string());
@@ -396,6 +694,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: VecDeque::from(vec![value("value1"), value("value2"), value("value3")]),
             chunked_value,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         let mut incomplete = rs(true);
         assert!(incomplete.next().is_some());
@@ -415,6 +720,13 @@ mod tests {
             index: Arc::new(Default::default()),
             rows: VecDeque::from(vec![value("value1"), value("value2"), value("value3")]),
             chunked_value,
+            chunked_value_size: 0,
+            max_chunked_value_size: DEFAULT_MAX_CHUNKED_VALUE_SIZE,
+            max_row_size: None,
+            row_size: 0,
+            row_column: 0,
+            transaction: None,
+            stats: None,
         };
         let mut incomplete = rs(true);
         assert_eq!(incomplete.next().unwrap().column::<String>(1).unwrap(), "value2".to_string());
@@ -435,6 +747,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rs_merge_unsupported_value_kind() {
+        let unknown = Value { kind: None };
+        let result = ResultSet::merge(value("val"), unknown.clone());
+        assert_eq!(result.unwrap_err().code(), Code::Internal);
+        let result = ResultSet::merge(unknown, value("ue1"));
+        assert_eq!(result.unwrap_err().code(), Code::Internal);
+        let result = ResultSet::merge(value(vec!["val"]), value(Vec::<String>::new()));
+        assert_eq!(result.unwrap_err().code(), Code::Internal);
+    }
+
     #[test]
     fn test_rs_merge_list_value() {
         let previous_last = value(vec!["value1-1", "value1-2", "val"]);
@@ -710,4 +1033,216 @@ mod tests {
         );
         assert!(rs.next().is_none());
     }
+
+    #[test]
+    fn test_rs_add_chunked_value_exceeds_limit() {
+        let mut rs = empty_rs();
+        rs.max_chunked_value_size = 10;
+        let metadata = Some(ResultSetMetadata {
+            row_type: Some(StructType {
+                fields: vec![field("column1")],
+            }),
+            transaction: None,
+            undeclared_parameters: None,
+        });
+        // the completed values don't count.
+        assert!(rs
+            .add(metadata.clone(), vec![value("value1value1"), value("12345")], true)
+            .unwrap());
+        assert_eq!(rs.chunked_value_size, 5);
+        assert!(rs.add(metadata.clone(), vec![value("12345")], true).unwrap());
+        assert_eq!(rs.chunked_value_size, 10);
+
+        let err = rs.add(metadata, vec![value("1")], false).unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+    }
+
+    #[test]
+    fn test_rs_add_row_exceeds_limit() {
+        let mut rs = empty_rs();
+        rs.max_row_size = Some(10);
+        let metadata = Some(ResultSetMetadata {
+            row_type: Some(StructType {
+                fields: vec![field("column1"), field("column2")],
+            }),
+            transaction: None,
+            undeclared_parameters: None,
+        });
+        // the size is reset on each row.
+        assert!(rs
+            .add(
+                metadata.clone(),
+                vec![value("12345"), value("12345"), value("12345"), value("123")],
+                true
+            )
+            .unwrap());
+        assert_eq!(rs.row_size, 8);
+        assert!(rs.next().is_some());
+
+        // the chunk of column2 exceeds the limit
+        let err = rs.add(metadata, vec![value("123")], false).unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+        assert!(err.message().contains("column2"), "{}", err.message());
+
+        let mut rs = empty_rs();
+        rs.max_row_size = Some(10);
+        let metadata = Some(ResultSetMetadata {
+            row_type: Some(StructType {
+                fields: vec![field("column1"), field("column2"), field("column3")],
+            }),
+            transaction: None,
+            undeclared_parameters: None,
+        });
+        let err = rs
+            .add(metadata, vec![value("1"), value("12345678901"), value("1")], false)
+            .unwrap_err();
+        assert!(err.message().contains("column2"), "{}", err.message());
+    }
+
+    #[test]
+    fn test_rs_capture_transaction() {
+        let mut rs = empty_rs();
+        rs.capture_transaction(None);
+        assert!(rs.transaction.is_none());
+
+        let metadata = |id: &[u8]| ResultSetMetadata {
+            row_type: Some(StructType {
+                fields: vec![field("column1")],
+            }),
+            transaction: Some(Transaction {
+                id: id.to_vec(),
+                read_timestamp: None,
+            }),
+            undeclared_parameters: None,
+        };
+        // the first response
+        rs.capture_transaction(Some(&metadata(b"tx1")));
+        assert_eq!(rs.transaction.as_ref().unwrap().id, b"tx1".to_vec());
+
+        // the following responses never overwrite the transaction
+        rs.capture_transaction(Some(&metadata(b"tx2")));
+        assert_eq!(rs.transaction.as_ref().unwrap().id, b"tx1".to_vec());
+        assert!(rs.add(Some(metadata(b"tx2")), vec![value("value1")], false).unwrap());
+        assert_eq!(rs.transaction.as_ref().unwrap().id, b"tx1".to_vec());
+    }
+
+    #[test]
+    fn test_rs_add_array_of_struct_column() {
+        let struct_type = Type {
+            code: TypeCode::Struct.into(),
+            array_element_type: None,
+            struct_type: Some(StructType {
+                fields: vec![Field {
+                    name: "ItemId".to_string(),
+                    r#type: Some(i64::get_type()),
+                }],
+            }),
+            type_annotation: TypeAnnotationCode::Unspecified.into(),
+        };
+        let array_type = Type {
+            code: TypeCode::Array.into(),
+            array_element_type: Some(Box::new(struct_type.clone())),
+            struct_type: None,
+            type_annotation: TypeAnnotationCode::Unspecified.into(),
+        };
+        let mut rs = empty_rs();
+        let metadata = Some(ResultSetMetadata {
+            row_type: Some(StructType {
+                fields: vec![Field {
+                    name: "Items".to_string(),
+                    r#type: Some(array_type.clone()),
+                }],
+            }),
+            transaction: None,
+            undeclared_parameters: None,
+        });
+        let items = Value {
+            kind: Some(Kind::ListValue(ListValue { values: vec![] })),
+        };
+        assert!(rs.add(metadata, vec![items], false).unwrap());
+        let row = rs.next().unwrap();
+        let columns = row.columns();
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].name, "Items");
+        let column_type = columns[0].r#type.as_ref().unwrap();
+        assert_eq!(*column_type, array_type);
+        let element_type = column_type.array_element_type.as_ref().unwrap();
+        assert_eq!(element_type.code, TypeCode::Struct as i32);
+        let struct_fields = &element_type.struct_type.as_ref().unwrap().fields;
+        assert_eq!(struct_fields[0].name, "ItemId");
+        assert_eq!(struct_fields[0].r#type.as_ref().unwrap().code, TypeCode::Int64 as i32);
+    }
+
+    /// CountingReader counts the streaming RPCs started by the RowIterator.
+    struct CountingReader {
+        inner: StatementReader,
+        reads: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Reader for CountingReader {
+        async fn read(
+            &self,
+            session: &mut SessionHandle,
+            option: Option<CallOptions>,
+        ) -> Result<Response<Streaming<PartialResultSet>>, Status> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.inner.read(session, option).await
+        }
+
+        fn update_token(&mut self, resume_token: Vec<u8>) {
+            self.inner.update_token(resume_token)
+        }
+
+        fn can_retry(&self) -> bool {
+            self.inner.can_retry()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_row_iterator_reads_on_demand() {
+        let fake = FakeSpanner::default();
+        // each stream returns two rows.
+        fake.set_results(vec![PartialResultSet {
+            metadata: Some(ResultSetMetadata {
+                row_type: Some(StructType {
+                    fields: vec![field("Value")],
+                }),
+                ..Default::default()
+            }),
+            values: vec![value(1), value(2)],
+            ..Default::default()
+        }]);
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let session = Session {
+            name: "session".to_string(),
+            ..Default::default()
+        };
+        let mut session = SessionHandle::new(session, cm.conn(), 0, Instant::now());
+        let reads = Arc::new(AtomicUsize::new(0));
+        let reader = || -> Box<dyn Reader + Sync + Send> {
+            Box::new(CountingReader {
+                inner: StatementReader {
+                    request: ExecuteSqlRequest::default(),
+                    read_write: false,
+                },
+                reads: reads.clone(),
+            })
+        };
+
+        let mut iter = RowIterator::new(&mut session, reader(), None).await.unwrap();
+        iter.queue_readers(vec![reader(), reader()], None);
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        // the next stream is started only when the consumer asks for the row after the rows of the previous one.
+        for expected in [1, 1, 2, 2, 3, 3] {
+            assert!(iter.next().await.unwrap().is_some());
+            assert_eq!(reads.load(Ordering::SeqCst), expected);
+        }
+        assert!(iter.next().await.unwrap().is_none());
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+        assert_eq!(fake.requests::<ExecuteSqlRequest>("ExecuteStreamingSql").len(), 3);
+    }
 }
@@ -1,9 +1,29 @@
 use std::iter::Take;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use google_cloud_gax::grpc::{Code, Status};
 use google_cloud_gax::retry::{CodeCondition, Condition, ExponentialBackoff, Retry, RetrySetting, TryAs};
 
+use crate::transaction::CallOptions;
+
+/// TRANSIENT_INTERNAL_MESSAGES are the messages of the INTERNAL errors known to be caused by the transient
+/// network problems. The INTERNAL errors are retried only if the message contains one of them.
+pub const TRANSIENT_INTERNAL_MESSAGES: [&str; 4] = [
+    "stream terminated by RST_STREAM",
+    "HTTP/2 error code: INTERNAL_ERROR",
+    "Connection closed with unknown cause",
+    "Received unexpected EOS on DATA frame from server",
+];
+
+/// is_transient_internal returns true if the status is INTERNAL with one of TRANSIENT_INTERNAL_MESSAGES.
+pub fn is_transient_internal(status: &Status) -> bool {
+    status.code() == Code::Internal
+        && TRANSIENT_INTERNAL_MESSAGES
+            .iter()
+            .any(|message| status.message().contains(message))
+}
+
 pub struct TransactionCondition<E>
 where
     E: TryAs<Status>,
@@ -18,15 +38,7 @@ where
 {
     fn should_retry(&mut self, error: &E) -> bool {
         if let Some(status) = error.try_as() {
-            let code = status.code();
-            if code == Code::Internal
-                && !status.message().contains("stream terminated by RST_STREAM")
-                && !status.message().contains("HTTP/2 error code: INTERNAL_ERROR")
-                && !status.message().contains("Connection closed with unknown cause")
-                && !status
-                    .message()
-                    .contains("Received unexpected EOS on DATA frame from server")
-            {
+            if status.code() == Code::Internal && !is_transient_internal(status) {
                 return false;
             }
             return self.inner.should_retry(error);
@@ -35,6 +47,56 @@ where
     }
 }
 
+/// RpcCondition is the condition of the RPCs. Like TransactionCondition, the INTERNAL errors in the codes
+/// are retried only if they are transient.
+pub(crate) struct RpcCondition {
+    inner: CodeCondition,
+}
+
+impl Condition<Status> for RpcCondition {
+    fn should_retry(&mut self, status: &Status) -> bool {
+        if status.code() == Code::Internal && !is_transient_internal(status) {
+            return false;
+        }
+        self.inner.should_retry(status)
+    }
+}
+
+/// RpcRetrySetting retries the RPCs with RpcCondition.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RpcRetrySetting {
+    inner: RetrySetting,
+    timeout: Option<Duration>,
+}
+
+impl RpcRetrySetting {
+    pub fn new(inner: RetrySetting) -> Self {
+        Self { inner, timeout: None }
+    }
+
+    /// with_timeout caps the total time of the retries. The deadline is resolved when the retry starts.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Retry<Status, RpcCondition> for RpcRetrySetting {
+    fn strategy(&self) -> Take<ExponentialBackoff> {
+        self.inner.strategy()
+    }
+
+    fn condition(&self) -> RpcCondition {
+        RpcCondition {
+            inner: CodeCondition::new(self.inner.codes.clone()),
+        }
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
 pub struct TransactionRetry<E>
 where
     E: TryAs<Status>,
@@ -113,6 +175,23 @@ impl TransactionRetrySetting {
     }
 }
 
+impl TransactionRetrySetting {
+    /// disabled returns the setting that never retries the aborted transaction.
+    pub fn disabled() -> Self {
+        Self {
+            inner: RetrySetting::disabled(),
+        }
+    }
+
+    /// with_call_options returns the setting retrying the codes unless the retry of the options is disabled.
+    pub(crate) fn with_call_options(codes: Vec<Code>, options: &CallOptions) -> Self {
+        match &options.retry {
+            Some(retry) if retry.is_disabled() => Self::disabled(),
+            _ => Self::new(codes),
+        }
+    }
+}
+
 impl Default for TransactionRetrySetting {
     fn default() -> Self {
         TransactionRetrySetting::new(vec![Code::Aborted])
@@ -125,7 +204,10 @@ mod tests {
     use google_cloud_gax::retry::{Condition, Retry};
 
     use crate::client::Error;
-    use crate::retry::TransactionRetrySetting;
+    use google_cloud_gax::retry::RetrySetting;
+
+    use crate::retry::{is_transient_internal, RpcRetrySetting, TransactionRetrySetting};
+    use crate::transaction::CallOptions;
 
     #[test]
     fn test_transaction_condition() {
@@ -136,4 +218,47 @@ mod tests {
         let err = &Error::GRPC(Status::new(Code::Aborted, ""));
         assert!(default.condition().should_retry(err));
     }
+
+    #[test]
+    fn test_rpc_condition() {
+        let transient = Status::new(
+            Code::Internal,
+            "h2 protocol error: stream terminated by RST_STREAM with error code: INTERNAL_ERROR",
+        );
+        let other = Status::new(Code::Internal, "internal error");
+        assert!(is_transient_internal(&transient));
+        assert!(!is_transient_internal(&other));
+        assert!(!is_transient_internal(&Status::new(
+            Code::Unavailable,
+            "stream terminated by RST_STREAM"
+        )));
+
+        let setting = RpcRetrySetting::new(RetrySetting {
+            codes: vec![Code::Unavailable, Code::Internal],
+            ..Default::default()
+        });
+        assert!(setting.condition().should_retry(&transient));
+        assert!(!setting.condition().should_retry(&other));
+        assert!(setting.condition().should_retry(&Status::new(Code::Unavailable, "")));
+
+        // INTERNAL is not retried unless it is in the codes.
+        let setting = RpcRetrySetting::new(RetrySetting {
+            codes: vec![Code::Unavailable],
+            ..Default::default()
+        });
+        assert!(!setting.condition().should_retry(&transient));
+    }
+
+    #[test]
+    fn test_transaction_retry_disabled() {
+        let err = &Error::GRPC(Status::new(Code::Aborted, ""));
+        let setting = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &CallOptions::no_retry());
+        assert!(!setting.condition().should_retry(err));
+        assert!(<TransactionRetrySetting as Retry<Error, _>>::strategy(&setting)
+            .next()
+            .is_none());
+
+        let setting = TransactionRetrySetting::with_call_options(vec![Code::Aborted], &CallOptions::default());
+        assert!(setting.condition().should_retry(err));
+    }
 }
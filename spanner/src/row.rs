@@ -11,9 +11,12 @@ use time::macros::format_description;
 use time::{Date, OffsetDateTime};
 
 use google_cloud_googleapis::spanner::v1::struct_type::Field;
-use google_cloud_googleapis::spanner::v1::StructType;
+use google_cloud_googleapis::spanner::v1::{StructType, Type, TypeCode};
 
-use crate::value::{CommitTimestamp, SpannerNumeric};
+use crate::statement::INTERVAL_TYPE_CODE;
+use crate::value::{CommitTimestamp, DynamicValue, Interval, SpannerNumeric, StructValue};
+#[cfg(feature = "proto")]
+use crate::value::{ProtoEnum, ProtoMessage};
 
 #[derive(Clone)]
 pub struct Row {
@@ -24,8 +27,23 @@ pub struct Row {
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[deprecated(note = "the column that can't be decoded as the requested type is returned as TypeMismatch")]
     #[error("Illegal Kind: field={0}, kind={1}")]
     KindMismatch(String, String),
+    /// TypeMismatch is returned when the column can't be decoded as the requested type.
+    /// expected is the requested Rust type, actual is the Spanner type of the column and kind is the kind of the value.
+    #[error("column `{column}`: expected {expected}-decodable, found {actual} ({kind})")]
+    TypeMismatch {
+        column: String,
+        expected: String,
+        actual: String,
+        kind: String,
+    },
+    /// UnsupportedValueKind is returned when the value has no kind or a kind this crate does not recognize,
+    /// such as a kind introduced after this version. The raw value is returned for the caller to handle.
+    #[error("Unsupported value kind: field={0}")]
+    UnsupportedValueKind(String, Box<Value>),
+    #[deprecated(note = "the value without the kind is returned as UnsupportedValueKind")]
     #[error("No kind found: field={0}")]
     NoKind(String),
     #[error("Parse field: field={0}")]
@@ -53,6 +71,16 @@ impl Row {
         Row { index, fields, values }
     }
 
+    pub(crate) fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// columns returns the name and the complete Type of each column,
+    /// including the element type of the array and the field types of the struct.
+    pub fn columns(&self) -> &[Field] {
+        &self.fields
+    }
+
     pub fn column<T>(&self, column_index: usize) -> Result<T, Error>
     where
         T: TryFromValue,
@@ -66,6 +94,34 @@ impl Row {
     {
         self.column(index(&self.index, column_name)?)
     }
+
+    /// column_str returns the STRING column borrowed from the row without allocating a String.
+    /// Use column::<Option<String>> for the nullable column.
+    pub fn column_str(&self, column_index: usize) -> Result<&str, Error> {
+        if self.values.len() <= column_index {
+            return Err(Error::InvalidColumnIndex(column_index, self.values.len()));
+        }
+        let field = &self.fields[column_index];
+        match as_ref(&self.values[column_index], field)? {
+            Kind::StringValue(s) => Ok(s),
+            v => kind_to_error(v, field),
+        }
+    }
+
+    pub fn column_str_by_name(&self, column_name: &str) -> Result<&str, Error> {
+        self.column_str(index(&self.index, column_name)?)
+    }
+
+    /// get_struct returns the STRUCT column decoded into the fields by the Spanner types,
+    /// for the caller without the type implementing TryFromStruct.
+    /// Use column::<Option<StructValue>> for the nullable column.
+    pub fn get_struct(&self, column_index: usize) -> Result<StructValue, Error> {
+        self.column(column_index)
+    }
+
+    pub fn get_struct_by_name(&self, column_name: &str) -> Result<StructValue, Error> {
+        self.column_by_name(column_name)
+    }
 }
 
 //don't use TryFrom trait to avoid the conflict
@@ -140,7 +196,14 @@ impl<'a> Struct<'a> {
 impl TryFromValue for i64 {
     fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
         match as_ref(item, field)? {
-            Kind::StringValue(s) => s.parse().map_err(|e| Error::IntParseError(field.name.to_string(), e)),
+            Kind::StringValue(s) => match s.parse() {
+                Ok(v) => Ok(v),
+                // the STRING column is not an INT64 that failed to parse but the mismatch of the types.
+                Err(_) if !is_code(field, &[TypeCode::Int64, TypeCode::Unspecified]) => {
+                    kind_to_error(&Kind::StringValue(s.to_string()), field)
+                }
+                Err(e) => Err(Error::IntParseError(field.name.to_string(), e)),
+            },
             v => kind_to_error(v, field),
         }
     }
@@ -204,6 +267,26 @@ impl TryFromValue for Vec<u8> {
     }
 }
 
+#[cfg(feature = "proto")]
+impl<T: prost::Message + Default> TryFromValue for ProtoMessage<T> {
+    fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
+        let bytes: Vec<u8> = TryFromValue::try_from(item, field)?;
+        T::decode(bytes.as_slice())
+            .map(ProtoMessage)
+            .map_err(|e| Error::CustomParseError(format!("field={}, {e}", field.name)))
+    }
+}
+
+#[cfg(feature = "proto")]
+impl TryFromValue for ProtoEnum {
+    fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
+        let value: i64 = TryFromValue::try_from(item, field)?;
+        i32::try_from(value)
+            .map(ProtoEnum)
+            .map_err(|e| Error::CustomParseError(format!("field={}, {e}", field.name)))
+    }
+}
+
 impl TryFromValue for SpannerNumeric {
     fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
         match as_ref(item, field)? {
@@ -213,6 +296,17 @@ impl TryFromValue for SpannerNumeric {
     }
 }
 
+impl TryFromValue for Interval {
+    fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
+        match as_ref(item, field)? {
+            Kind::StringValue(s) => s
+                .parse()
+                .map_err(|e| Error::CustomParseError(format!("field={}, {e}", field.name))),
+            v => kind_to_error(v, field),
+        }
+    }
+}
+
 impl TryFromValue for String {
     fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
         match as_ref(item, field)? {
@@ -227,13 +321,17 @@ where
     T: TryFromStruct,
 {
     fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
-        let maybe_array = match field.r#type.as_ref() {
+        let field_type = match field.r#type.as_ref() {
             None => return Err(Error::StructParseError(field.name.to_string(), "field type must not be none")),
-            Some(tp) => tp.array_element_type.as_ref(),
+            Some(tp) => tp,
         };
-        let maybe_struct_type = match maybe_array {
-            None => return Err(Error::StructParseError(field.name.to_string(), "array must not be none")),
-            Some(tp) => tp.struct_type.as_ref(),
+        // STRUCT is a member of the other struct, and ARRAY<STRUCT> is passed from the Vec<T> decoder.
+        let maybe_struct_type = match field_type.struct_type.as_ref() {
+            Some(struct_type) => Some(struct_type),
+            None => match field_type.array_element_type.as_ref() {
+                None => return Err(Error::StructParseError(field.name.to_string(), "array must not be none")),
+                Some(tp) => tp.struct_type.as_ref(),
+            },
         };
         let struct_type = match maybe_struct_type {
             None => {
@@ -249,6 +347,64 @@ where
     }
 }
 
+impl TryFromStruct for StructValue {
+    fn try_from_struct(s: Struct<'_>) -> Result<Self, Error> {
+        let fields = s
+            .metadata
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| Ok((field.name.to_string(), s.column::<DynamicValue>(i)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(StructValue::new(fields))
+    }
+}
+
+/// DynamicValue is decoded by the decoder of the Spanner type of the column.
+impl TryFromValue for DynamicValue {
+    fn try_from(item: &Value, field: &Field) -> Result<Self, Error> {
+        let kind = as_ref(item, field)?;
+        if let Kind::NullValue(_) = kind {
+            return Ok(DynamicValue::Null);
+        }
+        let tp = match field.r#type.as_ref() {
+            Some(tp) => tp,
+            None => return Ok(DynamicValue::Other(item.clone())),
+        };
+        if tp.code == INTERVAL_TYPE_CODE {
+            return Ok(DynamicValue::Interval(TryFromValue::try_from(item, field)?));
+        }
+        Ok(match TypeCode::from_i32(tp.code) {
+            Some(TypeCode::Bool) => DynamicValue::Bool(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Int64) => DynamicValue::Int64(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Float64) => DynamicValue::Float64(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::String) => DynamicValue::String(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Bytes) => DynamicValue::Bytes(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Numeric) => DynamicValue::Numeric(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Timestamp) => DynamicValue::Timestamp(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Date) => DynamicValue::Date(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Json) => DynamicValue::Json(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Struct) => DynamicValue::Struct(TryFromValue::try_from(item, field)?),
+            Some(TypeCode::Array) => {
+                let element = Field {
+                    name: field.name.to_string(),
+                    r#type: tp.array_element_type.as_deref().cloned(),
+                };
+                match kind {
+                    Kind::ListValue(s) => DynamicValue::Array(
+                        s.values
+                            .iter()
+                            .map(|v| <DynamicValue as TryFromValue>::try_from(v, &element))
+                            .collect::<Result<Vec<_>, Error>>()?,
+                    ),
+                    v => return kind_to_error(v, field),
+                }
+            }
+            _ => DynamicValue::Other(item.clone()),
+        })
+    }
+}
+
 impl<T> TryFromValue for Option<T>
 where
     T: TryFromValue,
@@ -261,6 +417,9 @@ where
     }
 }
 
+/// ARRAY is decoded by the decoder of the element type, so that ARRAY<BYTES> is decoded from the base64 strings
+/// and ARRAY<NUMERIC> is decoded from the decimal strings. Use `Vec<Option<T>>` if the array contains NULL.
+/// The NULL array is not decoded as the empty array. Use `Option<Vec<T>>` to decode the NULL array as None.
 impl<T> TryFromValue for Vec<T>
 where
     T: TryFromValue,
@@ -294,12 +453,13 @@ where
 pub fn as_ref<'a>(item: &'a Value, field: &'a Field) -> Result<&'a Kind, Error> {
     return match item.kind.as_ref() {
         Some(v) => Ok(v),
-        None => Err(Error::NoKind(field.name.to_string())),
+        None => Err(Error::UnsupportedValueKind(field.name.to_string(), Box::new(item.clone()))),
     };
 }
 
+/// kind_to_error returns TypeMismatch of the column decoded as T.
 pub fn kind_to_error<'a, T>(v: &'a value::Kind, field: &'a Field) -> Result<T, Error> {
-    let actual = match v {
+    let kind = match v {
         Kind::StringValue(_s) => "StringValue".to_string(),
         Kind::BoolValue(_s) => "BoolValue".to_string(),
         Kind::NumberValue(_s) => "NumberValue".to_string(),
@@ -307,7 +467,51 @@ pub fn kind_to_error<'a, T>(v: &'a value::Kind, field: &'a Field) -> Result<T, E
         Kind::StructValue(_s) => "StructValue".to_string(),
         _ => "unknown".to_string(),
     };
-    Err(Error::KindMismatch(field.name.to_string(), actual))
+    Err(Error::TypeMismatch {
+        column: field.name.to_string(),
+        expected: short_type_name(std::any::type_name::<T>()),
+        actual: field.r#type.as_ref().map_or("unknown type".to_string(), type_name),
+        kind,
+    })
+}
+
+/// is_code returns true if the type of the field, or the element type of the ARRAY field, is unknown or one of the codes.
+/// The elements of the ARRAY are decoded with the field of the ARRAY.
+fn is_code(field: &Field, codes: &[TypeCode]) -> bool {
+    let tp = match field.r#type.as_ref() {
+        Some(tp) if tp.code == TypeCode::Array as i32 => tp.array_element_type.as_deref(),
+        tp => tp,
+    };
+    match tp.and_then(|t| TypeCode::from_i32(t.code)) {
+        Some(code) => codes.contains(&code),
+        None => true,
+    }
+}
+
+/// type_name returns the Spanner type name such as ARRAY<INT64>.
+fn type_name(tp: &Type) -> String {
+    let code = TypeCode::from_i32(tp.code).unwrap_or_default();
+    match (code, tp.array_element_type.as_ref()) {
+        (TypeCode::Array, Some(element)) => format!("ARRAY<{}>", type_name(element)),
+        _ => code.as_str_name().to_string(),
+    }
+}
+
+/// short_type_name removes the module paths from the Rust type name, e.g. Vec<Option<String>>.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+            continue;
+        }
+        short.push_str(path.rsplit("::").next().unwrap_or_default());
+        short.push(c);
+        path.clear();
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+    short
 }
 
 #[cfg(test)]
@@ -315,14 +519,18 @@ mod tests {
     use std::collections::HashMap;
     use std::sync::Arc;
 
-    use prost_types::Value;
+    use prost_types::value::Kind;
+    use prost_types::{ListValue, Value};
     use time::OffsetDateTime;
 
     use google_cloud_googleapis::spanner::v1::struct_type::Field;
+    use google_cloud_googleapis::spanner::v1::{StructType, Type, TypeAnnotationCode, TypeCode};
 
     use crate::row::{Error, Row, Struct as RowStruct, TryFromStruct};
     use crate::statement::{Kinds, ToKind, ToStruct, Types};
-    use crate::value::CommitTimestamp;
+    use crate::value::{CommitTimestamp, DynamicValue, Interval, SpannerNumeric, StructValue};
+    #[cfg(feature = "proto")]
+    use crate::value::{ProtoEnum, ProtoMessage};
 
     struct TestStruct {
         pub struct_field: String,
@@ -424,4 +632,486 @@ mod tests {
         assert_eq!(struct_data[1].struct_field_time, now);
         assert_eq!(struct_data[1].commit_timestamp.timestamp, now);
     }
+
+    struct Child {
+        pub name: String,
+    }
+
+    impl TryFromStruct for Child {
+        fn try_from_struct(s: RowStruct<'_>) -> Result<Self, Error> {
+            Ok(Child {
+                name: s.column_by_name("name")?,
+            })
+        }
+    }
+
+    struct Parent {
+        pub id: i64,
+        pub name: String,
+        pub tags: Vec<i64>,
+        pub child: Child,
+        pub children: Vec<Child>,
+    }
+
+    impl TryFromStruct for Parent {
+        fn try_from_struct(s: RowStruct<'_>) -> Result<Self, Error> {
+            Ok(Parent {
+                id: s.column_by_name("id")?,
+                name: s.column_by_name("name")?,
+                tags: s.column_by_name("tags")?,
+                child: s.column_by_name("child")?,
+                children: s.column_by_name("children")?,
+            })
+        }
+    }
+
+    fn field(name: &str, tp: Type) -> Field {
+        Field {
+            name: name.to_string(),
+            r#type: Some(tp),
+        }
+    }
+
+    fn struct_type(fields: Vec<Field>) -> Type {
+        Type {
+            code: TypeCode::Struct.into(),
+            array_element_type: None,
+            struct_type: Some(StructType { fields }),
+            type_annotation: TypeAnnotationCode::Unspecified.into(),
+        }
+    }
+
+    fn array_type(element: Type) -> Type {
+        Type {
+            code: TypeCode::Array.into(),
+            array_element_type: Some(Box::new(element)),
+            struct_type: None,
+            type_annotation: TypeAnnotationCode::Unspecified.into(),
+        }
+    }
+
+    fn list(values: Vec<Kind>) -> Kind {
+        Kind::ListValue(ListValue {
+            values: values.into_iter().map(|kind| Value { kind: Some(kind) }).collect(),
+        })
+    }
+
+    #[test]
+    fn test_try_from_bytes_and_numeric_array() {
+        let null = Kind::NullValue(prost_types::NullValue::NullValue.into());
+        let mut index = HashMap::new();
+        index.insert("bytes".to_string(), 0);
+        index.insert("numerics".to_string(), 1);
+        let row = Row::new(
+            Arc::new(index),
+            Arc::new(vec![
+                field("bytes", array_type(Vec::<u8>::get_type())),
+                field("numerics", array_type(SpannerNumeric::get_type())),
+            ]),
+            vec![
+                Value {
+                    kind: Some(list(vec![
+                        vec![0x00_u8, 0xff].to_kind(),
+                        null.clone(),
+                        b"abc".to_vec().to_kind(),
+                    ])),
+                },
+                Value {
+                    kind: Some(list(vec![
+                        Kind::StringValue("1.5".to_string()),
+                        null,
+                        Kind::StringValue("-99999999999999999999999999999.999999999".to_string()),
+                    ])),
+                },
+            ],
+        );
+
+        let bytes = row.column_by_name::<Vec<Option<Vec<u8>>>>("bytes").unwrap();
+        assert_eq!(bytes, vec![Some(vec![0x00, 0xff]), None, Some(b"abc".to_vec())]);
+        let numerics = row.column_by_name::<Vec<Option<SpannerNumeric>>>("numerics").unwrap();
+        assert_eq!(
+            numerics,
+            vec![
+                Some(SpannerNumeric::new("1.5")),
+                None,
+                Some(SpannerNumeric::new("-99999999999999999999999999999.999999999")),
+            ]
+        );
+
+        // NULL elements require Option.
+        assert!(row.column_by_name::<Vec<Vec<u8>>>("bytes").is_err());
+        assert!(row.column_by_name::<Vec<SpannerNumeric>>("numerics").is_err());
+    }
+
+    #[test]
+    fn test_null_and_empty_array() {
+        let mut index = HashMap::new();
+        index.insert("null".to_string(), 0);
+        index.insert("empty".to_string(), 1);
+        let row = Row::new(
+            Arc::new(index),
+            Arc::new(vec![
+                field("null", Option::<Vec<i64>>::get_type()),
+                field("empty", Option::<Vec<i64>>::get_type()),
+            ]),
+            vec![
+                Value {
+                    kind: Some(None::<Vec<i64>>.to_kind()),
+                },
+                Value {
+                    kind: Some(Some(Vec::<i64>::new()).to_kind()),
+                },
+            ],
+        );
+        assert_eq!(Option::<Vec<i64>>::get_type(), array_type(i64::get_type()));
+        assert_eq!(row.column_by_name::<Option<Vec<i64>>>("null").unwrap(), None);
+        assert_eq!(row.column_by_name::<Option<Vec<i64>>>("empty").unwrap(), Some(vec![]));
+
+        // the NULL array is not conflated with the empty array.
+        assert!(row.column_by_name::<Vec<i64>>("null").is_err());
+        assert_eq!(row.column_by_name::<Vec<i64>>("empty").unwrap(), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn test_column_str() {
+        let mut index = HashMap::new();
+        index.insert("value".to_string(), 0);
+        index.insert("number".to_string(), 1);
+        let row = Row {
+            index: Arc::new(index),
+            fields: Arc::new(vec![
+                Field {
+                    name: "value".to_string(),
+                    r#type: Some(String::get_type()),
+                },
+                Field {
+                    name: "number".to_string(),
+                    r#type: Some(i64::get_type()),
+                },
+            ]),
+            values: vec![
+                Value {
+                    kind: Some("aaa".to_kind()),
+                },
+                Value {
+                    kind: Some(Kind::NumberValue(1.0)),
+                },
+            ],
+        };
+
+        let value = row.column_str_by_name("value").unwrap();
+        assert_eq!(value, "aaa");
+        // borrowed from the backing value
+        match row.values[0].kind.as_ref().unwrap() {
+            Kind::StringValue(s) => assert_eq!(value.as_ptr(), s.as_ptr()),
+            _ => unreachable!("must be string value"),
+        }
+        assert!(matches!(row.column_str(1), Err(Error::TypeMismatch { .. })));
+        assert!(matches!(row.column_str(2), Err(Error::InvalidColumnIndex(2, 2))));
+        assert!(matches!(row.column_str_by_name("none"), Err(Error::NoColumnFound(_))));
+    }
+
+    #[test]
+    fn test_try_from_nested_struct() {
+        // SELECT ARRAY(SELECT AS STRUCT id, name, tags, child, children FROM Parent) AS parents
+        let child_type = struct_type(vec![field("name", String::get_type())]);
+        let parent_type = struct_type(vec![
+            field("id", i64::get_type()),
+            field("name", String::get_type()),
+            field("tags", Vec::<i64>::get_type()),
+            field("child", child_type.clone()),
+            field("children", array_type(child_type)),
+        ]);
+        let mut index = HashMap::new();
+        index.insert("parents".to_string(), 0);
+
+        // the struct in the result set is encoded as the list of the values.
+        let parent = |id: i64, name: &str| {
+            list(vec![
+                id.to_kind(),
+                name.to_kind(),
+                vec![id, id * 10].to_kind(),
+                list(vec![format!("{name}-child").to_kind()]),
+                list(vec![list(vec!["c1".to_kind()]), list(vec!["c2".to_kind()])]),
+            ])
+        };
+        let row = Row {
+            index: Arc::new(index),
+            fields: Arc::new(vec![field("parents", array_type(parent_type))]),
+            values: vec![Value {
+                kind: Some(list(vec![parent(1, "a"), parent(2, "b")])),
+            }],
+        };
+
+        let parents = row.column_by_name::<Vec<Parent>>("parents").unwrap();
+        assert_eq!(2, parents.len());
+        assert_eq!(1, parents[0].id);
+        assert_eq!("a", parents[0].name);
+        assert_eq!(vec![1, 10], parents[0].tags);
+        assert_eq!("a-child", parents[0].child.name);
+        assert_eq!(2, parents[1].id);
+        assert_eq!("b", parents[1].name);
+        assert_eq!(vec![2, 20], parents[1].tags);
+        assert_eq!("b-child", parents[1].child.name);
+        assert_eq!(
+            vec!["c1", "c2"],
+            parents[1]
+                .children
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn test_get_struct() {
+        // SELECT STRUCT(1 AS id, 'a' AS name, [1, 10] AS tags, STRUCT('a-child' AS name, NULL AS age) AS child) AS parent
+        let child_type = struct_type(vec![field("name", String::get_type()), field("age", i64::get_type())]);
+        let parent_type = struct_type(vec![
+            field("id", i64::get_type()),
+            field("name", String::get_type()),
+            field("tags", Vec::<i64>::get_type()),
+            field("child", child_type),
+        ]);
+        let null = Kind::NullValue(prost_types::NullValue::NullValue.into());
+        let mut index = HashMap::new();
+        index.insert("parent".to_string(), 0);
+        index.insert("none".to_string(), 1);
+        let row = Row {
+            index: Arc::new(index),
+            fields: Arc::new(vec![field("parent", parent_type.clone()), field("none", parent_type)]),
+            values: vec![
+                Value {
+                    kind: Some(list(vec![
+                        1_i64.to_kind(),
+                        "a".to_kind(),
+                        vec![1_i64, 10].to_kind(),
+                        list(vec!["a-child".to_kind(), null.clone()]),
+                    ])),
+                },
+                Value { kind: Some(null) },
+            ],
+        };
+
+        let parent = row.get_struct_by_name("parent").unwrap();
+        assert_eq!(parent.len(), 4);
+        assert_eq!(
+            parent
+                .fields()
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["id", "name", "tags", "child"]
+        );
+        assert_eq!(parent.get("id"), Some(&DynamicValue::Int64(1)));
+        assert_eq!(parent.get_index(1), Some(&DynamicValue::String("a".to_string())));
+        assert_eq!(
+            parent.get("tags"),
+            Some(&DynamicValue::Array(vec![DynamicValue::Int64(1), DynamicValue::Int64(10)]))
+        );
+        let child = match parent.get("child") {
+            Some(DynamicValue::Struct(child)) => child,
+            other => panic!("must be struct: {other:?}"),
+        };
+        assert_eq!(
+            child,
+            &StructValue::new(vec![
+                ("name".to_string(), DynamicValue::String("a-child".to_string())),
+                ("age".to_string(), DynamicValue::Null),
+            ])
+        );
+        assert!(parent.get("unknown").is_none());
+
+        assert!(row.column_by_name::<Option<StructValue>>("none").unwrap().is_none());
+        assert!(matches!(row.get_struct(2), Err(Error::InvalidColumnIndex(2, 2))));
+    }
+
+    #[cfg(feature = "proto")]
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct TestMessage {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(int64, repeated, tag = "2")]
+        values: Vec<i64>,
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn test_try_from_proto() {
+        let message = ProtoMessage(TestMessage {
+            name: "aaa".to_string(),
+            values: vec![1, 2, 3],
+        });
+        let mut index = HashMap::new();
+        index.insert("message".to_string(), 0);
+        index.insert("enum".to_string(), 1);
+        let row = Row {
+            index: Arc::new(index),
+            fields: Arc::new(vec![
+                Field {
+                    name: "message".to_string(),
+                    r#type: Some(ProtoMessage::<TestMessage>::get_type()),
+                },
+                Field {
+                    name: "enum".to_string(),
+                    r#type: Some(ProtoEnum::get_type()),
+                },
+            ]),
+            values: vec![
+                Value {
+                    kind: Some(message.to_kind()),
+                },
+                Value {
+                    kind: Some(ProtoEnum(2).to_kind()),
+                },
+            ],
+        };
+        let decoded = row.column_by_name::<ProtoMessage<TestMessage>>("message").unwrap();
+        assert_eq!(message, decoded);
+        assert_eq!("aaa", decoded.name);
+        assert_eq!(ProtoEnum(2), row.column_by_name::<ProtoEnum>("enum").unwrap());
+    }
+
+    #[test]
+    fn test_unsupported_value_kind() {
+        // prost decodes the kind unknown to this version as None.
+        let unknown = Value { kind: None };
+        let fields = vec![
+            Field {
+                name: "Value".to_string(),
+                r#type: Some(String::get_type()),
+            },
+            Field {
+                name: "Values".to_string(),
+                r#type: Some(Vec::<String>::get_type()),
+            },
+        ];
+        let values = vec![
+            unknown.clone(),
+            Value {
+                kind: Some(Kind::ListValue(ListValue {
+                    values: vec![
+                        Value {
+                            kind: Some(Kind::StringValue("a".to_string())),
+                        },
+                        unknown.clone(),
+                    ],
+                })),
+            },
+        ];
+        let mut index = HashMap::new();
+        index.insert("Value".to_string(), 0);
+        index.insert("Values".to_string(), 1);
+        let row = Row::new(Arc::new(index), Arc::new(fields), values);
+
+        let assert_unsupported = |result: Result<(), Error>, expected: &str| match result {
+            Err(Error::UnsupportedValueKind(field, value)) => {
+                assert_eq!(field, expected);
+                assert_eq!(*value, unknown);
+            }
+            other => panic!("must be unsupported value kind: {other:?}"),
+        };
+        assert_unsupported(row.column_by_name::<String>("Value").map(|_| ()), "Value");
+        assert_unsupported(row.column_by_name::<Option<String>>("Value").map(|_| ()), "Value");
+        assert_unsupported(row.column_str(0).map(|_| ()), "Value");
+        assert_unsupported(row.column_by_name::<Vec<String>>("Values").map(|_| ()), "Values");
+    }
+
+    #[test]
+    fn test_kind_mismatch_message() {
+        let mut index = HashMap::new();
+        index.insert("age".to_string(), 0);
+        index.insert("ids".to_string(), 1);
+        let row = Row::new(
+            Arc::new(index),
+            Arc::new(vec![field("age", String::get_type()), field("ids", Vec::<i64>::get_type())]),
+            vec![
+                Value {
+                    kind: Some("twenty".to_kind()),
+                },
+                Value {
+                    kind: Some(vec![1_i64, 2].to_kind()),
+                },
+            ],
+        );
+        let message = |err: Error| err.to_string();
+        assert_eq!(
+            message(row.column_by_name::<i64>("age").unwrap_err()),
+            "column `age`: expected i64-decodable, found STRING (StringValue)"
+        );
+        assert_eq!(
+            message(row.column_by_name::<bool>("age").unwrap_err()),
+            "column `age`: expected bool-decodable, found STRING (StringValue)"
+        );
+        assert_eq!(
+            message(row.column_by_name::<Vec<Option<String>>>("age").unwrap_err()),
+            "column `age`: expected Vec<Option<String>>-decodable, found STRING (StringValue)"
+        );
+        assert_eq!(
+            message(row.column_by_name::<f64>("ids").unwrap_err()),
+            "column `ids`: expected f64-decodable, found ARRAY<INT64> (ListValue)"
+        );
+        // the INT64 failing to parse is not a mismatch of the types.
+        let row = Row::new(
+            Arc::new(HashMap::from([("age".to_string(), 0)])),
+            Arc::new(vec![field("age", i64::get_type())]),
+            vec![Value {
+                kind: Some("twenty".to_kind()),
+            }],
+        );
+        assert!(matches!(
+            row.column_by_name::<i64>("age"),
+            Err(Error::IntParseError(name, _)) if name == "age"
+        ));
+    }
+
+    #[test]
+    fn test_interval_round_trip() {
+        let values = [
+            (Interval::new(14, 3, 14_706_500_000_000), "P1Y2M3DT4H5M6.5S"),
+            (Interval::new(-14, -3, -5_400_000_000_001), "P-1Y-2M-3DT-1H-30M-0.000000001S"),
+            (Interval::new(1, 0, 0), "P1M"),
+            (Interval::new(0, 0, 1_000_000), "PT0.001S"),
+            (Interval::new(0, 40, 0), "P40D"),
+            (Interval::default(), "P0Y"),
+        ];
+        let row = Row::new(
+            Arc::new(HashMap::from([("interval".to_string(), 0)])),
+            Arc::new(vec![field("interval", Interval::get_type())]),
+            vec![Value { kind: None }],
+        );
+        for (interval, iso) in values {
+            assert_eq!(interval.to_kind(), Kind::StringValue(iso.to_string()));
+            let row = Row {
+                values: vec![Value {
+                    kind: Some(interval.to_kind()),
+                }],
+                ..row.clone()
+            };
+            assert_eq!(row.column_by_name::<Interval>("interval").unwrap(), interval);
+        }
+        assert_eq!(Interval::get_type().code, 16);
+
+        // the non-canonical forms are accepted.
+        let parse = |s: &str| s.parse::<Interval>();
+        assert_eq!(parse("P2W").unwrap(), Interval::new(0, 14, 0));
+        assert_eq!(parse("PT36H").unwrap(), Interval::new(0, 0, 36 * 3_600_000_000_000));
+        assert_eq!(parse("P1Y-1M").unwrap(), Interval::new(11, 0, 0));
+        assert_eq!(parse("PT1,5S").unwrap(), Interval::new(0, 0, 1_500_000_000));
+        for invalid in [
+            "",
+            "P",
+            "1Y",
+            "PT",
+            "P1H",
+            "PT1D",
+            "P1.5Y",
+            "PT1.0000000001S",
+            "P99999999999Y",
+            "P1é",
+            "PT1é5S",
+        ] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
 }
@@ -14,8 +14,10 @@ use tokio::time::{sleep, timeout};
 
 use google_cloud_gax::cancel::CancellationToken;
 use google_cloud_gax::grpc::{Code, Status};
-use google_cloud_gax::retry::TryAs;
-use google_cloud_googleapis::spanner::v1::{BatchCreateSessionsRequest, DeleteSessionRequest, Session};
+use google_cloud_gax::retry::{RetrySetting, TryAs};
+use google_cloud_googleapis::spanner::v1::{
+    BatchCreateSessionsRequest, DeleteSessionRequest, GetSessionRequest, Session,
+};
 
 use crate::apiv1::conn_pool::ConnectionManager;
 use crate::apiv1::spanner_client::{ping_query_request, Client};
@@ -24,6 +26,7 @@ use crate::apiv1::spanner_client::{ping_query_request, Client};
 pub struct SessionHandle {
     pub session: Session,
     pub spanner_client: Client,
+    channel_index: usize,
     valid: bool,
     deleted: bool,
     last_used_at: Instant,
@@ -33,10 +36,11 @@ pub struct SessionHandle {
 }
 
 impl SessionHandle {
-    pub(crate) fn new(session: Session, spanner_client: Client, now: Instant) -> SessionHandle {
+    pub(crate) fn new(session: Session, spanner_client: Client, channel_index: usize, now: Instant) -> SessionHandle {
         SessionHandle {
             session,
             spanner_client,
+            channel_index,
             valid: true,
             deleted: false,
             last_used_at: now,
@@ -50,7 +54,7 @@ impl SessionHandle {
         match arg {
             Ok(s) => Ok(s),
             Err(e) => {
-                if e.code() == Code::NotFound && e.message().contains("Session not found:") {
+                if is_session_not_found(&e) {
                     tracing::debug!("session invalidate {}", self.session.name);
                     self.delete().await;
                 }
@@ -59,6 +63,39 @@ impl SessionHandle {
         }
     }
 
+    /// invalidate marks the session deleted on the server side, so that the pool discards it without DeleteSession.
+    pub(crate) fn invalidate(&mut self) {
+        self.valid = false;
+        self.deleted = true;
+    }
+
+    /// exists checks the session with GetSession. The session is invalidated only when the server
+    /// reports NOT_FOUND, any other error is regarded as transient and the session is kept.
+    async fn exists(&mut self) -> bool {
+        let request = GetSessionRequest {
+            name: self.session.name.to_string(),
+        };
+        match self.spanner_client.get_session(request, None, None).await {
+            Err(e) if e.code() == Code::NotFound => {
+                tracing::debug!("session not found {}", self.session.name);
+                self.valid = false;
+                self.deleted = true;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// is_idle_longer_than returns true if the session has been neither used nor pinged for the duration.
+    fn is_idle_longer_than(&self, duration: Duration, now: Instant) -> bool {
+        std::cmp::max(self.last_used_at, self.last_pong_at) + duration < now
+    }
+
+    /// is_older_than returns true if the session was created more than the duration ago.
+    fn is_older_than(&self, duration: Duration, now: Instant) -> bool {
+        self.created_at + duration < now
+    }
+
     async fn delete(&mut self) {
         self.valid = false;
         let session_name = &self.session.name;
@@ -72,6 +109,12 @@ impl SessionHandle {
     }
 }
 
+/// is_session_not_found returns true if the session was deleted on the server side.
+/// It is distinguished from the other NotFound errors such as the table or the row not found.
+pub(crate) fn is_session_not_found(status: &Status) -> bool {
+    status.code() == Code::NotFound && status.message().contains("Session not found:")
+}
+
 /// ManagedSession
 pub struct ManagedSession {
     session_pool: SessionPool,
@@ -123,6 +166,15 @@ struct Sessions {
 
     /// number of sessions scheduled to be replenished.
     num_creating: usize,
+
+    /// number of sessions per gRPC channel including the sessions scheduled to be replenished.
+    num_per_channel: Vec<usize>,
+
+    /// maximum number of sessions per gRPC channel.
+    max_sessions_per_channel: usize,
+
+    /// number of evicted sessions to be replaced to keep min_opened.
+    num_evicted: usize,
 }
 
 impl Sessions {
@@ -130,6 +182,11 @@ impl Sessions {
         self.num_inuse + self.available_sessions.len()
     }
 
+    /// is_exhausted returns true if no session is available and no more sessions can be created.
+    fn is_exhausted(&self, max_opened: usize) -> bool {
+        self.available_sessions.is_empty() && self.num_opened() + self.num_creating >= max_opened
+    }
+
     fn take_waiter(&mut self) -> Option<oneshot::Sender<SessionHandle>> {
         while let Some(waiter) = self.waiters.pop_front() {
             // Waiter can be closed when session acquisition times out.
@@ -150,19 +207,30 @@ impl Sessions {
         }
     }
 
+    /// has_unmet_demand returns true if the sessions in creation are not enough for the waiters.
+    fn has_unmet_demand(&self) -> bool {
+        self.waiters.len() > self.num_creating
+    }
+
     fn release(&mut self, session: SessionHandle) {
         self.num_inuse -= 1;
         if session.valid {
             self.available_sessions.push_back(session);
-        } else if !session.deleted {
+            return;
+        }
+        self.num_per_channel[session.channel_index] -= 1;
+        self.num_evicted += 1;
+        if !session.deleted {
             tracing::trace!("save as orphan name={}", session.session.name);
             self.orphans.push(session);
         }
     }
 
-    /// reserve calculates next session count to create.
-    /// Must call replenish after calling this method.
-    fn reserve(&mut self, max_opened: usize, inc_step: usize) -> usize {
+    /// reserve calculates next session count to create and the channels to create them on.
+    /// No more than max_creating sessions are created at the same time.
+    /// Returns the pairs of channel index and session count.
+    /// Must call replenish for each pair after calling this method.
+    fn reserve(&mut self, max_opened: usize, inc_step: usize, max_creating: usize) -> Vec<(usize, usize)> {
         let num_opened = self.num_opened();
         let num_creating = self.num_creating;
         if max_opened < num_creating + num_opened {
@@ -172,18 +240,85 @@ impl Sessions {
                 num_creating,
                 num_opened
             );
-            return 0;
+            return vec![];
+        }
+        if max_creating <= num_creating {
+            tracing::trace!("Too many sessions in creation max_creating={}", max_creating);
+            return vec![];
         }
         let mut increasing = max_opened - (num_creating + num_opened);
         if increasing > inc_step {
             increasing = inc_step
         }
-        self.num_creating += increasing;
-        increasing
+        if increasing > max_creating - num_creating {
+            increasing = max_creating - num_creating
+        }
+        let allocation = self.allocate(increasing);
+        if allocation.is_empty() {
+            tracing::trace!(
+                "No available channels max_sessions_per_channel={}",
+                self.max_sessions_per_channel
+            );
+        }
+        self.num_creating += allocation.iter().map(|(_, count)| count).sum::<usize>();
+        allocation
     }
 
-    fn replenish(&mut self, session_count: usize, result: Result<Vec<SessionHandle>, Status>) {
+    /// allocate assigns the new sessions to the channels with the fewest sessions,
+    /// so that no channel holds more than max_sessions_per_channel sessions.
+    fn allocate(&mut self, session_count: usize) -> Vec<(usize, usize)> {
+        let mut allocation: Vec<(usize, usize)> = Vec::new();
+        for _ in 0..session_count {
+            let least_busy = self
+                .num_per_channel
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count < self.max_sessions_per_channel)
+                .min_by_key(|(_, count)| **count)
+                .map(|(channel_index, _)| channel_index);
+            let channel_index = match least_busy {
+                Some(channel_index) => channel_index,
+                None => break,
+            };
+            self.num_per_channel[channel_index] += 1;
+            match allocation.iter_mut().find(|(index, _)| *index == channel_index) {
+                Some(entry) => entry.1 += 1,
+                None => allocation.push((channel_index, 1)),
+            }
+        }
+        allocation
+    }
+
+    /// reserve_replacement calculates the sessions replacing the evicted ones, up to max_sessions at once
+    /// and no more than needed to keep min_opened.
+    /// Must call replenish for each pair after calling this method.
+    fn reserve_replacement(
+        &mut self,
+        min_opened: usize,
+        max_sessions: usize,
+        max_creating: usize,
+    ) -> Vec<(usize, usize)> {
+        let missing = min_opened.saturating_sub(self.num_opened() + self.num_creating);
+        self.num_evicted = self.num_evicted.min(missing);
+        let count = self
+            .num_evicted
+            .min(max_sessions)
+            .min(max_creating.saturating_sub(self.num_creating));
+        if count == 0 {
+            return vec![];
+        }
+        let allocation = self.allocate(count);
+        let reserved = allocation.iter().map(|(_, count)| count).sum::<usize>();
+        self.num_creating += reserved;
+        self.num_evicted -= reserved;
+        allocation
+    }
+
+    fn replenish(&mut self, channel_index: usize, session_count: usize, result: Result<Vec<SessionHandle>, Status>) {
         self.num_creating -= session_count;
+        if result.is_err() {
+            self.num_per_channel[channel_index] -= session_count;
+        }
         match result {
             Ok(mut new_sessions) => {
                 while let Some(session) = new_sessions.pop() {
@@ -210,7 +345,7 @@ impl Sessions {
 #[derive(Clone)]
 struct SessionPool {
     inner: Arc<RwLock<Sessions>>,
-    session_creation_sender: UnboundedSender<usize>,
+    session_creation_sender: UnboundedSender<Vec<(usize, usize)>>,
     config: Arc<SessionConfig>,
 }
 
@@ -218,10 +353,14 @@ impl SessionPool {
     async fn new(
         database: String,
         conn_pool: &ConnectionManager,
-        session_creation_sender: UnboundedSender<usize>,
+        session_creation_sender: UnboundedSender<Vec<(usize, usize)>>,
         config: Arc<SessionConfig>,
     ) -> Result<Self, Status> {
-        let available_sessions = Self::init_pool(database, conn_pool, config.min_opened).await?;
+        let available_sessions = Self::init_pool(database, conn_pool, &config).await?;
+        let mut num_per_channel = vec![0; conn_pool.num()];
+        for session in available_sessions.iter() {
+            num_per_channel[session.channel_index] += 1;
+        }
         Ok(SessionPool {
             inner: Arc::new(RwLock::new(Sessions {
                 available_sessions,
@@ -229,6 +368,9 @@ impl SessionPool {
                 orphans: Vec::new(),
                 num_inuse: 0,
                 num_creating: 0,
+                num_per_channel,
+                max_sessions_per_channel: config.max_sessions_per_channel,
+                num_evicted: 0,
             })),
             session_creation_sender,
             config,
@@ -238,16 +380,23 @@ impl SessionPool {
     async fn init_pool(
         database: String,
         conn_pool: &ConnectionManager,
-        min_opened: usize,
+        config: &SessionConfig,
     ) -> Result<VecDeque<SessionHandle>, Status> {
         let channel_num = conn_pool.num();
-        let creation_count_per_channel = min_opened / channel_num;
+        let creation_count_per_channel =
+            std::cmp::min(config.min_opened / channel_num, config.max_sessions_per_channel);
 
         let mut sessions = Vec::<SessionHandle>::new();
-        for _ in 0..channel_num {
-            let next_client = conn_pool.conn();
-            let new_sessions =
-                batch_create_sessions(next_client, database.as_str(), creation_count_per_channel).await?;
+        for channel_index in 0..channel_num {
+            let next_client = conn_pool.conn_at(channel_index);
+            let new_sessions = batch_create_sessions(
+                next_client,
+                channel_index,
+                database.as_str(),
+                creation_count_per_channel,
+                config.create_retry.clone(),
+            )
+            .await?;
             sessions.extend(new_sessions);
         }
         tracing::debug!("initial session created count = {}", sessions.len());
@@ -265,7 +414,41 @@ impl SessionPool {
     /// The client on the waiting list will be notified when another client's session has finished and
     /// when the process of replenishing the available sessions is complete.
     async fn acquire(&self) -> Result<ManagedSession, SessionError> {
-        let (on_session_acquired, session_count) = {
+        loop {
+            let idle = {
+                let mut sessions = self.inner.write();
+                // Prioritize waiters over new acquirers.
+                if sessions.waiters.is_empty() {
+                    sessions.take()
+                } else {
+                    None
+                }
+            };
+            let mut s = match idle {
+                Some(s) => s,
+                None => return self.acquire_or_wait().await,
+            };
+            if s.is_older_than(self.config.max_age, Instant::now()) {
+                // Recreate the session before the server expires it.
+                tracing::debug!("session exceeds max age {}", s.session.name);
+                s.delete().await;
+                self.recycle(s);
+                continue;
+            }
+            if let Some(threshold) = self.config.validate_idle_after {
+                if s.is_idle_longer_than(threshold, Instant::now()) && !s.exists().await {
+                    // The session is discarded by the recycle since it is no longer valid.
+                    self.recycle(s);
+                    continue;
+                }
+            }
+            s.last_used_at = Instant::now();
+            return Ok(ManagedSession::new(self.clone(), s));
+        }
+    }
+
+    async fn acquire_or_wait(&self) -> Result<ManagedSession, SessionError> {
+        let (on_session_acquired, allocation, wait_timeout) = {
             let mut sessions = self.inner.write();
 
             // Prioritize waiters over new acquirers.
@@ -275,19 +458,28 @@ impl SessionPool {
                     return Ok(ManagedSession::new(self.clone(), s));
                 }
             }
+            let exhausted = sessions.is_exhausted(self.config.max_opened);
+            if self.config.pool_exhausted_policy == PoolExhaustedPolicy::FailFast && exhausted {
+                return Err(SessionError::SessionPoolExhausted);
+            }
             // Add the participant to the waiting list.
             let (sender, receiver) = oneshot::channel();
             sessions.waiters.push_back(sender);
-            let session_count = sessions.reserve(self.config.max_opened, self.config.inc_step);
-            (receiver, session_count)
+            // Create new sessions only when the sessions in creation can not serve all the waiters.
+            let allocation = if sessions.has_unmet_demand() {
+                sessions.reserve(self.config.max_opened, self.config.inc_step, self.config.max_creating)
+            } else {
+                vec![]
+            };
+            (receiver, allocation, wait_timeout(&self.config, exhausted))
         };
 
-        if session_count > 0 {
-            let _ = self.session_creation_sender.send(session_count);
+        if !allocation.is_empty() {
+            let _ = self.session_creation_sender.send(allocation);
         }
 
         // Wait for the session available notification.
-        match timeout(self.config.session_get_timeout, on_session_acquired).await {
+        match timeout(wait_timeout, on_session_acquired).await {
             Ok(Ok(mut session)) => {
                 session.last_used_at = Instant::now();
                 Ok(ManagedSession {
@@ -295,6 +487,10 @@ impl SessionPool {
                     session: Some(session),
                 })
             }
+            // All the sessions are still in use, rather than being created, after the acquire_timeout.
+            _ if self.config.acquire_timeout.is_some() && self.inner.read().is_exhausted(self.config.max_opened) => {
+                Err(SessionError::SessionPoolExhausted)
+            }
             _ => Err(SessionError::SessionGetTimeout),
         }
     }
@@ -326,17 +522,17 @@ impl SessionPool {
                 }
             };
         } else {
-            let session_count = {
+            let allocation = {
                 let mut sessions = self.inner.write();
                 sessions.release(session);
                 if sessions.num_opened() < self.config.min_opened && !sessions.waiters.is_empty() {
-                    sessions.reserve(self.config.max_opened, self.config.inc_step)
+                    sessions.reserve(self.config.max_opened, self.config.inc_step, self.config.max_creating)
                 } else {
-                    0
+                    vec![]
                 }
             };
-            if session_count > 0 {
-                let _ = self.session_creation_sender.send(session_count);
+            if !allocation.is_empty() {
+                let _ = self.session_creation_sender.send(allocation);
             }
         }
     }
@@ -374,7 +570,7 @@ pub struct SessionConfig {
     /// number of opened connections drops below min_opened. However, if a session
     /// is found to be broken, it will still be evicted from the session pool,
     /// therefore it is posssible that the number of opened sessions drops below
-    /// min_opened.
+    /// min_opened until the evicted sessions are replaced at replacement_pacing.
     pub min_opened: usize,
 
     /// max_idle is the maximum number of idle sessions, pool is allowed to keep.
@@ -388,11 +584,55 @@ pub struct SessionConfig {
     pub session_alive_trust_duration: Duration,
 
     /// session_get_timeout is the maximum value of the waiting time that occurs when retrieving from the connection pool when there is no idle session.
+    /// SessionError::SessionGetTimeout is returned when the time is exceeded.
     pub session_get_timeout: Duration,
 
+    /// pool_exhausted_policy is the behavior when all the max_opened sessions are in use.
+    pub pool_exhausted_policy: PoolExhaustedPolicy,
+
+    /// acquire_timeout is the maximum time to wait for a session to be returned to the exhausted pool
+    /// with PoolExhaustedPolicy::Wait. SessionError::SessionPoolExhausted is returned when the time is exceeded.
+    /// None uses session_get_timeout, after which SessionError::SessionGetTimeout is returned.
+    pub acquire_timeout: Option<Duration>,
+
     /// refresh_interval is the interval of cleanup and health check functions.
     pub refresh_interval: Duration,
 
+    /// max_sessions_per_channel is the maximum number of sessions assigned to one gRPC channel.
+    /// Each channel has a limit of concurrent HTTP/2 streams, so new sessions are spread
+    /// over the channels with the fewest sessions without exceeding this value.
+    pub max_sessions_per_channel: usize,
+
+    /// validate_idle_after enables the existence check of the idle sessions.
+    /// A session that has been idle longer than this value is checked with GetSession before it is handed out,
+    /// and is evicted if the server no longer knows it. The check is done on the acquiring task, not in the background.
+    /// None disables the check.
+    pub validate_idle_after: Option<Duration>,
+
+    /// max_creating is the maximum number of sessions being created at the same time.
+    /// Under burst load the pool creates the sessions in batches of up to inc_step while the waiters
+    /// outnumber the sessions in creation, but never more than this value at once nor beyond max_opened.
+    pub max_creating: usize,
+
+    /// max_age is the maximum lifetime of a session.
+    /// A session older than this value since it was created is deleted and replaced with a new one
+    /// when it is acquired, so that it is not used near the boundary where Cloud Spanner may delete it.
+    /// Cloud Spanner may delete a session older than 28 days.
+    pub max_age: Duration,
+
+    /// create_retry is the retry setting of the BatchCreateSessions RPC, so that the transient error such as
+    /// UNAVAILABLE during a scale-up burst does not fail the caller acquiring the session.
+    /// The sessions the server may have created for the failed attempt are unknown to the pool,
+    /// and are deleted by Cloud Spanner after they are idle for an hour.
+    /// None uses the default setting retrying UNAVAILABLE and UNKNOWN.
+    pub create_retry: Option<RetrySetting>,
+
+    /// replacement_pacing is the rate of creating the sessions replacing the evicted ones, such as the sessions
+    /// found broken by the health check, while the pool has less than min_opened sessions.
+    /// The replacements are paced so that the recovery from a mass eviction, e.g. by a backend restart,
+    /// does not flood the server with BatchCreateSessions.
+    pub replacement_pacing: ReplacementPacing,
+
     /// incStep is the number of sessions to create in one batch when at least
     /// one more session is needed.
     inc_step: usize,
@@ -409,14 +649,62 @@ impl Default for SessionConfig {
             session_alive_trust_duration: Duration::from_secs(55 * 60),
             session_get_timeout: Duration::from_secs(1),
             refresh_interval: Duration::from_secs(5 * 60),
+            max_sessions_per_channel: 100,
+            validate_idle_after: None,
+            max_creating: 100,
+            max_age: Duration::from_secs(27 * 24 * 60 * 60),
+            create_retry: None,
+            pool_exhausted_policy: PoolExhaustedPolicy::Wait,
+            acquire_timeout: None,
+            replacement_pacing: ReplacementPacing::default(),
         }
     }
 }
 
+/// wait_timeout returns the maximum time to wait for a session. The acquire_timeout is used
+/// if the pool is exhausted, otherwise the session being created is waited for up to session_get_timeout.
+fn wait_timeout(config: &SessionConfig, exhausted: bool) -> Duration {
+    match config.acquire_timeout {
+        Some(acquire_timeout) if exhausted => acquire_timeout,
+        _ => config.session_get_timeout,
+    }
+}
+
+/// ReplacementPacing is the rate of creating the sessions replacing the evicted ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplacementPacing {
+    /// max_sessions is the maximum number of the replacements created in one interval. 0 disables the replacement.
+    pub max_sessions: usize,
+    /// interval is the interval of creating the replacements.
+    pub interval: Duration,
+}
+
+impl Default for ReplacementPacing {
+    fn default() -> Self {
+        ReplacementPacing {
+            max_sessions: 25,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// PoolExhaustedPolicy is the behavior of the session pool when there is neither an idle session nor room to create one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolExhaustedPolicy {
+    /// Wait waits up to acquire_timeout for a session to be returned to the pool,
+    /// and returns SessionError::SessionPoolExhausted if none is returned.
+    /// Without acquire_timeout, it waits up to session_get_timeout and returns SessionError::SessionGetTimeout.
+    Wait,
+    /// FailFast returns SessionError::SessionPoolExhausted immediately.
+    FailFast,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SessionError {
     #[error("session get time out")]
     SessionGetTimeout,
+    #[error("session pool exhausted")]
+    SessionPoolExhausted,
     #[error("failed to create session")]
     FailedToCreateSession,
     #[error(transparent)]
@@ -449,14 +737,23 @@ impl SessionManager {
         let session_pool = SessionPool::new(database.clone(), &conn_pool, sender, Arc::new(config.clone())).await?;
 
         let cancel = CancellationToken::new();
+        let replacement_pacing = config.replacement_pacing;
         let task_session_cleaner = Self::spawn_health_check_task(config, session_pool.clone(), cancel.clone());
         let task_session_creator =
             Self::spawn_session_creation_task(session_pool.clone(), database, conn_pool, receiver, cancel.clone());
+        let mut tasks = vec![task_session_cleaner, task_session_creator];
+        if replacement_pacing.max_sessions > 0 {
+            tasks.push(Self::spawn_replacement_task(
+                replacement_pacing,
+                session_pool.clone(),
+                cancel.clone(),
+            ));
+        }
 
         let sm = SessionManager {
             session_pool,
             cancel,
-            tasks: Mutex::new(vec![task_session_cleaner, task_session_creator]),
+            tasks: Mutex::new(tasks),
         };
         Ok(Arc::new(sm))
     }
@@ -485,25 +782,62 @@ impl SessionManager {
         session_pool: SessionPool,
         database: String,
         conn_pool: ConnectionManager,
-        mut rx: UnboundedReceiver<usize>,
+        mut rx: UnboundedReceiver<Vec<(usize, usize)>>,
         cancel: CancellationToken,
     ) -> JoinHandle<()> {
         tokio::spawn(async move {
             loop {
-                let session_count: usize = select! {
-                    session_count = rx.recv() => match session_count {
-                        Some(session_count) => session_count,
+                let allocation = select! {
+                    allocation = rx.recv() => match allocation {
+                        Some(allocation) => allocation,
                         None => continue
                     },
                     _ = cancel.cancelled() => break
                 };
-                let result = batch_create_sessions(conn_pool.conn(), database.as_str(), session_count).await;
-                session_pool.inner.write().replenish(session_count, result);
+                for (channel_index, session_count) in allocation {
+                    let client = conn_pool.conn_at(channel_index);
+                    let retry = session_pool.config.create_retry.clone();
+                    let result =
+                        batch_create_sessions(client, channel_index, database.as_str(), session_count, retry).await;
+                    session_pool
+                        .inner
+                        .write()
+                        .replenish(channel_index, session_count, result);
+                }
             }
             tracing::trace!("shutdown session creation task.");
         })
     }
 
+    /// spawn_replacement_task creates up to max_sessions replacements of the evicted sessions every interval.
+    fn spawn_replacement_task(
+        pacing: ReplacementPacing,
+        session_pool: SessionPool,
+        cancel: CancellationToken,
+    ) -> JoinHandle<()> {
+        let period = pacing.interval.max(Duration::from_millis(1));
+        let mut interval = tokio::time::interval_at((Instant::now() + period).into(), period);
+
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    _ = interval.tick() => {},
+                    _ = cancel.cancelled() => break
+                }
+                let allocation = session_pool.inner.write().reserve_replacement(
+                    session_pool.config.min_opened,
+                    pacing.max_sessions,
+                    session_pool.config.max_creating,
+                );
+                if !allocation.is_empty() {
+                    tracing::debug!("replace evicted sessions {:?}", allocation);
+                    let _ = session_pool.session_creation_sender.send(allocation);
+                }
+            }
+            tracing::trace!("shutdown session replacement task.")
+        })
+    }
+
     fn spawn_health_check_task(
         config: SessionConfig,
         session_pool: SessionPool,
@@ -590,12 +924,21 @@ async fn health_check(
 
 async fn batch_create_sessions(
     spanner_client: Client,
+    channel_index: usize,
     database: &str,
     mut remaining_create_count: usize,
+    retry: Option<RetrySetting>,
 ) -> Result<Vec<SessionHandle>, Status> {
     let mut created = Vec::with_capacity(remaining_create_count);
     while remaining_create_count > 0 {
-        let sessions = batch_create_session(spanner_client.clone(), database, remaining_create_count).await?;
+        let sessions = batch_create_session(
+            spanner_client.clone(),
+            channel_index,
+            database,
+            remaining_create_count,
+            retry.clone(),
+        )
+        .await?;
         // Spanner could return less sessions than requested.
         // In that case, we should do another call using the same gRPC channel.
         let actually_created = sessions.len();
@@ -607,8 +950,10 @@ async fn batch_create_sessions(
 
 async fn batch_create_session(
     mut spanner_client: Client,
+    channel_index: usize,
     database: &str,
     session_count: usize,
+    retry: Option<RetrySetting>,
 ) -> Result<Vec<SessionHandle>, Status> {
     let request = BatchCreateSessionsRequest {
         database: database.to_string(),
@@ -618,7 +963,7 @@ async fn batch_create_session(
 
     tracing::debug!("spawn session creation request : session_count = {}", session_count);
     let response = spanner_client
-        .batch_create_sessions(request, None, None)
+        .batch_create_sessions(request, None, retry)
         .await?
         .into_inner();
 
@@ -626,26 +971,37 @@ async fn batch_create_session(
     Ok(response
         .session
         .into_iter()
-        .map(|s| SessionHandle::new(s, spanner_client.clone(), now))
+        .map(|s| SessionHandle::new(s, spanner_client.clone(), channel_index, now))
         .collect::<Vec<SessionHandle>>())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
     use std::sync::atomic::{AtomicI64, Ordering};
     use std::sync::Arc;
     use std::time::{Duration, Instant};
 
     use parking_lot::RwLock;
     use serial_test::serial;
-    use tokio::time::sleep;
+    use tokio::sync::{mpsc, oneshot};
+    use tokio::time::{sleep, timeout};
 
     use google_cloud_gax::cancel::CancellationToken;
     use google_cloud_gax::conn::Environment;
-    use google_cloud_googleapis::spanner::v1::ExecuteSqlRequest;
+    use google_cloud_gax::grpc::{Code, Status};
+    use google_cloud_gax::retry::RetrySetting;
+    use google_cloud_googleapis::spanner::v1::{
+        BatchCreateSessionsRequest, DeleteSessionRequest, ExecuteSqlRequest, GetSessionRequest,
+    };
 
     use crate::apiv1::conn_pool::ConnectionManager;
-    use crate::session::{batch_create_sessions, health_check, SessionConfig, SessionError, SessionManager};
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::session::{
+        batch_create_sessions, health_check, wait_timeout, PoolExhaustedPolicy, ReplacementPacing, SessionConfig,
+        SessionError, SessionManager, SessionPool, Sessions,
+    };
+    use crate::transaction_rw::{BeginError, BeginRetrySession};
 
     pub const DATABASE: &str = "projects/local-project/instances/test-instance/databases/local-database";
 
@@ -730,6 +1086,145 @@ mod tests {
         cancel.cancel();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_validate_idle_session_not_found() {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 1,
+            validate_idle_after: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config).await.unwrap();
+
+        // The session handed out before the idle time is not validated.
+        let name = (*sm.get().await.unwrap()).session.name.to_string();
+        assert!(fake.requests::<GetSessionRequest>("GetSession").is_empty());
+        sleep(Duration::from_millis(100)).await;
+
+        // The long idle session is validated and handed out if it still exists.
+        let validated = (*sm.get().await.unwrap()).session.name.to_string();
+        assert_eq!(validated, name);
+        let gets = fake.requests::<GetSessionRequest>("GetSession");
+        assert_eq!(gets.len(), 1);
+        assert_eq!(gets[0].1.name, name);
+        sleep(Duration::from_millis(100)).await;
+
+        // The session deleted by the server must be evicted instead of being handed out.
+        fake.fail("GetSession", Status::new(Code::NotFound, "Session not found"));
+        let session = sm.get().await.unwrap();
+        assert_ne!((*session).session.name, name);
+        assert_eq!(fake.requests::<GetSessionRequest>("GetSession").len(), 2);
+        assert_eq!(sm.num_opened(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_begin_error_session_disposition() {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 1,
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config).await.unwrap();
+
+        // The transient error keeps the session for the retry.
+        let session = sm.get().await.unwrap();
+        let name = (*session).session.name.to_string();
+        let err = BeginError {
+            status: Status::new(Code::Unavailable, "transient"),
+            session,
+        };
+        let (status, session) = err.into_reusable();
+        assert_eq!(status.code(), Code::Unavailable);
+        let session = session.unwrap();
+        assert_eq!((*session).session.name, name);
+        drop(session);
+        assert_eq!(sm.num_opened(), 1);
+
+        // The session not found is discarded.
+        let session = sm.get().await.unwrap();
+        assert_eq!((*session).session.name, name);
+        let err = BeginError {
+            status: Status::new(Code::NotFound, format!("Session not found: {name}")),
+            session,
+        };
+        let (status, session) = err.into_reusable();
+        assert_eq!(status.code(), Code::NotFound);
+        assert!(session.is_none());
+        assert_eq!(sm.num_opened(), 0);
+
+        let session = sm.get().await.unwrap();
+        assert_ne!((*session).session.name, name);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_begin_retry_session() {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 1,
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config).await.unwrap();
+
+        // The transient error returns the session to the pool to be replaced.
+        let session = sm.get().await.unwrap();
+        let name = (*session).session.name.to_string();
+        let err = BeginError {
+            status: Status::new(Code::Unavailable, "transient"),
+            session,
+        };
+        let (status, session) = err.into_retryable(BeginRetrySession::Replace);
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(session.is_none());
+        assert_eq!(sm.num_opened(), 1);
+
+        // The transient error keeps the session with Reuse.
+        let session = sm.get().await.unwrap();
+        let err = BeginError {
+            status: Status::new(Code::Unavailable, "transient"),
+            session,
+        };
+        let (_, session) = err.into_retryable(BeginRetrySession::Reuse);
+        assert_eq!((*session.unwrap()).session.name, name);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_recreate_session_exceeding_max_age() {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 1,
+            max_age: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config).await.unwrap();
+
+        let old_name = (*sm.get().await.unwrap()).session.name.to_string();
+        sleep(Duration::from_millis(100)).await;
+
+        // The over-age session must be deleted and recreated instead of being handed out.
+        let session = sm.get().await.unwrap();
+        assert_ne!((*session).session.name, old_name);
+        assert_eq!(sm.num_opened(), 1);
+        let deleted = fake.requests::<DeleteSessionRequest>("DeleteSession");
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].1.name, old_name);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     #[serial]
     async fn test_increase_session_and_idle_session_expired() {
@@ -1036,6 +1531,31 @@ mod tests {
         assert_eq!(sm.session_pool.inner.read().orphans.len(), 0);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_create_sessions_retry_unavailable() {
+        let fake = FakeSpanner::default();
+        fake.fail("BatchCreateSessions", Status::new(Code::Unavailable, "unavailable"));
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            create_retry: Some(RetrySetting {
+                from_millis: 1,
+                codes: vec![Code::Unavailable],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config.clone()).await.unwrap();
+
+        // the UNAVAILABLE is retried and the pool is filled.
+        assert_eq!(sm.num_opened(), config.min_opened);
+        let requests = fake.requests::<BatchCreateSessionsRequest>("BatchCreateSessions");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].1.session_count as usize, config.min_opened);
+        sm.close().await;
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     #[serial]
     async fn test_batch_create_sessions() {
@@ -1044,7 +1564,7 @@ mod tests {
             .unwrap();
         let client = cm.conn();
         let session_count = 125;
-        let result = batch_create_sessions(client.clone(), DATABASE, session_count).await;
+        let result = batch_create_sessions(client.clone(), 0, DATABASE, session_count, None).await;
         match result {
             Ok(created) => {
                 assert_eq!(session_count, created.len());
@@ -1075,4 +1595,301 @@ mod tests {
             Err(err) => panic!("{err:?}"),
         }
     }
+
+    #[test]
+    fn test_wait_timeout() {
+        let config = SessionConfig {
+            session_get_timeout: Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(wait_timeout(&config, true), Duration::from_secs(1));
+        assert_eq!(wait_timeout(&config, false), Duration::from_secs(1));
+
+        let config = SessionConfig {
+            acquire_timeout: Some(Duration::from_secs(5)),
+            ..config
+        };
+        assert_eq!(wait_timeout(&config, true), Duration::from_secs(5));
+        assert_eq!(wait_timeout(&config, false), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_reserve_spread_across_channels() {
+        let mut sessions = Sessions {
+            available_sessions: VecDeque::new(),
+            waiters: VecDeque::new(),
+            orphans: Vec::new(),
+            num_inuse: 0,
+            num_creating: 0,
+            num_per_channel: vec![0; 4],
+            max_sessions_per_channel: 2,
+            num_evicted: 0,
+        };
+        assert_eq!(sessions.reserve(100, 3, 100), vec![(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(sessions.reserve(100, 3, 100), vec![(3, 1), (0, 1), (1, 1)]);
+        assert_eq!(sessions.num_per_channel, vec![2, 2, 1, 1]);
+
+        // channels 0 and 1 are full.
+        assert_eq!(sessions.reserve(100, 25, 100), vec![(2, 1), (3, 1)]);
+        assert_eq!(sessions.num_creating, 8);
+        assert!(sessions.reserve(100, 25, 100).is_empty(), "all the channels are full");
+
+        // failed creation releases the channel capacity.
+        sessions.replenish(2, 1, Err(Status::new(Code::Unavailable, "unavailable")));
+        assert_eq!(sessions.num_creating, 7);
+        assert_eq!(sessions.reserve(100, 25, 100), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn test_reserve_max_creating() {
+        let mut sessions = Sessions {
+            available_sessions: VecDeque::new(),
+            waiters: VecDeque::new(),
+            orphans: Vec::new(),
+            num_inuse: 0,
+            num_creating: 0,
+            num_per_channel: vec![0],
+            max_sessions_per_channel: 100,
+            num_evicted: 0,
+        };
+        assert_eq!(sessions.reserve(100, 25, 30), vec![(0, 25)]);
+        assert_eq!(sessions.reserve(100, 25, 30), vec![(0, 5)]);
+        assert!(sessions.reserve(100, 25, 30).is_empty(), "too many sessions in creation");
+
+        sessions.replenish(0, 25, Err(Status::new(Code::Unavailable, "unavailable")));
+        assert_eq!(sessions.reserve(100, 25, 30), vec![(0, 25)]);
+        assert_eq!(sessions.num_creating, 30);
+    }
+
+    #[test]
+    fn test_has_unmet_demand() {
+        let mut sessions = Sessions {
+            available_sessions: VecDeque::new(),
+            waiters: VecDeque::new(),
+            orphans: Vec::new(),
+            num_inuse: 0,
+            num_creating: 1,
+            num_per_channel: vec![1],
+            max_sessions_per_channel: 100,
+            num_evicted: 0,
+        };
+        assert!(!sessions.has_unmet_demand());
+        sessions.waiters.push_back(oneshot::channel().0);
+        assert!(!sessions.has_unmet_demand());
+        sessions.waiters.push_back(oneshot::channel().0);
+        assert!(sessions.has_unmet_demand());
+    }
+
+    #[test]
+    fn test_is_exhausted() {
+        let mut sessions = Sessions {
+            available_sessions: VecDeque::new(),
+            waiters: VecDeque::new(),
+            orphans: Vec::new(),
+            num_inuse: 1,
+            num_creating: 0,
+            num_per_channel: vec![1],
+            max_sessions_per_channel: 100,
+            num_evicted: 0,
+        };
+        assert!(!sessions.is_exhausted(2));
+        sessions.num_creating = 1;
+        assert!(sessions.is_exhausted(2));
+        sessions.num_creating = 0;
+        sessions.num_inuse = 2;
+        assert!(sessions.is_exhausted(2));
+    }
+
+    fn evicted_sessions(num_evicted: usize) -> Sessions {
+        Sessions {
+            available_sessions: VecDeque::new(),
+            waiters: VecDeque::new(),
+            orphans: Vec::new(),
+            num_inuse: 0,
+            num_creating: 0,
+            num_per_channel: vec![0; 2],
+            max_sessions_per_channel: 100,
+            num_evicted,
+        }
+    }
+
+    #[test]
+    fn test_reserve_replacement() {
+        // all the 10 sessions are evicted.
+        let mut sessions = evicted_sessions(10);
+        assert_eq!(sessions.reserve_replacement(10, 3, 100), vec![(0, 2), (1, 1)]);
+        assert_eq!(sessions.reserve_replacement(10, 3, 100), vec![(1, 2), (0, 1)]);
+        assert_eq!(sessions.reserve_replacement(10, 3, 100), vec![(0, 2), (1, 1)]);
+        assert_eq!(sessions.reserve_replacement(10, 3, 100), vec![(1, 1)]);
+        assert!(sessions.reserve_replacement(10, 3, 100).is_empty());
+        assert_eq!(sessions.num_creating, 10);
+
+        // no more than needed to keep min_opened.
+        let mut sessions = evicted_sessions(10);
+        sessions.num_inuse = 8;
+        assert_eq!(sessions.reserve_replacement(10, 3, 100), vec![(0, 1), (1, 1)]);
+        assert_eq!(sessions.num_evicted, 0);
+
+        // no more than max_creating.
+        let mut sessions = evicted_sessions(10);
+        sessions.num_creating = 99;
+        assert_eq!(sessions.reserve_replacement(200, 3, 100), vec![(0, 1)]);
+
+        // the shortage not caused by the eviction is not replaced.
+        assert!(evicted_sessions(0).reserve_replacement(10, 3, 100).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replacement_pacing() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let pool = SessionPool {
+            inner: Arc::new(RwLock::new(evicted_sessions(7))),
+            session_creation_sender: sender,
+            config: Arc::new(SessionConfig {
+                min_opened: 7,
+                ..Default::default()
+            }),
+        };
+        let pacing = ReplacementPacing {
+            max_sessions: 3,
+            interval: Duration::from_millis(100),
+        };
+        let cancel = CancellationToken::new();
+        let task = SessionManager::spawn_replacement_task(pacing, pool.clone(), cancel.clone());
+
+        let start = Instant::now();
+        let mut created = vec![];
+        for _ in 0..3 {
+            let allocation = receiver.recv().await.unwrap();
+            created.push((allocation.iter().map(|(_, count)| count).sum::<usize>(), start.elapsed()));
+        }
+        assert_eq!(created.iter().map(|(count, _)| *count).collect::<Vec<_>>(), vec![3, 3, 1]);
+        for (i, (_, elapsed)) in created.iter().enumerate() {
+            assert!(*elapsed >= pacing.interval * (i as u32 + 1), "{i}: {elapsed:?}");
+        }
+        assert!(timeout(Duration::from_millis(300), receiver.recv()).await.is_err());
+
+        cancel.cancel();
+        task.await.unwrap();
+    }
+
+    async fn exhausted_session_manager(
+        pool_exhausted_policy: PoolExhaustedPolicy,
+        acquire_timeout: Option<Duration>,
+    ) -> Arc<SessionManager> {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(1, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 1,
+            session_get_timeout: Duration::from_millis(100),
+            pool_exhausted_policy,
+            acquire_timeout,
+            ..Default::default()
+        };
+        SessionManager::new(DATABASE, cm, config).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_pool_exhausted_wait() {
+        let sm = exhausted_session_manager(PoolExhaustedPolicy::Wait, None).await;
+        let _session = sm.get().await.unwrap();
+
+        // without the acquire_timeout, the session_get_timeout error is returned as before.
+        let start = Instant::now();
+        match sm.get().await {
+            Err(SessionError::SessionGetTimeout) => assert!(start.elapsed() < Duration::from_secs(1)),
+            _ => panic!("must be timeout"),
+        };
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_pool_exhausted_acquire_timeout() {
+        let sm = exhausted_session_manager(PoolExhaustedPolicy::Wait, Some(Duration::from_millis(500))).await;
+        let session = sm.get().await.unwrap();
+
+        // waits longer than session_get_timeout and returns the exhausted error rather than hanging.
+        let start = Instant::now();
+        match sm.get().await {
+            Err(SessionError::SessionPoolExhausted) => {
+                let elapsed = start.elapsed();
+                assert!(elapsed >= Duration::from_millis(500), "{elapsed:?}");
+                assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+            }
+            _ => panic!("must be exhausted"),
+        };
+
+        // the session returned within the acquire_timeout is handed to the waiter.
+        let waiter = {
+            let sm = sm.clone();
+            tokio::spawn(async move { sm.get().await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        drop(session);
+        assert!(waiter.await.unwrap().is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_pool_exhausted_fail_fast() {
+        let sm = exhausted_session_manager(PoolExhaustedPolicy::FailFast, None).await;
+        let session = sm.get().await.unwrap();
+
+        let start = Instant::now();
+        match sm.get().await {
+            Err(SessionError::SessionPoolExhausted) => assert!(start.elapsed() < Duration::from_millis(100)),
+            _ => panic!("must be exhausted"),
+        };
+
+        // The returned session can be acquired again.
+        drop(session);
+        assert!(sm.get().await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_burst_acquisition() {
+        let fake = FakeSpanner::default();
+        let cm = ConnectionManager::new(4, &Environment::Emulator(fake.start().await), "")
+            .await
+            .unwrap();
+        let config = SessionConfig {
+            min_opened: 1,
+            max_opened: 40,
+            inc_step: 10,
+            max_creating: 20,
+            session_get_timeout: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let sm = SessionManager::new(DATABASE, cm, config.clone()).await.unwrap();
+        let mut awaiters = Vec::with_capacity(50);
+        for _ in 0..50 {
+            let sm = sm.clone();
+            awaiters.push(tokio::spawn(async move {
+                let session = sm.get().await;
+                let count = sm.num_opened();
+                sleep(Duration::from_millis(10)).await;
+                (session.is_ok(), count)
+            }));
+        }
+        for handler in awaiters {
+            let (ok, count) = handler.await.unwrap();
+            assert!(ok, "must acquire the session");
+            assert!(count <= config.max_opened, "now is {count}");
+        }
+        let sessions = sm.session_pool.inner.read();
+        assert!(sessions.num_opened() > config.min_opened, "pool grows");
+        assert!(sessions.num_opened() <= config.max_opened);
+        assert_eq!(sessions.num_creating, 0);
+        assert_eq!(sessions.waiters.len(), 0);
+
+        // The sessions are created on demand in the batches, never beyond max_opened.
+        let batches = fake.requests::<BatchCreateSessionsRequest>("BatchCreateSessions");
+        assert!(batches.len() > 1, "batches = {}", batches.len());
+        let requested: i32 = batches.iter().map(|(_, r)| r.session_count).sum();
+        assert_eq!(requested as usize, sessions.num_opened());
+    }
 }
@@ -1,4 +1,5 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
 
 use base64::prelude::*;
 use prost_types::value::Kind;
@@ -8,10 +9,13 @@ use time::format_description::well_known::Rfc3339;
 use time::macros::format_description;
 use time::{Date, OffsetDateTime};
 
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
 use google_cloud_googleapis::spanner::v1::struct_type::Field;
 use google_cloud_googleapis::spanner::v1::{StructType, Type, TypeAnnotationCode, TypeCode};
 
-use crate::value::{CommitTimestamp, SpannerNumeric};
+use crate::value::{CommitTimestamp, Interval, PrecisionTimestamp, SpannerNumeric};
+#[cfg(feature = "proto")]
+use crate::value::{ProtoEnum, ProtoMessage};
 
 /// A Statement is a SQL query with named parameters.
 ///
@@ -30,6 +34,20 @@ pub struct Statement {
     pub(crate) sql: String,
     pub(crate) params: BTreeMap<String, Value>,
     pub(crate) param_types: HashMap<String, Type>,
+    /// names of the parameters whose types are set by set_param_type.
+    explicit_param_types: BTreeSet<String>,
+    int64_coercion: Int64Coercion,
+}
+
+/// Int64Coercion decides whether add_int64_param accepts the string, such as the integer of the upstream JSON,
+/// as the INT64 parameter.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Int64Coercion {
+    /// Strict rejects the string, so that the string is never bound as INT64 by surprise.
+    #[default]
+    Strict,
+    /// Lenient parses the string as the decimal integer and rejects it if it overflows INT64.
+    Lenient,
 }
 
 impl Statement {
@@ -39,6 +57,8 @@ impl Statement {
             sql: sql.into(),
             params: Default::default(),
             param_types: Default::default(),
+            explicit_param_types: Default::default(),
+            int64_coercion: Default::default(),
         }
     }
 
@@ -48,7 +68,9 @@ impl Statement {
     where
         T: ToKind,
     {
-        self.param_types.insert(name.to_string(), T::get_type());
+        if !self.explicit_param_types.contains(name) {
+            self.param_types.insert(name.to_string(), T::get_type());
+        }
         self.params.insert(
             name.to_string(),
             Value {
@@ -56,6 +78,148 @@ impl Statement {
             },
         );
     }
+
+    /// set_param_type sets the type of the parameter regardless of the type of the bound value,
+    /// for the parameter whose type the server can't infer. The type takes precedence over the type
+    /// of the value bound by add_param before or after this.
+    pub fn set_param_type(&mut self, name: &str, param_type: Type) {
+        self.explicit_param_types.insert(name.to_string());
+        self.param_types.insert(name.to_string(), param_type);
+    }
+
+    /// set_int64_coercion sets whether add_int64_param accepts the string. The default is Strict.
+    pub fn set_int64_coercion(&mut self, coercion: Int64Coercion) {
+        self.int64_coercion = coercion;
+    }
+
+    /// add_int64_param adds the string as the INT64 bind parameter if the coercion is Lenient.
+    /// ```
+    /// use google_cloud_spanner::statement::{Int64Coercion, Statement, StatementError};
+    ///
+    /// fn run(user_id: &str) -> Result<Statement, StatementError> {
+    ///     let mut stmt = Statement::new("SELECT * FROM User WHERE UserId = @UserId");
+    ///     stmt.set_int64_coercion(Int64Coercion::Lenient);
+    ///     stmt.add_int64_param("UserId", user_id)?;
+    ///     Ok(stmt)
+    /// }
+    /// ```
+    pub fn add_int64_param(&mut self, name: &str, value: &str) -> Result<(), StatementError> {
+        let value = coerce_int64(self.int64_coercion, name, value)?;
+        self.add_param(name, &value);
+        Ok(())
+    }
+
+    /// from_sql_file returns a Statement with the SQL read from the file.
+    /// This is for keeping the SQL in the `.sql` files. Use `Statement::new(include_str!(...))`
+    /// to embed the file at compile time instead.
+    pub fn from_sql_file(path: impl AsRef<Path>) -> Result<Self, StatementError> {
+        let sql = std::fs::read_to_string(path)?;
+        Ok(Statement::new(sql))
+    }
+
+    /// parameter_names returns the names of the parameter placeholders in the SQL.
+    /// The placeholders in the string literals, the quoted identifiers and the comments are ignored.
+    pub fn parameter_names(&self) -> BTreeSet<String> {
+        parameter_names(&self.sql)
+    }
+
+    /// validate returns an error if any parameter placeholder in the SQL is not bound.
+    /// The parameters bound but not used are allowed.
+    pub fn validate(&self) -> Result<(), StatementError> {
+        let unbound: Vec<String> = self
+            .parameter_names()
+            .into_iter()
+            .filter(|name| !self.params.contains_key(name))
+            .collect();
+        if unbound.is_empty() {
+            Ok(())
+        } else {
+            Err(StatementError::UnboundParameters(unbound))
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StatementError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("unbound parameters: {0:?}")]
+    UnboundParameters(Vec<String>),
+    #[error("string can't be bound as INT64 in the strict mode: parameter={0}")]
+    StrictInt64(String),
+    #[error("invalid INT64: parameter={name}, value={value}, {source}")]
+    InvalidInt64 {
+        name: String,
+        value: String,
+        source: std::num::ParseIntError,
+    },
+}
+
+/// coerce_int64 parses the string bound as INT64, so that the overflow is rejected on the client.
+fn coerce_int64(coercion: Int64Coercion, name: &str, value: &str) -> Result<i64, StatementError> {
+    match coercion {
+        Int64Coercion::Strict => Err(StatementError::StrictInt64(name.to_string())),
+        Int64Coercion::Lenient => value.parse::<i64>().map_err(|source| StatementError::InvalidInt64 {
+            name: name.to_string(),
+            value: value.to_string(),
+            source,
+        }),
+    }
+}
+
+fn parameter_names(sql: &str) -> BTreeSet<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut names = BTreeSet::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' | '"' | '`' => {
+                let quote = chars[i];
+                let triple = i + 2 < chars.len() && chars[i + 1] == quote && chars[i + 2] == quote;
+                let width = if triple { 3 } else { 1 };
+                i += width;
+                while i < chars.len() {
+                    if chars[i] == '\\' {
+                        i += 2;
+                    } else if chars[i] == quote && chars[i..].iter().take(width).all(|c| *c == quote) {
+                        i += width;
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => i = skip_line(&chars, i),
+            '#' => i = skip_line(&chars, i),
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '@' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                if end > start && !chars[start].is_ascii_digit() {
+                    names.insert(chars[start..end].iter().collect());
+                }
+                i = end.max(i + 1);
+            }
+            _ => i += 1,
+        }
+    }
+    names
+}
+
+fn skip_line(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    i
 }
 
 pub fn single_type<T>(code: T) -> Type
@@ -66,11 +230,40 @@ where
         code: code.into(),
         array_element_type: None,
         struct_type: None,
-        //TODO support PG Numeric
         type_annotation: TypeAnnotationCode::Unspecified.into(),
     }
 }
 
+/// dialect_param_types returns the parameter types for the dialect of the database.
+/// The PostgreSQL-dialect database requires the PG_NUMERIC and PG_JSONB annotations
+/// for the NUMERIC and JSON parameters.
+pub(crate) fn dialect_param_types(
+    param_types: HashMap<String, Type>,
+    dialect: DatabaseDialect,
+) -> HashMap<String, Type> {
+    if dialect != DatabaseDialect::Postgresql {
+        return param_types;
+    }
+    param_types.into_iter().map(|(k, v)| (k, pg_type(v))).collect()
+}
+
+fn pg_type(mut t: Type) -> Type {
+    if t.type_annotation == TypeAnnotationCode::Unspecified as i32 {
+        if t.code == TypeCode::Numeric as i32 {
+            t.type_annotation = TypeAnnotationCode::PgNumeric.into();
+        } else if t.code == TypeCode::Json as i32 {
+            t.type_annotation = TypeAnnotationCode::PgJsonb.into();
+        }
+    }
+    t.array_element_type = t.array_element_type.map(|e| Box::new(pg_type(*e)));
+    if let Some(struct_type) = t.struct_type.as_mut() {
+        for field in struct_type.fields.iter_mut() {
+            field.r#type = field.r#type.take().map(pg_type);
+        }
+    }
+    t
+}
+
 pub trait ToKind {
     fn to_kind(&self) -> value::Kind;
     fn get_type() -> Type
@@ -169,6 +362,15 @@ impl ToKind for OffsetDateTime {
     }
 }
 
+impl ToKind for PrecisionTimestamp {
+    fn to_kind(&self) -> Kind {
+        self.precision.apply(self.timestamp).to_kind()
+    }
+    fn get_type() -> Type {
+        single_type(TypeCode::Timestamp)
+    }
+}
+
 impl ToKind for CommitTimestamp {
     fn to_kind(&self) -> Kind {
         "spanner.commit_timestamp()".to_kind()
@@ -205,6 +407,38 @@ impl ToKind for SpannerNumeric {
     }
 }
 
+/// INTERVAL_TYPE_CODE is the type code of INTERVAL, which is newer than the TypeCode of the generated protos.
+pub(crate) const INTERVAL_TYPE_CODE: i32 = 16;
+
+impl ToKind for Interval {
+    fn to_kind(&self) -> Kind {
+        self.to_string().to_kind()
+    }
+    fn get_type() -> Type {
+        single_type(INTERVAL_TYPE_CODE)
+    }
+}
+
+#[cfg(feature = "proto")]
+impl<T: prost::Message + Default> ToKind for ProtoMessage<T> {
+    fn to_kind(&self) -> Kind {
+        self.0.encode_to_vec().to_kind()
+    }
+    fn get_type() -> Type {
+        single_type(TypeCode::Bytes)
+    }
+}
+
+#[cfg(feature = "proto")]
+impl ToKind for ProtoEnum {
+    fn to_kind(&self) -> Kind {
+        (self.0 as i64).to_kind()
+    }
+    fn get_type() -> Type {
+        single_type(TypeCode::Int64)
+    }
+}
+
 impl<T> ToKind for T
 where
     T: ToStruct,
@@ -234,6 +468,8 @@ where
     }
 }
 
+/// None is encoded as the typed NULL, so that `None::<Vec<T>>` is the NULL array and differs from
+/// `Some(vec![])`, the empty array, for `IS NULL` and `ARRAY_LENGTH`.
 impl<T> ToKind for Option<T>
 where
     T: ToKind,
@@ -272,3 +508,248 @@ where
         }
     }
 }
+
+/// MapEntry is the element of the ARRAY<STRUCT<key, value>> encoded from the BTreeMap.
+struct MapEntry<'a, K, V> {
+    key: &'a K,
+    value: &'a V,
+}
+
+impl<K, V> ToStruct for MapEntry<'_, K, V>
+where
+    K: ToKind,
+    V: ToKind,
+{
+    fn to_kinds(&self) -> Kinds {
+        vec![("key", self.key.to_kind()), ("value", self.value.to_kind())]
+    }
+
+    fn get_types() -> Types
+    where
+        Self: Sized,
+    {
+        vec![("key", K::get_type()), ("value", V::get_type())]
+    }
+}
+
+/// The BTreeMap is bound as ARRAY<STRUCT<key K, value V>> in the order of the keys,
+/// so that the query can join against the pairs with UNNEST.
+/// ```
+/// use std::collections::BTreeMap;
+/// use google_cloud_spanner::statement::Statement;
+///
+/// let quantities = BTreeMap::from([("item1".to_string(), 1), ("item2".to_string(), 2)]);
+/// let mut stmt = Statement::new("SELECT * FROM UNNEST(@Quantities) AS q JOIN Item ON Item.ItemId = q.key");
+/// stmt.add_param("Quantities", &quantities);
+/// ```
+impl<K, V> ToKind for BTreeMap<K, V>
+where
+    K: ToKind,
+    V: ToKind,
+{
+    fn to_kind(&self) -> Kind {
+        value::Kind::ListValue(ListValue {
+            values: self
+                .iter()
+                .map(|(key, value)| Value {
+                    kind: Some(MapEntry { key, value }.to_kind()),
+                })
+                .collect(),
+        })
+    }
+    fn get_type() -> Type {
+        Vec::<MapEntry<'_, K, V>>::get_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use prost_types::value::Kind;
+
+    use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+    use google_cloud_googleapis::spanner::v1::{TypeAnnotationCode, TypeCode};
+
+    use crate::statement::{dialect_param_types, single_type, Int64Coercion, Statement, StatementError, ToKind};
+    use crate::value::{PrecisionTimestamp, SpannerNumeric, TimestampPrecision};
+
+    fn names(v: &[&str]) -> BTreeSet<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parameter_names() {
+        let stmt = Statement::new(
+            r#"
+            -- find the items of @Ignored
+            SELECT * FROM UserItem@{FORCE_INDEX=UserItemByQuantity}
+            WHERE UserId = @UserId AND Quantity > @Quantity /* @Ignored */
+              AND Name != '@Ignored' AND Memo != "it's @Ignored" AND `@Ignored` IS NULL
+              AND Note != '''@Ignored''' # @Ignored
+              AND ItemId IN UNNEST(@ItemIds) AND UserId != @UserId
+            "#,
+        );
+        assert_eq!(stmt.parameter_names(), names(&["ItemIds", "Quantity", "UserId"]));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut stmt = Statement::new("SELECT * FROM User WHERE UserId = @UserId AND Quantity > @Quantity");
+        match stmt.validate() {
+            Err(StatementError::UnboundParameters(unbound)) => assert_eq!(unbound, vec!["Quantity", "UserId"]),
+            _ => panic!("must be unbound"),
+        }
+        stmt.add_param("UserId", &"user");
+        stmt.add_param("Quantity", &1);
+        stmt.add_param("Unused", &1);
+        assert!(stmt.validate().is_ok());
+    }
+
+    #[test]
+    fn test_add_int64_param() {
+        // strict by default
+        let mut stmt = Statement::new("SELECT * FROM User WHERE UserId = @UserId");
+        match stmt.add_int64_param("UserId", "42") {
+            Err(StatementError::StrictInt64(name)) => assert_eq!(name, "UserId"),
+            _ => panic!("must be rejected"),
+        }
+        assert!(stmt.validate().is_err());
+
+        stmt.set_int64_coercion(Int64Coercion::Lenient);
+        stmt.add_int64_param("UserId", "-42").unwrap();
+        assert_eq!(stmt.params["UserId"].kind, Some(Kind::StringValue("-42".to_string())));
+        assert_eq!(stmt.param_types["UserId"].code, TypeCode::Int64 as i32);
+
+        // not an integer or overflow
+        for value in ["4.2", "abc", " 42", "9223372036854775808"] {
+            match stmt.add_int64_param("Quantity", value) {
+                Err(StatementError::InvalidInt64 { name, value: v, .. }) => {
+                    assert_eq!(name, "Quantity");
+                    assert_eq!(v, value);
+                }
+                _ => panic!("must be invalid: {value}"),
+            }
+        }
+        assert!(!stmt.params.contains_key("Quantity"));
+    }
+
+    #[test]
+    fn test_from_sql_file() {
+        let path = std::env::temp_dir().join(format!("statement_test_{}.sql", std::process::id()));
+        std::fs::write(&path, "SELECT * FROM User WHERE UserId = @UserId\n").unwrap();
+        let mut stmt = Statement::from_sql_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(stmt.validate().is_err());
+        stmt.add_param("UserId", &"user");
+        assert!(stmt.validate().is_ok());
+
+        match Statement::from_sql_file(path) {
+            Err(StatementError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("must be not found"),
+        }
+    }
+
+    #[test]
+    fn test_dialect_param_types() {
+        let mut stmt = Statement::new("SELECT * FROM t WHERE a = $1 AND b = ANY($2) AND c = $3");
+        stmt.add_param("p1", &SpannerNumeric::new("1.0"));
+        stmt.add_param("p2", &vec![SpannerNumeric::new("1.0")]);
+        stmt.add_param("p3", &"text");
+
+        let param_types = dialect_param_types(stmt.param_types.clone(), DatabaseDialect::GoogleStandardSql);
+        assert_eq!(param_types, stmt.param_types);
+
+        let param_types = dialect_param_types(stmt.param_types, DatabaseDialect::Postgresql);
+        let numeric = &param_types["p1"];
+        assert_eq!(numeric.code, TypeCode::Numeric as i32);
+        assert_eq!(numeric.type_annotation, TypeAnnotationCode::PgNumeric as i32);
+        let element = param_types["p2"].array_element_type.as_ref().unwrap();
+        assert_eq!(element.type_annotation, TypeAnnotationCode::PgNumeric as i32);
+        assert_eq!(param_types["p3"], String::get_type());
+    }
+
+    #[test]
+    fn test_set_param_type() {
+        let mut stmt = Statement::new("SELECT JSON_VALUE(@Json, '$.name'), @Id");
+        stmt.add_param("Json", &"{\"name\":\"a\"}");
+        stmt.set_param_type("Json", single_type(TypeCode::Json));
+        stmt.set_param_type("Id", i64::get_type());
+        stmt.add_param("Id", &"1");
+        assert_eq!(stmt.param_types["Json"], single_type(TypeCode::Json));
+        assert_eq!(stmt.param_types["Id"], i64::get_type());
+
+        // the other parameters are inferred from the value.
+        stmt.add_param("Name", &"a");
+        assert_eq!(stmt.param_types["Name"], String::get_type());
+    }
+
+    #[test]
+    fn test_precision_timestamp() {
+        let kind = |nanos: u32, precision: TimestampPrecision| {
+            let value = time::macros::datetime!(2023-01-01 23:59:59 UTC)
+                .replace_nanosecond(nanos)
+                .unwrap();
+            match PrecisionTimestamp::new(value, precision).to_kind() {
+                Kind::StringValue(s) => s,
+                _ => panic!("must be string"),
+            }
+        };
+        assert_eq!(kind(123_456_789, TimestampPrecision::Nanos), "2023-01-01T23:59:59.123456789Z");
+        assert_eq!(
+            kind(123_456_789, TimestampPrecision::TruncateMicros),
+            "2023-01-01T23:59:59.123456Z"
+        );
+        assert_eq!(
+            kind(123_456_789, TimestampPrecision::RoundMicros),
+            "2023-01-01T23:59:59.123457Z"
+        );
+        assert_eq!(
+            kind(123_456_499, TimestampPrecision::RoundMicros),
+            "2023-01-01T23:59:59.123456Z"
+        );
+        // rounded up to the next day
+        assert_eq!(kind(999_999_500, TimestampPrecision::RoundMicros), "2023-01-02T00:00:00Z");
+        assert_eq!(PrecisionTimestamp::get_type(), time::OffsetDateTime::get_type());
+    }
+
+    #[test]
+    fn test_btree_map_param() {
+        let mut stmt = Statement::new("SELECT * FROM UNNEST(@Quantities)");
+        let quantities = BTreeMap::from([("item2".to_string(), 2), ("item1".to_string(), 1)]);
+        stmt.add_param("Quantities", &quantities);
+
+        let param_type = &stmt.param_types["Quantities"];
+        assert_eq!(param_type.code, TypeCode::Array as i32);
+        let element = param_type.array_element_type.as_ref().unwrap();
+        assert_eq!(element.code, TypeCode::Struct as i32);
+        let fields: Vec<_> = element
+            .struct_type
+            .as_ref()
+            .unwrap()
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.r#type.clone().unwrap()))
+            .collect();
+        assert_eq!(fields, vec![("key", String::get_type()), ("value", i64::get_type())]);
+
+        let entries = match stmt.params["Quantities"].kind.as_ref().unwrap() {
+            Kind::ListValue(v) => v.values.clone(),
+            _ => panic!("must be list"),
+        };
+        let entries: Vec<(Kind, Kind)> = entries
+            .into_iter()
+            .map(|e| match e.kind.unwrap() {
+                Kind::StructValue(mut s) => (
+                    s.fields.remove("key").unwrap().kind.unwrap(),
+                    s.fields.remove("value").unwrap().kind.unwrap(),
+                ),
+                _ => panic!("must be struct"),
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![("item1".to_kind(), 1.to_kind()), ("item2".to_kind(), 2.to_kind()),]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Utilities for the integration tests against the Cloud Spanner emulator.
+//!
+//! Enable the `test-util` feature to use this module.
+//! ```no_run
+//! use google_cloud_spanner::reader::AsyncIterator;
+//! use google_cloud_spanner::statement::Statement;
+//! use google_cloud_spanner::testing::with_emulator;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let ddl = ["CREATE TABLE Guild (GuildId STRING(MAX)) PRIMARY KEY(GuildId)"];
+//!     let count = with_emulator(&ddl, |client| async move {
+//!         let mut tx = client.single().await.unwrap();
+//!         let mut iter = tx.query(Statement::new("SELECT GuildId FROM Guild")).await.unwrap();
+//!         let mut count = 0;
+//!         while iter.next().await.unwrap().is_some() {
+//!             count += 1;
+//!         }
+//!         count
+//!     })
+//!     .await
+//!     .unwrap();
+//!     assert_eq!(count, 0);
+//! }
+//! ```
+use std::env::var;
+use std::future::Future;
+use std::net::{TcpListener, TcpStream};
+use std::process::Command;
+use std::time::Duration;
+
+use time::OffsetDateTime;
+
+use google_cloud_gax::conn::Environment;
+use google_cloud_gax::grpc::Status;
+use google_cloud_googleapis::spanner::admin::database::v1::{
+    CreateDatabaseRequest, DatabaseDialect, DropDatabaseRequest,
+};
+use google_cloud_googleapis::spanner::admin::instance::v1::{
+    CreateInstanceRequest, DeleteInstanceRequest, Instance, ListInstanceConfigsRequest,
+};
+
+use crate::admin::client::Client as AdminClient;
+use crate::admin::AdminClientConfig;
+use crate::client::{Client, ClientConfig};
+
+/// EMULATOR_IMAGE is the docker image of the Cloud Spanner emulator.
+pub const EMULATOR_IMAGE: &str = "gcr.io/cloud-spanner-emulator/emulator";
+
+const PROJECT: &str = "projects/local-project";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("docker: {0}")]
+    Docker(String),
+    #[error("emulator is not ready: {0}")]
+    NotReady(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    GRPC(#[from] Status),
+    #[error(transparent)]
+    Connection(#[from] google_cloud_gax::conn::Error),
+    #[error(transparent)]
+    Client(#[from] crate::client::Error),
+}
+
+/// Emulator is the running Cloud Spanner emulator.
+/// The emulator specified by SPANNER_EMULATOR_HOST is used if exists, otherwise a new emulator
+/// is launched with docker on a free port and stopped on drop.
+pub struct Emulator {
+    host: String,
+    container_id: Option<String>,
+}
+
+impl Emulator {
+    pub async fn start() -> Result<Self, Error> {
+        let emulator = match var("SPANNER_EMULATOR_HOST").ok() {
+            Some(host) => Emulator {
+                host,
+                container_id: None,
+            },
+            None => {
+                let port = free_port()?;
+                let output = Command::new("docker")
+                    .args([
+                        "run",
+                        "-d",
+                        "--rm",
+                        "-p",
+                        &format!("127.0.0.1:{port}:9010"),
+                        EMULATOR_IMAGE,
+                    ])
+                    .output()?;
+                if !output.status.success() {
+                    return Err(Error::Docker(String::from_utf8_lossy(&output.stderr).to_string()));
+                }
+                Emulator {
+                    host: format!("localhost:{port}"),
+                    container_id: Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+                }
+            }
+        };
+        emulator.wait_ready().await?;
+        Ok(emulator)
+    }
+
+    /// host returns the address of the emulator such as localhost:9010.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn environment(&self) -> Environment {
+        Environment::Emulator(self.host.to_string())
+    }
+
+    pub async fn admin_client(&self) -> Result<AdminClient, Error> {
+        let config = AdminClientConfig {
+            environment: self.environment(),
+        };
+        Ok(AdminClient::new(config).await?)
+    }
+
+    async fn wait_ready(&self) -> Result<(), Error> {
+        let mut last_error = String::new();
+        for _ in 0..60 {
+            if let Err(e) = TcpStream::connect(&self.host) {
+                last_error = e.to_string();
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                continue;
+            }
+            let request = ListInstanceConfigsRequest {
+                parent: PROJECT.to_string(),
+                page_size: 1,
+                page_token: "".to_string(),
+            };
+            match self.admin_client().await {
+                Ok(admin) => match admin.instance().list_instance_configs(request, None, None).await {
+                    Ok(_) => return Ok(()),
+                    Err(e) => last_error = e.to_string(),
+                },
+                Err(e) => last_error = e.to_string(),
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        Err(Error::NotReady(last_error))
+    }
+}
+
+impl Drop for Emulator {
+    fn drop(&mut self) {
+        if let Some(container_id) = &self.container_id {
+            if let Err(e) = Command::new("docker").args(["stop", container_id]).output() {
+                tracing::error!("failed to stop the emulator {}, {:?}", container_id, e);
+            }
+        }
+    }
+}
+
+/// TestDatabase is the instance and the database created for a test.
+pub struct TestDatabase {
+    pub instance: String,
+    pub database: String,
+}
+
+impl TestDatabase {
+    /// create creates a new instance and a new database with the ddl.
+    pub async fn create(admin: &AdminClient, ddl: &[&str]) -> Result<Self, Error> {
+        let id = format!("test{}", OffsetDateTime::now_utc().unix_timestamp_nanos());
+        let instance = format!("{PROJECT}/instances/{id}");
+        let request = CreateInstanceRequest {
+            parent: PROJECT.to_string(),
+            instance_id: id.to_string(),
+            instance: Some(Instance {
+                name: instance.to_string(),
+                config: "".to_string(),
+                display_name: id.to_string(),
+                node_count: 1,
+                processing_units: 0,
+                state: 0,
+                labels: Default::default(),
+                endpoint_uris: vec![],
+                create_time: None,
+                update_time: None,
+            }),
+        };
+        admin
+            .instance()
+            .create_instance(request, None, None)
+            .await?
+            .wait(None, None)
+            .await?;
+
+        let request = CreateDatabaseRequest {
+            parent: instance.to_string(),
+            create_statement: format!("CREATE DATABASE {id}"),
+            extra_statements: ddl.iter().map(|s| s.to_string()).collect(),
+            encryption_config: None,
+            database_dialect: DatabaseDialect::GoogleStandardSql.into(),
+        };
+        let test_database = TestDatabase {
+            database: format!("{instance}/databases/{id}"),
+            instance,
+        };
+        let result = match admin.database().create_database(request, None, None).await {
+            Ok(mut operation) => operation.wait(None, None).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            test_database.delete(admin).await;
+            return Err(e.into());
+        }
+        Ok(test_database)
+    }
+
+    /// delete drops the database and deletes the instance.
+    pub async fn delete(&self, admin: &AdminClient) {
+        let request = DropDatabaseRequest {
+            database: self.database.to_string(),
+        };
+        if let Err(e) = admin.database().drop_database(request, None, None).await {
+            tracing::error!("failed to drop database {}, {:?}", self.database, e);
+        }
+        let request = DeleteInstanceRequest {
+            name: self.instance.to_string(),
+        };
+        if let Err(e) = admin.instance().delete_instance(request, None, None).await {
+            tracing::error!("failed to delete instance {}, {:?}", self.instance, e);
+        }
+    }
+}
+
+/// with_emulator runs f with a Client connected to a new database created with the ddl
+/// and deletes the database after f completes.
+pub async fn with_emulator<T, F, Fut>(ddl: &[&str], f: F) -> Result<T, Error>
+where
+    F: FnOnce(Client) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let emulator = Emulator::start().await?;
+    let admin = emulator.admin_client().await?;
+    let test_database = TestDatabase::create(&admin, ddl).await?;
+
+    let config = ClientConfig {
+        environment: emulator.environment(),
+        ..Default::default()
+    };
+    let client = match Client::new(&test_database.database, config).await {
+        Ok(client) => client,
+        Err(e) => {
+            test_database.delete(&admin).await;
+            return Err(e.into());
+        }
+    };
+    let result = f(client.clone()).await;
+    client.close().await;
+    test_database.delete(&admin).await;
+    Ok(result)
+}
+
+/// free_port returns a port not used at the moment.
+fn free_port() -> Result<u16, std::io::Error> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use crate::testing::free_port;
+
+    #[test]
+    fn test_free_port() {
+        let port = free_port().unwrap();
+        assert_ne!(port, 0);
+        assert!(TcpListener::bind(("127.0.0.1", port)).is_ok());
+    }
+}
@@ -1,24 +1,30 @@
+use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::atomic::AtomicI64;
+use std::sync::Arc;
 
+use futures_util::Stream;
 use prost_types::Struct;
 
 use google_cloud_gax::cancel::CancellationToken;
 use google_cloud_gax::grpc::Status;
 use google_cloud_gax::retry::RetrySetting;
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
 use google_cloud_googleapis::spanner::v1::request_options::Priority;
 use google_cloud_googleapis::spanner::v1::{
-    execute_sql_request::QueryMode, execute_sql_request::QueryOptions as ExecuteQueryOptions, ExecuteSqlRequest,
-    ReadRequest, RequestOptions, TransactionSelector,
+    execute_sql_request::QueryMode, execute_sql_request::QueryOptions as ExecuteQueryOptions, transaction_options,
+    transaction_selector, ExecuteSqlRequest, PartialResultSet, ReadRequest, RequestOptions, TransactionOptions,
+    TransactionSelector,
 };
 
 use crate::key::{Key, KeySet};
-use crate::reader::{AsyncIterator, RowIterator, StatementReader, TableReader};
-use crate::row::Row;
+use crate::plan::Plan;
+use crate::reader::{raw_stream, AsyncIterator, Reader, RowIterator, StatementReader, TableReader};
+use crate::row::{Row, TryFromValue};
 use crate::session::ManagedSession;
-use crate::statement::Statement;
+use crate::statement::{dialect_param_types, Statement};
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Debug)]
 pub struct CallOptions {
     /// Priority is the RPC priority to use for the read operation.
     pub priority: Option<Priority>,
@@ -26,6 +32,17 @@ pub struct CallOptions {
     pub cancel: Option<CancellationToken>,
 }
 
+impl CallOptions {
+    /// no_retry returns the options that disable all the automatic retries including the retry of the aborted transaction.
+    /// This is for the non-idempotent operations that prefer the immediate error.
+    pub fn no_retry() -> Self {
+        Self {
+            retry: Some(RetrySetting::disabled()),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ReadOptions {
     /// The index to use for reading. If non-empty, you can only read columns
@@ -36,7 +53,20 @@ pub struct ReadOptions {
     /// The maximum number of rows to read. A limit value less than 1 means no limit.
     pub limit: i64,
 
+    /// request_tag is the tag of the read request shown in the query insights and the statistics tables.
+    pub request_tag: Option<String>,
+
     pub call_options: CallOptions,
+
+    /// max_keys_per_request splits the key set with more keys and ranges than this into the multiple read
+    /// requests, so that a large key set fits into the size limit of a request. The RowIterator returns
+    /// the rows of the requests in sequence, so the rows are in the key order only within each request.
+    /// The key set is not split if the limit is set, because the limit applies to each request.
+    /// The split requests read the snapshot of the transaction id they share, so the read of the single-use
+    /// transaction such as Client::single, whose each request would read at its own timestamp, fails with
+    /// INVALID_ARGUMENT if the key set needs to be split. Use Client::read_only_transaction instead.
+    /// The default is None, which sends the key set in one request.
+    pub max_keys_per_request: Option<usize>,
 }
 
 impl Default for ReadOptions {
@@ -44,16 +74,173 @@ impl Default for ReadOptions {
         ReadOptions {
             index: "".to_string(),
             limit: 0,
+            request_tag: None,
             call_options: CallOptions::default(),
+            max_keys_per_request: None,
         }
     }
 }
 
+impl ReadOptions {
+    /// request_options returns the RequestOptions of the ReadRequest with the priority and the request tag.
+    pub(crate) fn request_options(&self) -> Option<RequestOptions> {
+        match &self.request_tag {
+            Some(request_tag) => Some(RequestOptions {
+                priority: self.call_options.priority.unwrap_or(Priority::Unspecified).into(),
+                request_tag: request_tag.to_string(),
+                transaction_tag: "".to_string(),
+            }),
+            None => Transaction::create_request_options(self.call_options.priority),
+        }
+    }
+}
+
+/// ReadTemplate is the table, the columns and the options shared by the repeated reads,
+/// so that each read specifies only the keys with Transaction::read_with_template.
+/// ```
+/// use google_cloud_spanner::key::Key;
+/// use google_cloud_spanner::client::{Client, Error};
+/// use google_cloud_spanner::transaction::ReadTemplate;
+///
+/// async fn run(client: Client) -> Result<(), Error> {
+///     let guild = ReadTemplate::new("Guild", &["GuildID", "OwnerUserID"]);
+///     let mut tx = client.single().await?;
+///     let iter = tx.read_with_template(&guild, Key::new(&"guild1")).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ReadTemplate {
+    table: String,
+    columns: Vec<String>,
+    options: ReadOptions,
+}
+
+impl ReadTemplate {
+    pub fn new(table: &str, columns: &[&str]) -> Self {
+        ReadTemplate {
+            table: table.to_string(),
+            columns: columns.iter().map(|x| x.to_string()).collect(),
+            options: ReadOptions::default(),
+        }
+    }
+
+    /// with_options sets the options of every read with the template.
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn options(&self) -> &ReadOptions {
+        &self.options
+    }
+
+    fn requests(&self, session: String, selector: TransactionSelector, key_set: KeySet) -> Vec<ReadRequest> {
+        read_requests(session, selector, &self.table, self.columns.clone(), key_set, &self.options)
+    }
+}
+
+/// read_requests creates the read request of each key set split by the ReadOptions::max_keys_per_request.
+fn read_requests(
+    session: String,
+    selector: TransactionSelector,
+    table: &str,
+    columns: Vec<String>,
+    key_set: KeySet,
+    options: &ReadOptions,
+) -> Vec<ReadRequest> {
+    let key_sets = match options.max_keys_per_request {
+        Some(max_keys) if options.limit < 1 => key_set.split(max_keys),
+        _ => vec![key_set],
+    };
+    key_sets
+        .into_iter()
+        .map(|key_set| read_request(session.clone(), selector.clone(), table, columns.clone(), key_set, options))
+        .collect()
+}
+
+fn read_request(
+    session: String,
+    selector: TransactionSelector,
+    table: &str,
+    columns: Vec<String>,
+    key_set: KeySet,
+    options: &ReadOptions,
+) -> ReadRequest {
+    ReadRequest {
+        session,
+        transaction: Some(selector),
+        table: table.to_string(),
+        index: options.index.to_string(),
+        columns,
+        key_set: Some(key_set.inner),
+        limit: options.limit,
+        resume_token: vec![],
+        partition_token: vec![],
+        request_options: options.request_options(),
+    }
+}
+
+/// LockHint is the lock acquired by a query in a read-write transaction.
+/// It is sent as the `LOCK_SCANNED_RANGES` statement hint of the GoogleSQL query, and is ignored
+/// with a warning for the PostgreSQL-dialect database, which doesn't accept the GoogleSQL hint syntax.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockHint {
+    /// Shared acquires the shared locks, which is suitable for the lookups.
+    Shared,
+    /// Exclusive acquires the exclusive locks, which reduces the deadlocks of the reads followed by the writes of the same rows.
+    Exclusive,
+}
+
+impl LockHint {
+    /// apply adds the statement hint to the sql. The hint is merged into the leading statement hint if exists,
+    /// and the LOCK_SCANNED_RANGES already in the sql takes precedence.
+    pub(crate) fn apply(&self, sql: String, dialect: DatabaseDialect) -> String {
+        if dialect == DatabaseDialect::Postgresql {
+            tracing::warn!("the lock hint is ignored for the PostgreSQL-dialect database");
+            return sql;
+        }
+        let mode = match self {
+            LockHint::Shared => "shared",
+            LockHint::Exclusive => "exclusive",
+        };
+        let trimmed = sql.trim_start();
+        match trimmed.strip_prefix("@{") {
+            Some(hints) => {
+                let existing = hints.split('}').next().unwrap_or_default();
+                if existing.to_ascii_uppercase().contains("LOCK_SCANNED_RANGES") {
+                    return sql;
+                }
+                let separator = if existing.trim().is_empty() { "" } else { ", " };
+                format!("@{{LOCK_SCANNED_RANGES={mode}{separator}{hints}")
+            }
+            None => format!("@{{LOCK_SCANNED_RANGES={mode}}} {sql}"),
+        }
+    }
+}
+
+/// RequestMutator modifies the ExecuteSqlRequest just before it is sent.
+pub type RequestMutator = Arc<dyn Fn(&mut ExecuteSqlRequest) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct QueryOptions {
     pub mode: QueryMode,
     pub optimizer_options: Option<ExecuteQueryOptions>,
     pub call_options: CallOptions,
+    /// request_mutator is an escape hatch to set the fields of the ExecuteSqlRequest this crate doesn't support yet.
+    /// It is called after all the standard fields are filled, so it can also override them.
+    pub request_mutator: Option<RequestMutator>,
+    /// lock_hint is the lock acquired by the query. This is valid only in the read-write transaction.
+    /// None uses the default lock of Cloud Spanner.
+    pub lock_hint: Option<LockHint>,
 }
 
 impl Default for QueryOptions {
@@ -62,15 +249,95 @@ impl Default for QueryOptions {
             mode: QueryMode::Normal,
             optimizer_options: None,
             call_options: CallOptions::default(),
+            request_mutator: None,
+            lock_hint: None,
+        }
+    }
+}
+
+/// The builder methods set every field of QueryOptions, so that the call sites do not break as the fields are added.
+/// The options built from QueryOptions::default() are the same as the ones set field by field.
+/// ```
+/// use google_cloud_googleapis::spanner::v1::execute_sql_request::QueryMode;
+/// use google_cloud_googleapis::spanner::v1::request_options::Priority;
+/// use google_cloud_spanner::transaction::{CallOptions, LockHint, QueryOptions};
+///
+/// let options = QueryOptions::default()
+///     .with_mode(QueryMode::Profile)
+///     .with_call_options(CallOptions {
+///         priority: Some(Priority::Low),
+///         ..Default::default()
+///     })
+///     .with_lock_hint(LockHint::Exclusive);
+/// ```
+impl QueryOptions {
+    /// with_mode sets the query mode, e.g. QueryMode::Profile to return the query plan and the statistics.
+    pub fn with_mode(mut self, mode: QueryMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// with_optimizer_options sets the optimizer version and the statistics package of the query.
+    pub fn with_optimizer_options(mut self, optimizer_options: ExecuteQueryOptions) -> Self {
+        self.optimizer_options = Some(optimizer_options);
+        self
+    }
+
+    /// with_call_options sets the priority, the retry and the cancellation of the RPC.
+    pub fn with_call_options(mut self, call_options: CallOptions) -> Self {
+        self.call_options = call_options;
+        self
+    }
+
+    /// with_lock_hint sets the lock acquired by the query in the read-write transaction.
+    pub fn with_lock_hint(mut self, lock_hint: LockHint) -> Self {
+        self.lock_hint = Some(lock_hint);
+        self
+    }
+
+    /// with_request_mutator sets the function to modify the ExecuteSqlRequest before it is sent.
+    pub fn with_request_mutator(mut self, f: impl Fn(&mut ExecuteSqlRequest) + Send + Sync + 'static) -> Self {
+        self.request_mutator = Some(Arc::new(f));
+        self
+    }
+
+    pub(crate) fn mutate_request(&self, request: &mut ExecuteSqlRequest) {
+        if let Some(f) = &self.request_mutator {
+            f(request)
+        }
+    }
+
+    pub(crate) fn sql(&self, sql: String, dialect: DatabaseDialect) -> String {
+        match &self.lock_hint {
+            Some(lock_hint) => lock_hint.apply(sql, dialect),
+            None => sql,
         }
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum QueryOneValueError {
+    #[error(transparent)]
+    GRPC(#[from] Status),
+    #[error(transparent)]
+    ParseError(#[from] crate::row::Error),
+    #[error("no rows returned")]
+    NoRows,
+    #[error("more than one row returned")]
+    TooManyRows,
+    #[error("one column expected: columns={0}")]
+    InvalidColumnCount(usize),
+}
+
 pub struct Transaction {
     pub(crate) session: Option<ManagedSession>,
     // for returning ownership of session on before destroy
     pub(crate) sequence_number: AtomicI64,
     pub(crate) transaction_selector: TransactionSelector,
+    /// dialect is the dialect of the database to bind the parameters with.
+    pub(crate) dialect: DatabaseDialect,
+    /// read_write is true if the statements are executed in the read-write transaction.
+    pub(crate) read_write: bool,
 }
 
 impl Transaction {
@@ -99,24 +366,134 @@ impl Transaction {
         statement: Statement,
         options: QueryOptions,
     ) -> Result<RowIterator<'_>, Status> {
-        let request = ExecuteSqlRequest {
+        let request = self.create_query_request(statement, &options);
+        let session = self.session.as_mut().unwrap().deref_mut();
+        let reader = Box::new(StatementReader {
+            request,
+            read_write: self.read_write,
+        });
+        RowIterator::new(session, reader, Some(options.call_options)).await
+    }
+
+    /// execute_streaming_raw executes a query and returns the PartialResultSets as they are received,
+    /// for the consumers decoding the values by themselves.
+    /// The first PartialResultSet has the metadata, and the last one has the stats if requested by the QueryMode.
+    /// The value may be split into the chunks of the consecutive PartialResultSets with chunked_value.
+    /// The stream is resumed with the last resume token on the error like the RowIterator of query.
+    /// ```
+    /// use futures_util::StreamExt;
+    /// use google_cloud_gax::grpc::Status;
+    /// use google_cloud_spanner::client::Client;
+    /// use google_cloud_spanner::statement::Statement;
+    /// use google_cloud_spanner::transaction::QueryOptions;
+    ///
+    /// async fn run(client: Client) -> Result<(), Status> {
+    ///     let mut tx = client.single().await.unwrap();
+    ///     let stmt = Statement::new("SELECT * FROM User");
+    ///     let stream = tx.execute_streaming_raw(stmt, QueryOptions::default()).await?;
+    ///     futures_util::pin_mut!(stream);
+    ///     while let Some(result_set) = stream.next().await {
+    ///         let result_set = result_set?;
+    ///         println!("values={} chunked={}", result_set.values.len(), result_set.chunked_value);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn execute_streaming_raw(
+        &mut self,
+        statement: Statement,
+        options: QueryOptions,
+    ) -> Result<impl Stream<Item = Result<PartialResultSet, Status>> + '_, Status> {
+        let request = self.create_query_request(statement, &options);
+        let session = self.session.as_mut().unwrap().deref_mut();
+        let reader = Box::new(StatementReader {
+            request,
+            read_write: self.read_write,
+        });
+        raw_stream(session, reader, Some(options.call_options)).await
+    }
+
+    fn create_query_request(&self, statement: Statement, options: &QueryOptions) -> ExecuteSqlRequest {
+        let mut request = ExecuteSqlRequest {
             session: self.session.as_ref().unwrap().session.name.to_string(),
             transaction: Some(self.transaction_selector.clone()),
-            sql: statement.sql,
+            sql: options.sql(statement.sql, self.dialect),
             params: Some(Struct {
                 fields: statement.params,
             }),
-            param_types: statement.param_types,
+            param_types: dialect_param_types(statement.param_types, self.dialect),
             resume_token: vec![],
             query_mode: options.mode.into(),
             partition_token: vec![],
             seqno: 0,
-            query_options: options.optimizer_options,
+            query_options: options.optimizer_options.clone(),
             request_options: Transaction::create_request_options(options.call_options.priority),
         };
-        let session = self.session.as_mut().unwrap().deref_mut();
-        let reader = Box::new(StatementReader { request });
-        RowIterator::new(session, reader, Some(options.call_options)).await
+        options.mutate_request(&mut request);
+        request
+    }
+
+    /// query_all executes a query and reads all the resulting rows at once.
+    /// The rows are read with the streaming RPC resumed on the transient errors like query,
+    /// so use this only for the bounded result sets that fit in memory.
+    pub async fn query_all(&mut self, statement: Statement) -> Result<Vec<Row>, Status> {
+        let mut reader = self.query(statement).await?;
+        let mut rows = vec![];
+        while let Some(row) = reader.next().await? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    /// query_one_value executes a query which returns exactly one row with one column,
+    /// such as `SELECT COUNT(*) FROM Guild`, and decodes the column.
+    /// ```
+    /// use google_cloud_spanner::client::Client;
+    /// use google_cloud_spanner::statement::Statement;
+    /// use google_cloud_spanner::transaction::QueryOneValueError;
+    ///
+    /// async fn run(client: Client) -> Result<(), QueryOneValueError> {
+    ///     let mut tx = client.single().await.unwrap();
+    ///     let count = tx.query_one_value::<i64>(Statement::new("SELECT COUNT(*) FROM Guild")).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn query_one_value<T>(&mut self, statement: Statement) -> Result<T, QueryOneValueError>
+    where
+        T: TryFromValue,
+    {
+        let mut reader = self.query(statement).await?;
+        let row = match reader.next().await? {
+            Some(row) => row,
+            None => return Err(QueryOneValueError::NoRows),
+        };
+        if reader.next().await?.is_some() {
+            return Err(QueryOneValueError::TooManyRows);
+        }
+        match row.values().len() {
+            1 => Ok(row.column(0)?),
+            n => Err(QueryOneValueError::InvalidColumnCount(n)),
+        }
+    }
+
+    /// validate checks that the statement and its parameters are valid by planning it with QueryMode::Plan.
+    /// No data is scanned and the plan is discarded, so that the error of the server's parser and analyzer is
+    /// returned for the invalid statement. This is useful to detect the broken queries after the schema changes.
+    pub async fn validate(&mut self, statement: Statement) -> Result<(), Status> {
+        self.plan(statement).await.map(|_| ())
+    }
+
+    /// plan returns the query plan of the statement planned with QueryMode::Plan without executing it.
+    /// See Plan for the helpers to inspect the plan.
+    pub async fn plan(&mut self, statement: Statement) -> Result<Plan, Status> {
+        let options = QueryOptions {
+            mode: QueryMode::Plan,
+            ..Default::default()
+        };
+        let mut reader = self.query_with_option(statement, options).await?;
+        while reader.next().await?.is_some() {}
+        let plan = reader.stats().and_then(|s| s.query_plan.clone());
+        Ok(plan.map(Plan::from).unwrap_or_default())
     }
 
     /// read returns a RowIterator for reading multiple rows from the database.
@@ -158,22 +535,54 @@ impl Transaction {
         key_set: impl Into<KeySet>,
         options: ReadOptions,
     ) -> Result<RowIterator<'_>, Status> {
-        let request = ReadRequest {
-            session: self.get_session_name(),
-            transaction: Some(self.transaction_selector.clone()),
-            table: table.to_string(),
-            index: options.index,
-            columns: columns.iter().map(|x| x.to_string()).collect(),
-            key_set: Some(key_set.into().inner),
-            limit: options.limit,
-            resume_token: vec![],
-            partition_token: vec![],
-            request_options: Transaction::create_request_options(options.call_options.priority),
-        };
+        let columns = columns.iter().map(|x| x.to_string()).collect();
+        let requests = read_requests(
+            self.get_session_name(),
+            self.transaction_selector.clone(),
+            table,
+            columns,
+            key_set.into(),
+            &options,
+        );
+        self.read_requests(requests, options.call_options).await
+    }
 
+    /// read_with_template returns a RowIterator for reading the rows of the keys
+    /// with the table, the columns and the options of the template.
+    pub async fn read_with_template(
+        &mut self,
+        template: &ReadTemplate,
+        key_set: impl Into<KeySet>,
+    ) -> Result<RowIterator<'_>, Status> {
+        let requests = template.requests(self.get_session_name(), self.transaction_selector.clone(), key_set.into());
+        self.read_requests(requests, template.options.call_options.clone())
+            .await
+    }
+
+    /// read_requests returns the RowIterator reading the rows of the requests in sequence.
+    /// The requests split from one key set must share the transaction id to read one snapshot.
+    async fn read_requests(
+        &mut self,
+        requests: Vec<ReadRequest>,
+        call_options: CallOptions,
+    ) -> Result<RowIterator<'_>, Status> {
+        let shared = matches!(self.transaction_selector.selector, Some(transaction_selector::Selector::Id(_)));
+        if requests.len() > 1 && !shared {
+            return Err(Status::invalid_argument(format!(
+                "the key set split into {} requests by max_keys_per_request needs the transaction shared by \
+                 the requests, such as the read-only transaction, to read one snapshot",
+                requests.len()
+            )));
+        }
+        let read_write = self.read_write;
+        let mut readers = requests
+            .into_iter()
+            .map(|request| Box::new(TableReader { request, read_write }) as Box<dyn Reader + Sync + Send>);
+        let first = readers.next().unwrap();
         let session = self.as_mut_session();
-        let reader = Box::new(TableReader { request });
-        RowIterator::new(session, reader, Some(options.call_options)).await
+        let mut iter = RowIterator::new(session, first, Some(call_options.clone())).await?;
+        iter.queue_readers(readers, Some(call_options));
+        Ok(iter)
     }
 
     /// read returns a RowIterator for reading multiple rows from the database.
@@ -209,6 +618,59 @@ impl Transaction {
         reader.next().await
     }
 
+    /// batch_read_by_key reads the rows of the keys with one read request, and returns
+    /// the row of each key in the order of the keys. The row is None if the key is not found.
+    ///
+    /// Cloud Spanner returns the rows in the order of the primary key, so the rows are associated
+    /// with the keys by the values of the key columns.
+    /// The columns must start with the primary key columns in the order of the primary key.
+    /// ```
+    /// use google_cloud_spanner::client::{Client, Error};
+    /// use google_cloud_spanner::key::Key;
+    ///
+    /// async fn run(client: Client) -> Result<(), Error> {
+    ///     let mut tx = client.single().await?;
+    ///     let keys = vec![Key::new(&"user-1"), Key::new(&"user-2")];
+    ///     for (key, row) in tx.batch_read_by_key("User", keys, &["UserId", "Name"]).await? {
+    ///         if let Some(row) = row {
+    ///             let name = row.column_by_name::<String>("Name")?;
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn batch_read_by_key(
+        &mut self,
+        table: &str,
+        keys: Vec<Key>,
+        columns: &[&str],
+    ) -> Result<Vec<(Key, Option<Row>)>, Status> {
+        let mut reader = self.read(table, columns, keys.clone()).await?;
+        let mut rows = vec![];
+        while let Some(row) = reader.next().await? {
+            rows.push(row);
+        }
+        Ok(associate_rows(keys, rows))
+    }
+
+    /// selector_description returns the human readable description of the transaction selector
+    /// used by the requests such as `Id(0a1b)`, `SingleUse(ReadOnly)` and `Begin(ReadWrite)`.
+    /// This is intended for debugging and tracing.
+    pub fn selector_description(&self) -> String {
+        let mode = |options: &TransactionOptions| match options.mode.as_ref() {
+            Some(transaction_options::Mode::ReadWrite(_)) => "ReadWrite",
+            Some(transaction_options::Mode::PartitionedDml(_)) => "PartitionedDml",
+            Some(transaction_options::Mode::ReadOnly(_)) => "ReadOnly",
+            None => "None",
+        };
+        match self.transaction_selector.selector.as_ref() {
+            Some(transaction_selector::Selector::Id(id)) => format!("Id({})", hex(id)),
+            Some(transaction_selector::Selector::SingleUse(options)) => format!("SingleUse({})", mode(options)),
+            Some(transaction_selector::Selector::Begin(options)) => format!("Begin({})", mode(options)),
+            None => "None".to_string(),
+        }
+    }
+
     pub(crate) fn get_session_name(&self) -> String {
         return self.session.as_ref().unwrap().session.name.to_string();
     }
@@ -223,3 +685,372 @@ impl Transaction {
         self.session.take()
     }
 }
+
+/// hex returns the hex encoded bytes such as the transaction id.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// associate_rows returns the row of each key, comparing the key with the leading values of the row.
+fn associate_rows(keys: Vec<Key>, rows: Vec<Row>) -> Vec<(Key, Option<Row>)> {
+    let key_len = keys.first().map(|key| key.values.values.len()).unwrap_or_default();
+    let rows_by_key: HashMap<String, Row> = rows
+        .into_iter()
+        .map(|row| (format!("{:?}", &row.values()[..key_len.min(row.values().len())]), row))
+        .collect();
+    keys.into_iter()
+        .map(|key| {
+            let row = rows_by_key.get(&format!("{:?}", key.values.values.as_slice())).cloned();
+            (key, row)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::AtomicI64;
+    use std::sync::Arc;
+
+    use prost_types::{ListValue, Value};
+
+    use google_cloud_gax::conn::Environment;
+    use google_cloud_gax::grpc::Code;
+    use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+    use google_cloud_googleapis::spanner::v1::request_options::Priority;
+    use google_cloud_googleapis::spanner::v1::struct_type::Field;
+    use google_cloud_googleapis::spanner::v1::{
+        execute_sql_request::QueryMode, execute_sql_request::QueryOptions as ExecuteQueryOptions, transaction_options,
+        transaction_selector, ExecuteSqlRequest, ReadRequest, TransactionOptions, TransactionSelector,
+    };
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::client::{Client, ClientConfig};
+    use crate::key::{Key, KeySet};
+    use crate::reader::AsyncIterator;
+    use crate::row::Row;
+    use crate::statement::ToKind;
+    use crate::transaction::{
+        associate_rows, read_requests, CallOptions, LockHint, QueryOptions, ReadOptions, ReadTemplate, Transaction,
+    };
+
+    fn row(user_id: &str, item_id: i64, quantity: i64) -> Row {
+        let mut index = HashMap::new();
+        index.insert("UserId".to_string(), 0);
+        index.insert("ItemId".to_string(), 1);
+        index.insert("Quantity".to_string(), 2);
+        let fields = vec![
+            Field {
+                name: "UserId".to_string(),
+                r#type: Some(String::get_type()),
+            },
+            Field {
+                name: "ItemId".to_string(),
+                r#type: Some(i64::get_type()),
+            },
+            Field {
+                name: "Quantity".to_string(),
+                r#type: Some(i64::get_type()),
+            },
+        ];
+        let values = vec![
+            Value {
+                kind: Some(user_id.to_kind()),
+            },
+            Value {
+                kind: Some(item_id.to_kind()),
+            },
+            Value {
+                kind: Some(quantity.to_kind()),
+            },
+        ];
+        Row::new(Arc::new(index), Arc::new(fields), values)
+    }
+
+    #[test]
+    fn test_associate_rows() {
+        let keys = vec![
+            Key::composite(&[&"user-2", &1]),
+            Key::composite(&[&"user-3", &1]),
+            Key::composite(&[&"user-1", &2]),
+        ];
+        // the rows are returned in key order
+        let rows = vec![row("user-1", 2, 10), row("user-2", 1, 20)];
+        let result = associate_rows(keys, rows);
+        let quantities = result
+            .iter()
+            .map(|(_, row)| row.as_ref().map(|r| r.column_by_name::<i64>("Quantity").unwrap()))
+            .collect::<Vec<Option<i64>>>();
+        assert_eq!(vec![Some(20), None, Some(10)], quantities);
+    }
+
+    fn transaction(selector: transaction_selector::Selector) -> Transaction {
+        Transaction {
+            session: None,
+            sequence_number: AtomicI64::new(0),
+            transaction_selector: TransactionSelector {
+                selector: Some(selector),
+            },
+            dialect: DatabaseDialect::GoogleStandardSql,
+            read_write: false,
+        }
+    }
+
+    #[test]
+    fn test_selector_description() {
+        let tx = transaction(transaction_selector::Selector::Id(vec![0x0a, 0x1b, 0xff]));
+        assert_eq!("Id(0a1bff)", tx.selector_description());
+
+        let read_only = TransactionOptions {
+            mode: Some(transaction_options::Mode::ReadOnly(transaction_options::ReadOnly::default())),
+        };
+        let tx = transaction(transaction_selector::Selector::SingleUse(read_only));
+        assert_eq!("SingleUse(ReadOnly)", tx.selector_description());
+
+        let read_write = TransactionOptions {
+            mode: Some(transaction_options::Mode::ReadWrite(transaction_options::ReadWrite::default())),
+        };
+        let tx = transaction(transaction_selector::Selector::Begin(read_write));
+        assert_eq!("Begin(ReadWrite)", tx.selector_description());
+    }
+
+    #[test]
+    fn test_request_mutator() {
+        let mut request = ExecuteSqlRequest {
+            sql: "SELECT 1".to_string(),
+            ..Default::default()
+        };
+        QueryOptions::default().mutate_request(&mut request);
+        assert_eq!("SELECT 1", request.sql);
+        assert!(request.partition_token.is_empty());
+
+        let options = QueryOptions::default().with_request_mutator(|r| {
+            r.sql = "SELECT 2".to_string();
+            r.partition_token = vec![1, 2, 3];
+        });
+        options.clone().mutate_request(&mut request);
+        assert_eq!("SELECT 2", request.sql);
+        assert_eq!(vec![1, 2, 3], request.partition_token);
+    }
+
+    #[test]
+    fn test_read_request_options() {
+        assert!(ReadOptions::default().request_options().is_none());
+
+        let options = ReadOptions {
+            request_tag: Some("app=guild,action=list".to_string()),
+            ..Default::default()
+        };
+        let request_options = options.request_options().unwrap();
+        assert_eq!(request_options.request_tag, "app=guild,action=list");
+        assert_eq!(request_options.priority, Priority::Unspecified as i32);
+
+        let options = ReadOptions {
+            request_tag: Some("app=guild".to_string()),
+            call_options: CallOptions {
+                priority: Some(Priority::Low),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let request_options = options.request_options().unwrap();
+        assert_eq!(request_options.request_tag, "app=guild");
+        assert_eq!(request_options.priority, Priority::Low as i32);
+    }
+
+    #[test]
+    fn test_read_requests_split() {
+        let keys: Vec<Key> = (0..100_000_i64).map(|i| Key::new(&i)).collect();
+        let options = ReadOptions {
+            max_keys_per_request: Some(30_000),
+            ..Default::default()
+        };
+        let columns = vec!["GuildID".to_string()];
+        let requests = |key_set: KeySet, options: &ReadOptions| {
+            read_requests(
+                "session".to_string(),
+                TransactionSelector::default(),
+                "Guild",
+                columns.clone(),
+                key_set,
+                options,
+            )
+        };
+        let split = requests(KeySet::from(keys.clone()), &options);
+        assert_eq!(
+            split
+                .iter()
+                .map(|r| r.key_set.as_ref().unwrap().keys.len())
+                .collect::<Vec<usize>>(),
+            vec![30_000, 30_000, 30_000, 10_000]
+        );
+        let split_keys: Vec<ListValue> = split.into_iter().flat_map(|r| r.key_set.unwrap().keys).collect();
+        assert_eq!(split_keys, keys.iter().map(|k| k.values.clone()).collect::<Vec<ListValue>>());
+
+        // not split without max_keys_per_request or with the limit.
+        assert_eq!(requests(KeySet::from(keys.clone()), &ReadOptions::default()).len(), 1);
+        let limited = ReadOptions { limit: 10, ..options };
+        assert_eq!(requests(KeySet::from(keys), &limited).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_split_snapshot() {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let database = "projects/local-project/instances/test-instance/databases/local-database";
+        let client = Client::new(database, config).await.unwrap();
+        let keys: Vec<Key> = (0..100_i64).map(|i| Key::new(&i)).collect();
+        let options = ReadOptions {
+            max_keys_per_request: Some(30),
+            ..Default::default()
+        };
+
+        // the split requests of the single-use transaction would read at the different timestamps.
+        let mut tx = client.single().await.unwrap();
+        match tx
+            .read_with_option("Guild", &["GuildID"], keys.clone(), options.clone())
+            .await
+        {
+            Err(status) => assert_eq!(status.code(), Code::InvalidArgument),
+            Ok(_) => panic!("must not split the single-use read"),
+        }
+        assert!(fake.requests::<ReadRequest>("StreamingRead").is_empty());
+        // the key set within the limit is read in one request.
+        tx.read_with_option("Guild", &["GuildID"], keys[..30].to_vec(), options.clone())
+            .await
+            .unwrap();
+        assert_eq!(fake.requests::<ReadRequest>("StreamingRead").len(), 1);
+        drop(tx);
+
+        // the split requests of the read-only transaction share its snapshot.
+        let mut tx = client.read_only_transaction().await.unwrap();
+        let mut reader = tx.read_with_option("Guild", &["GuildID"], keys, options).await.unwrap();
+        while reader.next().await.unwrap().is_some() {}
+        drop(reader);
+        let reads = fake.requests::<ReadRequest>("StreamingRead");
+        assert_eq!(reads.len(), 5);
+        // the fake returns "transaction" as the transaction id.
+        let selector = Some(TransactionSelector {
+            selector: Some(transaction_selector::Selector::Id(b"transaction".to_vec())),
+        });
+        assert!(reads[1..].iter().all(|(_, read)| read.transaction == selector));
+        drop(tx);
+        client.close().await;
+    }
+
+    #[test]
+    fn test_template_read_request() {
+        let template = ReadTemplate::new("Guild", &["GuildID", "OwnerUserID"]).with_options(ReadOptions {
+            index: "GuildByOwnerUserID".to_string(),
+            limit: 10,
+            request_tag: Some("app=guild".to_string()),
+            ..Default::default()
+        });
+        let request = |key: &str| {
+            template
+                .requests(
+                    "session".to_string(),
+                    TransactionSelector::default(),
+                    KeySet::from(Key::new(&key.to_string())),
+                )
+                .pop()
+                .unwrap()
+        };
+        let first = request("guild1");
+        let second = request("guild2");
+        assert_eq!(first.session, "session");
+        assert_eq!(first.table, "Guild");
+        assert_eq!(first.columns, vec!["GuildID", "OwnerUserID"]);
+        assert_eq!(first.index, "GuildByOwnerUserID");
+        assert_eq!(first.limit, 10);
+        assert_eq!(first.request_options.as_ref().unwrap().request_tag, "app=guild");
+        assert_ne!(first.key_set, second.key_set);
+        assert_eq!(
+            ReadRequest { key_set: None, ..first },
+            ReadRequest {
+                key_set: None,
+                ..second
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_options_builder() {
+        let optimizer_options = ExecuteQueryOptions {
+            optimizer_version: "5".to_string(),
+            optimizer_statistics_package: "auto_20230101_00_00_00UTC".to_string(),
+        };
+        let built = QueryOptions::default()
+            .with_mode(QueryMode::Profile)
+            .with_optimizer_options(optimizer_options.clone())
+            .with_call_options(CallOptions {
+                priority: Some(Priority::Low),
+                ..Default::default()
+            })
+            .with_lock_hint(LockHint::Shared)
+            .with_request_mutator(|request| request.seqno = 10);
+
+        let manual = QueryOptions {
+            mode: QueryMode::Profile,
+            optimizer_options: Some(optimizer_options),
+            call_options: CallOptions {
+                priority: Some(Priority::Low),
+                ..Default::default()
+            },
+            request_mutator: Some(Arc::new(|request: &mut ExecuteSqlRequest| request.seqno = 10)),
+            lock_hint: Some(LockHint::Shared),
+        };
+
+        for options in [&built, &manual] {
+            assert_eq!(options.mode, QueryMode::Profile);
+            assert_eq!(options.optimizer_options, manual.optimizer_options);
+            assert_eq!(options.call_options.priority, Some(Priority::Low));
+            assert!(options.call_options.retry.is_none());
+            assert!(options.call_options.cancel.is_none());
+            assert_eq!(options.lock_hint, Some(LockHint::Shared));
+            let mut request = ExecuteSqlRequest::default();
+            options.mutate_request(&mut request);
+            assert_eq!(request.seqno, 10);
+        }
+    }
+
+    #[test]
+    fn test_lock_hint() {
+        let google = DatabaseDialect::GoogleStandardSql;
+        let sql = "SELECT Quantity FROM UserItem WHERE UserId = @UserId".to_string();
+        assert_eq!(QueryOptions::default().sql(sql.clone(), google), sql);
+
+        let options = QueryOptions {
+            lock_hint: Some(LockHint::Exclusive),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.sql(sql.clone(), google),
+            "@{LOCK_SCANNED_RANGES=exclusive} SELECT Quantity FROM UserItem WHERE UserId = @UserId"
+        );
+
+        let options = QueryOptions {
+            lock_hint: Some(LockHint::Shared),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.sql(sql.clone(), google),
+            "@{LOCK_SCANNED_RANGES=shared} SELECT Quantity FROM UserItem WHERE UserId = @UserId"
+        );
+
+        // merged into the existing statement hint
+        let hinted = "@{USE_ADDITIONAL_PARALLELISM=TRUE} SELECT Quantity FROM UserItem".to_string();
+        assert_eq!(
+            options.sql(hinted, google),
+            "@{LOCK_SCANNED_RANGES=shared, USE_ADDITIONAL_PARALLELISM=TRUE} SELECT Quantity FROM UserItem"
+        );
+        let locked = "@{lock_scanned_ranges=exclusive} SELECT Quantity FROM UserItem".to_string();
+        assert_eq!(options.sql(locked.clone(), google), locked);
+
+        // not supported by the PostgreSQL dialect
+        assert_eq!(options.sql(sql.clone(), DatabaseDialect::Postgresql), sql);
+    }
+}
@@ -5,6 +5,7 @@ use std::time::SystemTime;
 use time::OffsetDateTime;
 
 use google_cloud_gax::grpc::Status;
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
 use google_cloud_googleapis::spanner::v1::{
     transaction_options, transaction_selector, BeginTransactionRequest, ExecuteSqlRequest, PartitionOptions,
     PartitionQueryRequest, PartitionReadRequest, ReadRequest, TransactionOptions, TransactionSelector,
@@ -13,7 +14,7 @@ use google_cloud_googleapis::spanner::v1::{
 use crate::key::KeySet;
 use crate::reader::{Reader, RowIterator, StatementReader, TableReader};
 use crate::session::ManagedSession;
-use crate::statement::Statement;
+use crate::statement::{dialect_param_types, Statement};
 use crate::transaction::{CallOptions, QueryOptions, ReadOptions, Transaction};
 use crate::value::TimestampBound;
 
@@ -56,6 +57,8 @@ impl ReadOnlyTransaction {
             base_tx: Transaction {
                 session: Some(session),
                 sequence_number: AtomicI64::new(0),
+                dialect: DatabaseDialect::GoogleStandardSql,
+                read_write: false,
                 transaction_selector: TransactionSelector {
                     selector: Some(transaction_selector::Selector::SingleUse(TransactionOptions {
                         mode: Some(transaction_options::Mode::ReadOnly(tb.into())),
@@ -93,6 +96,8 @@ impl ReadOnlyTransaction {
                     base_tx: Transaction {
                         session: Some(session),
                         sequence_number: AtomicI64::new(0),
+                        dialect: DatabaseDialect::GoogleStandardSql,
+                        read_write: false,
                         transaction_selector: TransactionSelector {
                             selector: Some(transaction_selector::Selector::Id(tx.id)),
                         },
@@ -160,6 +165,10 @@ impl BatchReadOnlyTransaction {
     /// the database. These partitions can be executed across multiple processes,
     /// even across different machines. The partition size and count hints can be
     /// configured using PartitionOptions.
+    ///
+    /// PartitionOptions.partition_size_bytes is the desired data size of each partition and
+    /// PartitionOptions.max_partitions is the desired maximum number of partitions. Both are hints,
+    /// and 0 or None leaves them to the server (currently 1 GiB and 10,000 partitions).
     pub async fn partition_read_with_option(
         &mut self,
         table: &str,
@@ -168,21 +177,19 @@ impl BatchReadOnlyTransaction {
         po: Option<PartitionOptions>,
         ro: ReadOptions,
     ) -> Result<Vec<Partition<TableReader>>, Status> {
-        let columns: Vec<String> = columns.iter().map(|x| x.to_string()).collect();
-        let inner_keyset = keys.into().inner;
         let request = PartitionReadRequest {
             session: self.get_session_name(),
             transaction: Some(self.transaction_selector.clone()),
             table: table.to_string(),
             index: ro.index.clone(),
-            columns: columns.clone(),
-            key_set: Some(inner_keyset.clone()),
-            partition_options: po,
+            columns: columns.iter().map(|x| x.to_string()).collect(),
+            key_set: Some(keys.into().inner),
+            partition_options: partition_options(po),
         };
         let result = match self
             .as_mut_session()
             .spanner_client
-            .partition_read(request, ro.call_options.cancel, ro.call_options.retry)
+            .partition_read(request.clone(), ro.call_options.cancel.clone(), ro.call_options.retry.clone())
             .await
         {
             Ok(r) => Ok(r
@@ -191,18 +198,8 @@ impl BatchReadOnlyTransaction {
                 .into_iter()
                 .map(|x| Partition {
                     reader: TableReader {
-                        request: ReadRequest {
-                            session: self.get_session_name(),
-                            transaction: Some(self.transaction_selector.clone()),
-                            table: table.to_string(),
-                            index: ro.index.clone(),
-                            columns: columns.clone(),
-                            key_set: Some(inner_keyset.clone()),
-                            limit: ro.limit,
-                            resume_token: vec![],
-                            partition_token: x.partition_token,
-                            request_options: Transaction::create_request_options(ro.call_options.priority),
-                        },
+                        request: partition_read_request(&request, &ro, x.partition_token),
+                        read_write: false,
                     },
                 })
                 .collect()),
@@ -218,6 +215,7 @@ impl BatchReadOnlyTransaction {
     }
 
     /// partition_query returns a list of Partitions that can be used to execute a query against the database.
+    /// See partition_read_with_option for the hints of PartitionOptions.
     pub async fn partition_query_with_option(
         &mut self,
         stmt: Statement,
@@ -231,8 +229,8 @@ impl BatchReadOnlyTransaction {
             params: Some(prost_types::Struct {
                 fields: stmt.params.clone(),
             }),
-            param_types: stmt.param_types.clone(),
-            partition_options: po,
+            param_types: dialect_param_types(stmt.param_types.clone(), self.dialect),
+            partition_options: partition_options(po),
         };
         let result = match self
             .as_mut_session()
@@ -246,21 +244,8 @@ impl BatchReadOnlyTransaction {
                 .into_iter()
                 .map(|x| Partition {
                     reader: StatementReader {
-                        request: ExecuteSqlRequest {
-                            session: self.get_session_name(),
-                            transaction: Some(self.transaction_selector.clone()),
-                            sql: stmt.sql.clone(),
-                            params: Some(prost_types::Struct {
-                                fields: stmt.params.clone(),
-                            }),
-                            param_types: stmt.param_types.clone(),
-                            resume_token: vec![],
-                            query_mode: 0,
-                            partition_token: x.partition_token,
-                            seqno: 0,
-                            query_options: qo.optimizer_options.clone(),
-                            request_options: Transaction::create_request_options(qo.call_options.priority),
-                        },
+                        request: partition_query_request(&request, &qo, x.partition_token),
+                        read_write: false,
                     },
                 })
                 .collect()),
@@ -270,12 +255,206 @@ impl BatchReadOnlyTransaction {
     }
 
     /// execute runs a single Partition obtained from partition_read or partition_query.
+    /// The priority of the partition is the priority of the ReadOptions or the QueryOptions given to
+    /// partition_read_with_option or partition_query_with_option, and the priority of the option overrides it,
+    /// e.g. Priority::Low for the bulk exports not to contend with the serving traffic.
     pub async fn execute<T: Reader + Sync + Send + 'static>(
         &mut self,
-        partition: Partition<T>,
+        mut partition: Partition<T>,
         option: Option<CallOptions>,
     ) -> Result<RowIterator<'_>, Status> {
+        if let Some(priority) = option.as_ref().and_then(|o| o.priority) {
+            partition.reader.set_priority(priority);
+        }
         let session = self.as_mut_session();
         RowIterator::new(session, Box::new(partition.reader), option).await
     }
 }
+
+/// partition_read_request creates the ReadRequest of the partition with the request options of the ReadOptions.
+fn partition_read_request(request: &PartitionReadRequest, ro: &ReadOptions, partition_token: Vec<u8>) -> ReadRequest {
+    ReadRequest {
+        session: request.session.clone(),
+        transaction: request.transaction.clone(),
+        table: request.table.clone(),
+        index: request.index.clone(),
+        columns: request.columns.clone(),
+        key_set: request.key_set.clone(),
+        limit: ro.limit,
+        resume_token: vec![],
+        partition_token,
+        request_options: ro.request_options(),
+    }
+}
+
+/// partition_query_request creates the ExecuteSqlRequest of the partition with the request options of the QueryOptions.
+fn partition_query_request(
+    request: &PartitionQueryRequest,
+    qo: &QueryOptions,
+    partition_token: Vec<u8>,
+) -> ExecuteSqlRequest {
+    let mut execute_request = ExecuteSqlRequest {
+        session: request.session.clone(),
+        transaction: request.transaction.clone(),
+        sql: request.sql.clone(),
+        params: request.params.clone(),
+        param_types: request.param_types.clone(),
+        resume_token: vec![],
+        query_mode: 0,
+        partition_token,
+        seqno: 0,
+        query_options: qo.optimizer_options.clone(),
+        request_options: Transaction::create_request_options(qo.call_options.priority),
+    };
+    qo.mutate_request(&mut execute_request);
+    execute_request
+}
+
+/// partition_options returns the hints sent with the partition request. The hints of 0 are left to the server,
+/// so no PartitionOptions is sent if neither hint is set.
+fn partition_options(po: Option<PartitionOptions>) -> Option<PartitionOptions> {
+    po.filter(|po| po.partition_size_bytes > 0 || po.max_partitions > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use google_cloud_googleapis::spanner::v1::request_options::Priority;
+    use google_cloud_googleapis::spanner::v1::{PartitionOptions, PartitionQueryRequest, PartitionReadRequest};
+
+    use google_cloud_gax::conn::Environment;
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::client::{Client, ClientConfig};
+    use crate::key::all_keys;
+    use crate::reader::{Reader, StatementReader, TableReader};
+    use crate::statement::Statement;
+    use crate::transaction::{CallOptions, QueryOptions, ReadOptions};
+    use crate::transaction_ro::{partition_options, partition_query_request, partition_read_request};
+
+    fn low() -> CallOptions {
+        CallOptions {
+            priority: Some(Priority::Low),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_partition_request_priority() {
+        let read = PartitionReadRequest {
+            session: "session".to_string(),
+            table: "User".to_string(),
+            columns: vec!["UserId".to_string()],
+            ..Default::default()
+        };
+        let ro = ReadOptions {
+            request_tag: Some("export".to_string()),
+            call_options: low(),
+            ..Default::default()
+        };
+        let request = partition_read_request(&read, &ro, vec![1]);
+        assert_eq!(request.partition_token, vec![1]);
+        assert_eq!(request.table, "User");
+        let request_options = request.request_options.unwrap();
+        assert_eq!(request_options.priority, Priority::Low as i32);
+        assert_eq!(request_options.request_tag, "export");
+
+        let query = PartitionQueryRequest {
+            session: "session".to_string(),
+            sql: "SELECT * FROM User".to_string(),
+            ..Default::default()
+        };
+        let qo = QueryOptions {
+            call_options: low(),
+            ..Default::default()
+        };
+        let request = partition_query_request(&query, &qo, vec![2]);
+        assert_eq!(request.partition_token, vec![2]);
+        assert_eq!(request.sql, "SELECT * FROM User");
+        assert_eq!(request.request_options.unwrap().priority, Priority::Low as i32);
+    }
+
+    #[test]
+    fn test_partition_execute_priority() {
+        // the priority given to execute overrides the priority of the partition and keeps the tag.
+        let ro = ReadOptions {
+            request_tag: Some("export".to_string()),
+            call_options: CallOptions {
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut reader = TableReader {
+            request: partition_read_request(&PartitionReadRequest::default(), &ro, vec![1]),
+            read_write: false,
+        };
+        reader.set_priority(Priority::Low);
+        let request_options = reader.request.request_options.unwrap();
+        assert_eq!(request_options.priority, Priority::Low as i32);
+        assert_eq!(request_options.request_tag, "export");
+
+        // the partition without the priority
+        let mut reader = StatementReader {
+            request: partition_query_request(&PartitionQueryRequest::default(), &QueryOptions::default(), vec![2]),
+            read_write: false,
+        };
+        assert!(reader.request.request_options.is_none());
+        reader.set_priority(Priority::Low);
+        assert_eq!(reader.request.request_options.unwrap().priority, Priority::Low as i32);
+    }
+
+    #[test]
+    fn test_partition_options() {
+        let po = PartitionOptions {
+            partition_size_bytes: 64 * 1024 * 1024,
+            max_partitions: 8,
+        };
+        assert_eq!(partition_options(Some(po.clone())), Some(po));
+        let size_only = PartitionOptions {
+            partition_size_bytes: 1024,
+            max_partitions: 0,
+        };
+        assert_eq!(partition_options(Some(size_only.clone())), Some(size_only));
+
+        // the server defaults
+        assert!(partition_options(None).is_none());
+        assert!(partition_options(Some(PartitionOptions::default())).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_partition_options_request() {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let database = "projects/local-project/instances/test-instance/databases/local-database";
+        let client = Client::new(database, config).await.unwrap();
+        let mut tx = client.batch_read_only_transaction().await.unwrap();
+        let po = PartitionOptions {
+            partition_size_bytes: 64 * 1024 * 1024,
+            max_partitions: 8,
+        };
+        let stmt = Statement::new("SELECT * FROM User");
+
+        tx.partition_read("User", &["UserId"], all_keys()).await.unwrap();
+        tx.partition_read_with_option("User", &["UserId"], all_keys(), Some(po.clone()), ReadOptions::default())
+            .await
+            .unwrap();
+        tx.partition_query(stmt.clone()).await.unwrap();
+        tx.partition_query_with_option(stmt, Some(po.clone()), QueryOptions::default())
+            .await
+            .unwrap();
+
+        let reads = fake.requests::<PartitionReadRequest>("PartitionRead");
+        assert_eq!(reads.len(), 2);
+        assert!(reads[0].1.partition_options.is_none());
+        assert_eq!(reads[1].1.partition_options, Some(po.clone()));
+        let queries = fake.requests::<PartitionQueryRequest>("PartitionQuery");
+        assert_eq!(queries.len(), 2);
+        assert!(queries[0].1.partition_options.is_none());
+        assert_eq!(queries[1].1.partition_options, Some(po));
+        drop(tx);
+        client.close().await;
+    }
+}
@@ -1,28 +1,252 @@
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use prost::Message;
 use prost_types::Struct;
 
 use google_cloud_gax::cancel::CancellationToken;
+use google_cloud_gax::grpc::codegen::Bytes;
 use google_cloud_gax::grpc::{Code, Status};
 use google_cloud_gax::retry::{RetrySetting, TryAs};
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
 use google_cloud_googleapis::spanner::v1::commit_request::Transaction::TransactionId;
+use google_cloud_googleapis::spanner::v1::execute_sql_request::QueryMode;
+use google_cloud_googleapis::spanner::v1::mutation::Operation;
+use google_cloud_googleapis::spanner::v1::request_options::Priority;
+use google_cloud_googleapis::spanner::v1::transaction_options::read_write::ReadLockMode;
 use google_cloud_googleapis::spanner::v1::{
     commit_request, execute_batch_dml_request, result_set_stats, transaction_options, transaction_selector,
-    BeginTransactionRequest, CommitRequest, CommitResponse, ExecuteBatchDmlRequest, ExecuteSqlRequest, Mutation,
-    ResultSetStats, RollbackRequest, TransactionOptions, TransactionSelector,
+    BeginTransactionRequest, CommitRequest, CommitResponse, ExecuteBatchDmlRequest, ExecuteBatchDmlResponse,
+    ExecuteSqlRequest, Mutation, RequestOptions, ResultSetStats, RollbackRequest, TransactionOptions,
+    TransactionSelector,
 };
 
-use crate::session::ManagedSession;
-use crate::statement::Statement;
-use crate::transaction::{CallOptions, QueryOptions, Transaction};
+use crate::mutation;
+use crate::retry::is_transient_internal;
+use crate::session::{is_session_not_found, ManagedSession};
+use crate::statement::{dialect_param_types, Statement};
+use crate::transaction::{hex, CallOptions, QueryOptions, Transaction};
 use crate::value::Timestamp;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Debug)]
 pub struct CommitOptions {
     pub return_commit_stats: bool,
     pub call_options: CallOptions,
+    /// on_commit_deadline is the handling of DEADLINE_EXCEEDED returned by the commit.
+    pub on_commit_deadline: CommitDeadlinePolicy,
+    /// return_audit returns the CommitAudit of the buffered mutations in the CommitResult.
+    pub return_audit: bool,
+    /// transaction_tag is the tag of the transaction shown in the transaction statistics tables.
+    pub transaction_tag: Option<String>,
+    /// on_empty_commit is the handling of the commit of the transaction without any write.
+    pub on_empty_commit: EmptyCommitPolicy,
+    /// on_application_error is the handling of the error returned by the application, which is not a Status.
+    pub on_application_error: ApplicationErrorPolicy,
+}
+
+/// ApplicationErrorPolicy decides how to end the transaction when the function returns an error without a Status.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ApplicationErrorPolicy {
+    /// Rollback rolls back the transaction and returns the error.
+    #[default]
+    Rollback,
+    /// CommitThenReturn commits the buffered writes and returns the error, treating the error as advisory.
+    /// If the commit fails, the error of the commit is returned instead, so that the aborted transaction is retried.
+    CommitThenReturn,
+}
+
+impl ApplicationErrorPolicy {
+    fn should_commit<E: TryAs<Status>>(&self, err: &E) -> bool {
+        *self == ApplicationErrorPolicy::CommitThenReturn && err.try_as().is_none()
+    }
+}
+
+/// EmptyCommitPolicy decides how to end the transaction that neither buffered a mutation nor executed a DML.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum EmptyCommitPolicy {
+    /// Commit sends the CommitRequest without any mutation.
+    #[default]
+    Commit,
+    /// Skip sends no RPC. The CommitResult has no commit timestamp.
+    Skip,
+    /// Rollback rolls back the transaction instead of committing it to release the locks held by the reads.
+    /// The CommitResult has no commit timestamp.
+    Rollback,
+}
+
+/// CommitDeadlinePolicy decides how to handle the commit failed with DEADLINE_EXCEEDED.
+/// The commit may have succeeded in Cloud Spanner even though the client stopped waiting for the response.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum CommitDeadlinePolicy {
+    /// Leave returns the error as is without rolling back the transaction.
+    #[default]
+    Leave,
+    /// AttemptRollback tries to roll back the transaction before returning the error.
+    /// The rollback fails if the commit has already succeeded, so the transaction may still be committed.
+    AttemptRollback,
+    /// TreatAsUnknown returns the error with UNKNOWN code to tell that the outcome of the commit is unknown.
+    TreatAsUnknown,
+}
+
+impl CommitDeadlinePolicy {
+    fn should_rollback(&self, status: &Status) -> bool {
+        *self == CommitDeadlinePolicy::AttemptRollback && status.code() == Code::DeadlineExceeded
+    }
+
+    fn convert(&self, status: Status) -> Status {
+        if *self != CommitDeadlinePolicy::TreatAsUnknown || status.code() != Code::DeadlineExceeded {
+            return status;
+        }
+        Status::with_details_and_metadata(
+            Code::Unknown,
+            format!("commit outcome is unknown: {}", status.message()),
+            status.details().to_vec().into(),
+            status.metadata().clone(),
+        )
+    }
+}
+
+/// CommitResult is the result of the commit shared by all the write paths.
+#[derive(Clone, Default)]
+pub struct CommitResult {
+    /// timestamp is the Cloud Spanner timestamp at which the transaction committed.
+    pub timestamp: Option<Timestamp>,
+    /// mutation_count is the number of mutations in the transaction.
+    /// It is returned only if CommitOptions.return_commit_stats is true.
+    pub mutation_count: Option<i64>,
+    /// audit is the summary of the mutations committed by the transaction.
+    /// It is returned only if CommitOptions.return_audit is true.
+    pub audit: Option<CommitAudit>,
+}
+
+impl CommitResult {
+    /// commit_stats returns the statistics of the commit if CommitOptions.return_commit_stats is true.
+    pub fn commit_stats(&self) -> Option<CommitStats> {
+        self.mutation_count.map(|mutation_count| CommitStats { mutation_count })
+    }
+}
+
+/// CommitStats is the statistics of the commit.
+/// It is serializable with the serde feature to be logged as the structured data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CommitStats {
+    /// mutation_count is the number of mutations in the transaction.
+    pub mutation_count: i64,
+}
+
+impl From<CommitResponse> for CommitResult {
+    fn from(value: CommitResponse) -> Self {
+        Self {
+            timestamp: value.commit_timestamp.map(|ts| ts.into()),
+            mutation_count: value.commit_stats.map(|s| s.mutation_count),
+            audit: None,
+        }
+    }
+}
+
+/// TransactionStats is the statistics of the operations performed in a read-write transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionStats {
+    /// buffered_mutations is the number of the mutations buffered to be committed.
+    pub buffered_mutations: usize,
+    /// estimated_mutations is the mutation count of the buffered mutations toward the commit limit.
+    /// See mutation::mutation_count for the estimation.
+    pub estimated_mutations: usize,
+    /// statements is the number of the DML statements executed successfully.
+    pub statements: usize,
+}
+
+/// MutationOperation is the kind of the write of a Mutation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MutationOperation {
+    Insert,
+    Update,
+    InsertOrUpdate,
+    Replace,
+    Delete,
+}
+
+/// MutationSummary is the summary of the mutations of one operation to one table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationSummary {
+    pub table: String,
+    pub operation: MutationOperation,
+    /// key_count is the number of the rows written, or the number of the keys deleted.
+    /// A deleted key range and the deletion of all the keys are counted as one key.
+    pub key_count: usize,
+}
+
+/// CommitAudit is the summary of the mutations written by a transaction for the audit logging.
+/// It is derived from the buffered mutations without any RPC,
+/// so the rows written by the DML statements are not included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommitAudit {
+    /// transaction_id is the hex encoded id of the transaction.
+    pub transaction_id: String,
+    /// mutations are the summaries per table and operation in the order of the first appearance.
+    pub mutations: Vec<MutationSummary>,
+}
+
+impl CommitAudit {
+    pub fn new(transaction_id: String, ms: &[Mutation]) -> Self {
+        let mut mutations: Vec<MutationSummary> = vec![];
+        for m in ms {
+            let (table, operation, key_count) = match m.operation.as_ref() {
+                Some(Operation::Insert(w)) => (&w.table, MutationOperation::Insert, w.values.len()),
+                Some(Operation::Update(w)) => (&w.table, MutationOperation::Update, w.values.len()),
+                Some(Operation::InsertOrUpdate(w)) => (&w.table, MutationOperation::InsertOrUpdate, w.values.len()),
+                Some(Operation::Replace(w)) => (&w.table, MutationOperation::Replace, w.values.len()),
+                Some(Operation::Delete(d)) => (&d.table, MutationOperation::Delete, mutation::mutation_count(m)),
+                None => continue,
+            };
+            match mutations
+                .iter_mut()
+                .find(|s| s.table == *table && s.operation == operation)
+            {
+                Some(summary) => summary.key_count += key_count,
+                None => mutations.push(MutationSummary {
+                    table: table.to_string(),
+                    operation,
+                    key_count,
+                }),
+            }
+        }
+        CommitAudit {
+            transaction_id,
+            mutations,
+        }
+    }
+}
+
+/// TransactionHook observes the lifecycle of the read-write transaction to build the instrumentation
+/// such as the metrics and the tracing. Every method does nothing by default.
+/// The methods are called synchronously in the transaction, so they should return quickly.
+pub trait TransactionHook: Send + Sync {
+    /// on_begin is called after the transaction is begun.
+    fn on_begin(&self, _event: &TransactionEvent) {}
+    /// on_statement is called after the DML statements are executed with one request.
+    fn on_statement(&self, _event: &TransactionEvent, _statements: &[String], _result: Result<(), &Status>) {}
+    /// on_commit is called after the commit. The timestamp is None if the commit RPC is skipped.
+    fn on_commit(&self, _event: &TransactionEvent, _result: Result<Option<&Timestamp>, &Status>) {}
+    /// on_rollback is called after the rollback.
+    fn on_rollback(&self, _event: &TransactionEvent, _result: Result<(), &Status>) {}
+    /// on_abort is called when the transaction is aborted by Cloud Spanner and will be retried.
+    fn on_abort(&self, _event: &TransactionEvent, _status: &Status) {}
+}
+
+/// TransactionEvent is the context of the event of the TransactionHook.
+#[derive(Clone, Debug)]
+pub struct TransactionEvent {
+    /// transaction_id is the hex encoded id of the transaction.
+    pub transaction_id: String,
+    /// time is the time when the event occurred.
+    pub time: SystemTime,
+    /// elapsed is the elapsed time since the begin of the transaction was requested.
+    pub elapsed: Duration,
 }
 
 /// ReadWriteTransaction provides a locking read-write transaction.
@@ -77,10 +301,41 @@ pub struct CommitOptions {
 /// successfully committing. Thus, it is not a good idea to cap the number of
 /// retries a transaction can attempt; instead, it is better to limit the total
 /// amount of wall time spent retrying.
+///
+/// Statement retries
+///
+/// The reads and the queries failed with UNAVAILABLE, UNKNOWN or the transient INTERNAL error are
+/// re-issued alone, because they are idempotent and resumed with the resume token.
+/// The DML statements are not re-issued alone by default, since the effects of the failed attempt are
+/// unknown to the following statements. Instead, the transaction is rolled back and the error is returned
+/// as ABORTED, so that Client::read_write_transaction retries the whole transaction.
+/// Setting the retry of the call options of the DML statement re-issues the statement alone with the setting.
+///
+/// Concurrent operations
+///
+/// The statements and the commit take `&mut self`, and the RowIterator of a query or a read borrows the
+/// transaction until it is dropped, so operations never overlap on the same transaction and the statements
+/// are always sent in their sequence. Overlapping operations are rejected at compile time:
+/// ```compile_fail,E0499
+/// use google_cloud_spanner::statement::Statement;
+/// use google_cloud_spanner::transaction_rw::ReadWriteTransaction;
+///
+/// async fn run(tx: &mut ReadWriteTransaction) {
+///     let first = tx.update(Statement::new("UPDATE User SET Name = 'a' WHERE true"));
+///     let second = tx.update(Statement::new("UPDATE User SET Name = 'b' WHERE true"));
+///     let _ = (first.await, second.await);
+/// }
+/// ```
+/// To run the operations from multiple tasks, share the transaction behind an async mutex,
+/// which serializes them.
 pub struct ReadWriteTransaction {
     base_tx: Transaction,
     tx_id: Vec<u8>,
     wb: Vec<Mutation>,
+    default_commit_options: CommitOptions,
+    statement_count: usize,
+    hook: Option<Arc<dyn TransactionHook>>,
+    began_at: Instant,
 }
 
 impl Deref for ReadWriteTransaction {
@@ -97,41 +352,222 @@ impl DerefMut for ReadWriteTransaction {
     }
 }
 
+/// TransactionError is the error of the commit or the rollback with the transaction it was for.
+/// It is returned as client::Error::Transaction. The other error types receive it as the Status whose message
+/// has the context, from which TransactionError::from_status takes it back.
+#[derive(thiserror::Error, Debug)]
+pub enum TransactionError {
+    #[error("failed to commit: transaction_id={transaction_id}, session={session_name}, {}", .status.message())]
+    Commit {
+        /// transaction_id is the hex encoded id of the transaction.
+        transaction_id: String,
+        session_name: String,
+        #[source]
+        status: Status,
+    },
+    #[error("failed to rollback: transaction_id={transaction_id}, session={session_name}, {}", .status.message())]
+    Rollback {
+        /// transaction_id is the hex encoded id of the transaction.
+        transaction_id: String,
+        session_name: String,
+        #[source]
+        status: Status,
+    },
+}
+
+impl TransactionError {
+    /// transaction_id returns the hex encoded id of the transaction.
+    pub fn transaction_id(&self) -> &str {
+        match self {
+            TransactionError::Commit { transaction_id, .. } => transaction_id,
+            TransactionError::Rollback { transaction_id, .. } => transaction_id,
+        }
+    }
+
+    /// session_name returns the name of the session of the transaction.
+    pub fn session_name(&self) -> &str {
+        match self {
+            TransactionError::Commit { session_name, .. } => session_name,
+            TransactionError::Rollback { session_name, .. } => session_name,
+        }
+    }
+
+    /// status returns the status returned by the commit or the rollback.
+    pub fn status(&self) -> &Status {
+        match self {
+            TransactionError::Commit { status, .. } => status,
+            TransactionError::Rollback { status, .. } => status,
+        }
+    }
+
+    /// from_status returns the TransactionError the status was converted from.
+    pub fn from_status(status: &Status) -> Option<&TransactionError> {
+        let context = std::error::Error::source(status)?.downcast_ref::<TransactionContext>()?;
+        Some(&context.error)
+    }
+
+    /// copy returns the TransactionError with the copy of the status, which is not Clone.
+    pub(crate) fn copy(&self) -> TransactionError {
+        let status = self.status();
+        let status = Status::with_details_and_metadata(
+            status.code(),
+            status.message(),
+            status.details().to_vec().into(),
+            status.metadata().clone(),
+        );
+        match self {
+            TransactionError::Commit {
+                transaction_id,
+                session_name,
+                ..
+            } => TransactionError::Commit {
+                transaction_id: transaction_id.clone(),
+                session_name: session_name.clone(),
+                status,
+            },
+            TransactionError::Rollback {
+                transaction_id,
+                session_name,
+                ..
+            } => TransactionError::Rollback {
+                transaction_id: transaction_id.clone(),
+                session_name: session_name.clone(),
+                status,
+            },
+        }
+    }
+}
+
+impl TryAs<Status> for TransactionError {
+    fn try_as(&self) -> Option<&Status> {
+        Some(self.status())
+    }
+}
+
+/// TransactionContext is the source of the Status converted from the TransactionError.
+/// Its own source is the status with the context in the message, which the Status is built from.
+#[derive(thiserror::Error, Debug)]
+#[error("{error}")]
+struct TransactionContext {
+    error: TransactionError,
+    #[source]
+    status: Status,
+}
+
+/// The code, details and metadata of the underlying status are preserved and the context is added to the message,
+/// so that the retry and the error handling based on the code work the same.
+impl From<TransactionError> for Status {
+    fn from(err: TransactionError) -> Self {
+        let message = err.to_string();
+        let status = err.status();
+        let status = Status::with_details_and_metadata(
+            status.code(),
+            message,
+            status.details().to_vec().into(),
+            status.metadata().clone(),
+        );
+        Status::from_error(Box::new(TransactionContext { error: err, status }))
+    }
+}
+
+/// should_rollback returns false if the transaction can't be rolled back and should be retried.
+/// ABORTED means the transaction was already rolled back by Cloud Spanner, and the session not found means
+/// the session was deleted, so the transaction should be retried with the new session.
+/// The other NotFound errors such as the row not found are caused by the user's logic, so the transaction
+/// is rolled back.
+fn should_rollback<E: TryAs<Status>>(err: &E) -> bool {
+    match err.try_as() {
+        Some(status) => status.code() != Code::Aborted && !is_session_not_found(status),
+        None => true,
+    }
+}
+
 pub struct BeginError {
     pub status: Status,
     pub session: ManagedSession,
 }
 
+impl BeginError {
+    /// into_reusable returns the status and the session only if the session can be reused for the retry.
+    /// The session poisoned by the error, such as the session deleted on the server side, is dropped
+    /// so that the pool discards it, and the other sessions are returned to be reused or dropped into the pool.
+    pub fn into_reusable(self) -> (Status, Option<ManagedSession>) {
+        let mut session = self.session;
+        if is_session_not_found(&self.status) {
+            session.invalidate();
+            return (self.status, None);
+        }
+        (self.status, Some(session))
+    }
+
+    /// into_retryable returns the status and the session retrying the begin by the policy.
+    /// The poisoned session is discarded like into_reusable regardless of the policy, and the session
+    /// not reused by BeginRetrySession::Replace is returned to the pool.
+    pub fn into_retryable(self, policy: BeginRetrySession) -> (Status, Option<ManagedSession>) {
+        let reuse = reuses_session(&self.status, policy);
+        match self.into_reusable() {
+            (status, Some(session)) if !reuse => {
+                drop(session);
+                (status, None)
+            }
+            result => result,
+        }
+    }
+}
+
+/// BeginRetrySession decides the session of the retried begin after the begin of the read-write transaction fails.
+/// The session poisoned by the error, such as the session not found, is always replaced.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum BeginRetrySession {
+    /// Reuse retries the begin on the same session, so that the retried transaction keeps
+    /// the wound-wait priority continuity of the session.
+    #[default]
+    Reuse,
+    /// Replace returns the session to the pool and retries the begin on the session taken from the pool.
+    /// The same session may be taken again if it is the only idle session.
+    Replace,
+}
+
+/// reuses_session returns true if the begin failed with the status is retried on the same session.
+fn reuses_session(status: &Status, policy: BeginRetrySession) -> bool {
+    policy == BeginRetrySession::Reuse && !is_session_not_found(status)
+}
+
 impl ReadWriteTransaction {
     pub async fn begin(session: ManagedSession, options: CallOptions) -> Result<ReadWriteTransaction, BeginError> {
-        ReadWriteTransaction::begin_internal(
-            session,
-            transaction_options::Mode::ReadWrite(transaction_options::ReadWrite::default()),
-            options,
-        )
-        .await
+        ReadWriteTransaction::begin_with_read_lock_mode(session, ReadLockMode::Unspecified, options).await
+    }
+
+    /// begin_with_read_lock_mode starts a read-write transaction with the specified read lock mode.
+    pub async fn begin_with_read_lock_mode(
+        session: ManagedSession,
+        read_lock_mode: ReadLockMode,
+        options: CallOptions,
+    ) -> Result<ReadWriteTransaction, BeginError> {
+        ReadWriteTransaction::begin_internal(session, read_write_transaction_options(read_lock_mode), options).await
     }
 
     pub async fn begin_partitioned_dml(
         session: ManagedSession,
         options: CallOptions,
     ) -> Result<ReadWriteTransaction, BeginError> {
-        ReadWriteTransaction::begin_internal(
-            session,
-            transaction_options::Mode::PartitionedDml(transaction_options::PartitionedDml {}),
-            options,
-        )
-        .await
+        let tx_options = TransactionOptions {
+            mode: Some(transaction_options::Mode::PartitionedDml(
+                transaction_options::PartitionedDml {},
+            )),
+        };
+        ReadWriteTransaction::begin_internal(session, tx_options, options).await
     }
 
     async fn begin_internal(
         mut session: ManagedSession,
-        mode: transaction_options::Mode,
+        tx_options: TransactionOptions,
         options: CallOptions,
     ) -> Result<ReadWriteTransaction, BeginError> {
+        let began_at = Instant::now();
         let request = BeginTransactionRequest {
             session: session.session.name.to_string(),
-            options: Some(TransactionOptions { mode: Some(mode) }),
+            options: Some(tx_options),
             request_options: Transaction::create_request_options(options.priority),
         };
         let result = session
@@ -149,45 +585,155 @@ impl ReadWriteTransaction {
             base_tx: Transaction {
                 session: Some(session),
                 sequence_number: AtomicI64::new(0),
+                dialect: DatabaseDialect::GoogleStandardSql,
+                read_write: true,
                 transaction_selector: TransactionSelector {
                     selector: Some(transaction_selector::Selector::Id(tx.id.clone())),
                 },
             },
             tx_id: tx.id,
             wb: vec![],
+            default_commit_options: CommitOptions::default(),
+            statement_count: 0,
+            hook: None,
+            began_at,
         })
     }
 
+    /// attach_hook sets the hook observing the rest of the transaction and notifies it of the begin.
+    pub(crate) fn attach_hook(&mut self, hook: Option<Arc<dyn TransactionHook>>) {
+        self.hook = hook;
+        self.notify(|hook, event| hook.on_begin(event));
+    }
+
+    /// notify calls the hook with the event. Nothing is computed if no hook is attached.
+    fn notify(&self, f: impl FnOnce(&dyn TransactionHook, &TransactionEvent)) {
+        if let Some(hook) = &self.hook {
+            let event = TransactionEvent {
+                transaction_id: hex(&self.tx_id),
+                time: SystemTime::now(),
+                elapsed: self.began_at.elapsed(),
+            };
+            f(hook.as_ref(), &event);
+        }
+    }
+
+    fn notify_if_aborted(&self, status: &Status) {
+        if status.code() == Code::Aborted {
+            self.notify(|hook, event| hook.on_abort(event, status));
+        }
+    }
+
+    /// statements returns the sql of the statements to notify the hook, which is empty without the hook.
+    fn statements<'a>(&self, sql: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        match self.hook {
+            Some(_) => sql.into_iter().map(|x| x.to_string()).collect(),
+            None => vec![],
+        }
+    }
+
+    /// set_default_commit_options sets the options used by end and finish when no options are specified.
+    pub(crate) fn set_default_commit_options(&mut self, options: CommitOptions) {
+        self.default_commit_options = options;
+    }
+
+    /// transaction_id returns the hex encoded id of the transaction such as `01abff`, which is readable in the logs.
+    pub fn transaction_id(&self) -> String {
+        hex(&self.tx_id)
+    }
+
+    /// stats returns the statistics of the operations performed in the transaction so far.
+    pub fn stats(&self) -> TransactionStats {
+        TransactionStats {
+            buffered_mutations: self.wb.len(),
+            estimated_mutations: self.wb.iter().map(mutation::mutation_count).sum(),
+            statements: self.statement_count,
+        }
+    }
+
+    /// audit returns the summary of the mutations buffered in the transaction so far.
+    pub fn audit(&self) -> CommitAudit {
+        CommitAudit::new(hex(&self.tx_id), &self.wb)
+    }
+
+    /// set_seqno_base sets the seqno of the next DML statement. The following statements use the sequence
+    /// numbers incremented from the base. This is for the tools replaying a recorded transaction
+    /// that need the predictable seqno for the DML deduplication. The seqno starts from 0 by default.
+    pub fn set_seqno_base(&mut self, base: i64) {
+        self.sequence_number.store(base, Ordering::Relaxed);
+    }
+
+    fn next_seqno(&self) -> i64 {
+        self.sequence_number.fetch_add(1, Ordering::Relaxed)
+    }
+
     pub fn buffer_write(&mut self, ms: Vec<Mutation>) {
         self.wb.extend_from_slice(&ms)
     }
 
+    /// buffer_write_coalesced buffers the mutations like buffer_write, and then merges the buffered
+    /// writes targeting the same table and primary key to reduce the mutation count of the commit.
+    /// See mutation::coalesce for the rules of the merging.
+    pub fn buffer_write_coalesced(&mut self, ms: Vec<Mutation>, primary_keys: &[(&str, &[&str])]) {
+        self.wb.extend_from_slice(&ms);
+        self.wb = mutation::coalesce(std::mem::take(&mut self.wb), primary_keys);
+    }
+
     pub async fn update(&mut self, stmt: Statement) -> Result<i64, Status> {
         self.update_with_option(stmt, QueryOptions::default()).await
     }
 
     pub async fn update_with_option(&mut self, stmt: Statement, options: QueryOptions) -> Result<i64, Status> {
-        let request = ExecuteSqlRequest {
+        Ok(self.update_row_count_with_option(stmt, options).await?.value())
+    }
+
+    /// update_row_count_with_option is the same as update_with_option, but returns the typed row count
+    /// that distinguishes the exact count of the standard DML from the lower bound of the partitioned DML.
+    pub async fn update_row_count_with_option(
+        &mut self,
+        stmt: Statement,
+        options: QueryOptions,
+    ) -> Result<RowCount, Status> {
+        Ok(self.update_stats_with_option(stmt, options).await?.into())
+    }
+
+    async fn update_stats_with_option(
+        &mut self,
+        stmt: Statement,
+        options: QueryOptions,
+    ) -> Result<Option<ResultSetStats>, Status> {
+        let mut request = ExecuteSqlRequest {
             session: self.get_session_name(),
             transaction: Some(self.transaction_selector.clone()),
             sql: stmt.sql.to_string(),
             params: Some(prost_types::Struct { fields: stmt.params }),
-            param_types: stmt.param_types,
+            param_types: dialect_param_types(stmt.param_types, self.dialect),
             resume_token: vec![],
             query_mode: options.mode.into(),
             partition_token: vec![],
-            seqno: self.sequence_number.fetch_add(1, Ordering::Relaxed),
-            query_options: options.optimizer_options,
+            seqno: self.next_seqno(),
+            query_options: options.optimizer_options.clone(),
             request_options: Transaction::create_request_options(options.call_options.priority),
         };
+        options.mutate_request(&mut request);
+        let statements = self.statements([request.sql.as_str()]);
 
+        let escalate = options.call_options.retry.is_none();
         let session = self.as_mut_session();
         let result = session
             .spanner_client
-            .execute_sql(request, options.call_options.cancel, options.call_options.retry)
+            .read_write()
+            .execute_sql(request, options.call_options.cancel, dml_retry(options.call_options.retry))
             .await;
-        let response = session.invalidate_if_needed(result).await?;
-        Ok(extract_row_count(response.into_inner().stats))
+        let result = session.invalidate_if_needed(result).await;
+        self.notify(|hook, event| hook.on_statement(event, &statements, result.as_ref().map(|_| ())));
+        let response = match result {
+            Ok(response) => response,
+            Err(status) if escalate => return Err(self.escalate_dml_error(status).await),
+            Err(status) => return Err(status),
+        };
+        self.statement_count += 1;
+        Ok(response.into_inner().stats)
     }
 
     pub async fn batch_update(&mut self, stmt: Vec<Statement>) -> Result<Vec<i64>, Status> {
@@ -199,55 +745,132 @@ impl ReadWriteTransaction {
         stmt: Vec<Statement>,
         options: QueryOptions,
     ) -> Result<Vec<i64>, Status> {
+        Ok(self
+            .batch_update_stats_with_option(stmt, options)
+            .await?
+            .into_iter()
+            .map(|x| extract_row_count(Some(x)))
+            .collect())
+    }
+
+    /// batch_update_stats_with_option is the same as batch_update_with_option, but returns the statistics of each statement.
+    ///
+    /// ExecuteBatchDml doesn't support the query mode, so if the mode is QueryMode::Profile the statements are
+    /// executed one by one with ExecuteSql in the profile mode, and the statistics include the query plan and
+    /// the execution statistics of each statement. As with ExecuteBatchDml, the execution stops at the first error.
+    pub async fn batch_update_stats_with_option(
+        &mut self,
+        stmt: Vec<Statement>,
+        options: QueryOptions,
+    ) -> Result<Vec<ResultSetStats>, Status> {
+        let result = self.batch_update_result_with_option(stmt, options).await?;
+        match result.status {
+            Some(status) => Err(status),
+            None => Ok(result.stats),
+        }
+    }
+
+    /// batch_update_result_with_option is the same as batch_update_stats_with_option, but returns the statistics
+    /// of the statements executed before the failed statement together with the error of the failed statement.
+    /// The returned error is only the error of the RPC.
+    pub async fn batch_update_result_with_option(
+        &mut self,
+        stmt: Vec<Statement>,
+        options: QueryOptions,
+    ) -> Result<BatchUpdateResult, Status> {
+        if options.mode == QueryMode::Profile {
+            let mut result = BatchUpdateResult::default();
+            for x in stmt {
+                match self.update_stats_with_option(x, options.clone()).await {
+                    Ok(stats) => result.stats.push(stats.unwrap_or_default()),
+                    Err(status) => {
+                        result.status = Some(status);
+                        break;
+                    }
+                }
+            }
+            return Ok(result);
+        }
         let request = ExecuteBatchDmlRequest {
             session: self.get_session_name(),
             transaction: Some(self.transaction_selector.clone()),
-            seqno: self.sequence_number.fetch_add(1, Ordering::Relaxed),
+            seqno: self.next_seqno(),
             request_options: Transaction::create_request_options(options.call_options.priority),
             statements: stmt
                 .into_iter()
                 .map(|x| execute_batch_dml_request::Statement {
                     sql: x.sql,
                     params: Some(Struct { fields: x.params }),
-                    param_types: x.param_types,
+                    param_types: dialect_param_types(x.param_types, self.dialect),
                 })
                 .collect(),
         };
+        let statements = self.statements(request.statements.iter().map(|x| x.sql.as_str()));
 
+        let escalate = options.call_options.retry.is_none();
         let session = self.as_mut_session();
         let result = session
             .spanner_client
-            .execute_batch_dml(request, options.call_options.cancel, options.call_options.retry)
+            .execute_batch_dml_response(request, options.call_options.cancel, dml_retry(options.call_options.retry))
             .await;
-        let response = session.invalidate_if_needed(result).await?;
-        Ok(response
-            .into_inner()
-            .result_sets
-            .into_iter()
-            .map(|x| extract_row_count(x.stats))
-            .collect())
+        let result = session
+            .invalidate_if_needed(result)
+            .await
+            .map(|r| BatchUpdateResult::from(r.into_inner()));
+        self.notify(|hook, event| {
+            // the partial failure is reported as the failure of the batch.
+            let status = match &result {
+                Ok(r) => r.status.as_ref().map_or(Ok(()), Err),
+                Err(e) => Err(e),
+            };
+            hook.on_statement(event, &statements, status)
+        });
+        let result = match result {
+            Ok(result) => result,
+            Err(status) if escalate => return Err(self.escalate_dml_error(status).await),
+            Err(status) => return Err(status),
+        };
+        self.statement_count += result.stats.len();
+        Ok(result)
+    }
+
+    /// batch_update_expecting is the same as batch_update, but returns FAILED_PRECONDITION error
+    /// if the total number of the affected rows is not expected_total.
+    /// The error is not retried and makes the transaction roll back, so that a partially wrong batch is never committed.
+    pub async fn batch_update_expecting(
+        &mut self,
+        stmt: Vec<Statement>,
+        expected_total: i64,
+    ) -> Result<Vec<i64>, Status> {
+        let row_counts = self.batch_update(stmt).await?;
+        match row_count_mismatch(&row_counts, expected_total) {
+            Some(status) => Err(status),
+            None => Ok(row_counts),
+        }
     }
 
     pub async fn end<S, E>(
         &mut self,
         result: Result<S, E>,
         options: Option<CommitOptions>,
-    ) -> Result<(Option<Timestamp>, S), E>
+    ) -> Result<(CommitResult, S), E>
     where
         E: TryAs<Status> + From<Status>,
     {
-        let opt = options.unwrap_or_default();
+        let opt = options.unwrap_or_else(|| self.default_commit_options.clone());
         match result {
             Ok(success) => {
-                let cr = self.commit(opt).await?;
-                Ok((cr.commit_timestamp.map(|e| e.into()), success))
+                let cr = self.commit(opt).await.map_err(Status::from)?;
+                Ok((cr.into(), success))
             }
             Err(err) => {
-                if let Some(status) = err.try_as() {
-                    // can't rollback. should retry
-                    if status.code() == Code::Aborted {
-                        return Err(err);
-                    }
+                if opt.on_application_error.should_commit(&err) {
+                    self.commit(opt).await.map_err(Status::from)?;
+                    return Err(err);
+                }
+                // can't rollback. should retry
+                if !should_rollback(&err) {
+                    return Err(err);
                 }
                 let _ = self.rollback(opt.call_options.cancel, opt.call_options.retry).await;
                 Err(err)
@@ -259,66 +882,162 @@ impl ReadWriteTransaction {
         &mut self,
         result: Result<T, E>,
         options: Option<CommitOptions>,
-    ) -> Result<(Option<Timestamp>, T), (E, Option<ManagedSession>)>
+    ) -> Result<(CommitResult, T), (E, Option<ManagedSession>)>
     where
         E: TryAs<Status> + From<Status>,
     {
-        let opt = options.unwrap_or_default();
+        let opt = options.unwrap_or_else(|| self.default_commit_options.clone());
 
         return match result {
-            Ok(s) => match self.commit(opt).await {
-                Ok(c) => Ok((c.commit_timestamp.map(|ts| ts.into()), s)),
+            Ok(s) => match self.commit(opt.clone()).await {
+                Ok(c) => {
+                    let mut result = CommitResult::from(c);
+                    if opt.return_audit {
+                        result.audit = Some(self.audit());
+                    }
+                    Ok((result, s))
+                }
                 // Retry the transaction using the same session on ABORT error.
                 // Cloud Spanner will create the new transaction with the previous
                 // one's wound-wait priority.
-                Err(e) => Err((E::from(e), self.take_session())),
+                // DEADLINE_EXCEEDED is handled by the CommitOptions.on_commit_deadline
+                // because the commit may have succeeded.
+                Err(e) => {
+                    self.notify_if_aborted(e.status());
+                    let policy = opt.on_commit_deadline;
+                    if policy.should_rollback(e.status()) {
+                        let _ = self.rollback(opt.call_options.cancel, opt.call_options.retry).await;
+                    }
+                    Err((E::from(policy.convert(Status::from(e))), self.take_session()))
+                }
             },
 
             // Rollback the transaction unless the error occurred during the
             // commit. Executing a rollback after a commit has failed will
             // otherwise cause an error. Note that transient errors, such as
-            // UNAVAILABLE, are already handled in the gRPC layer and do not show
-            // up here. Context errors (deadline exceeded / canceled) during
-            // commits are also not rolled back.
+            // UNAVAILABLE, are already handled in the gRPC layer or escalated to
+            // ABORTED by the DML statements, and do not show up here. Context errors (deadline exceeded / canceled) during
+            // commits are not rolled back here but by the on_commit_deadline.
+            // The application error is committed instead if the on_application_error says so.
+            Err(err) if opt.on_application_error.should_commit(&err) => match self.commit(opt.clone()).await {
+                Ok(_) => Err((err, self.take_session())),
+                Err(e) => {
+                    self.notify_if_aborted(e.status());
+                    Err((E::from(Status::from(e)), self.take_session()))
+                }
+            },
             Err(err) => {
-                let status = match err.try_as() {
-                    Some(status) => status,
-                    None => {
-                        let _ = self.rollback(opt.call_options.cancel, opt.call_options.retry).await;
-                        return Err((err, self.take_session()));
-                    }
-                };
-                match status.code() {
-                    Code::Aborted => Err((err, self.take_session())),
-                    _ => {
-                        let _ = self.rollback(opt.call_options.cancel, opt.call_options.retry).await;
-                        return Err((err, self.take_session()));
-                    }
+                if let Some(status) = err.try_as() {
+                    self.notify_if_aborted(status);
                 }
+                if should_rollback(&err) {
+                    let _ = self.rollback(opt.call_options.cancel, opt.call_options.retry).await;
+                }
+                Err((err, self.take_session()))
             }
         };
     }
 
-    pub(crate) async fn commit(&mut self, options: CommitOptions) -> Result<CommitResponse, Status> {
+    pub(crate) async fn commit(&mut self, options: CommitOptions) -> Result<CommitResponse, TransactionError> {
+        if self.wb.is_empty() && self.statement_count == 0 {
+            match options.on_empty_commit {
+                EmptyCommitPolicy::Commit => {}
+                EmptyCommitPolicy::Skip => {
+                    self.notify(|hook, event| hook.on_commit(event, Ok(None)));
+                    return Ok(CommitResponse::default());
+                }
+                EmptyCommitPolicy::Rollback => {
+                    let call_options = options.call_options;
+                    return self
+                        .rollback(call_options.cancel, call_options.retry)
+                        .await
+                        .map(|_| CommitResponse::default());
+                }
+            }
+        }
         let tx_id = self.tx_id.clone();
         let mutations = self.wb.to_vec();
         let session = self.as_mut_session();
-        commit(session, mutations, TransactionId(tx_id), options).await
+        let result = commit(session, mutations, TransactionId(tx_id), options).await;
+        self.notify(|hook, event| match &result {
+            Ok(response) => {
+                let timestamp = response.commit_timestamp.clone().map(Timestamp::from);
+                hook.on_commit(event, Ok(timestamp.as_ref()))
+            }
+            Err(status) => hook.on_commit(event, Err(status)),
+        });
+        result.map_err(|status| TransactionError::Commit {
+            transaction_id: hex(&self.tx_id),
+            session_name: self.get_session_name(),
+            status,
+        })
+    }
+
+    /// escalate_dml_error rolls back the transaction and returns ABORTED if the DML statement failed with
+    /// the retryable error, so that the whole transaction is retried instead of the statement alone.
+    async fn escalate_dml_error(&mut self, status: Status) -> Status {
+        if !is_retryable_dml_error(&status) {
+            return status;
+        }
+        let _ = self.rollback(None, None).await;
+        dml_aborted(status)
     }
 
     pub(crate) async fn rollback(
         &mut self,
         cancel: Option<CancellationToken>,
         retry: Option<RetrySetting>,
-    ) -> Result<(), Status> {
+    ) -> Result<(), TransactionError> {
         let request = RollbackRequest {
             transaction_id: self.tx_id.clone(),
             session: self.get_session_name(),
         };
         let session = self.as_mut_session();
         let result = session.spanner_client.rollback(request, cancel, retry).await;
-        session.invalidate_if_needed(result).await?.into_inner();
-        Ok(())
+        let result = session.invalidate_if_needed(result).await;
+        self.notify(|hook, event| hook.on_rollback(event, result.as_ref().map(|_| ())));
+        match result {
+            Ok(_) => Ok(()),
+            Err(status) => Err(TransactionError::Rollback {
+                transaction_id: hex(&self.tx_id),
+                session_name: self.get_session_name(),
+                status,
+            }),
+        }
+    }
+}
+
+/// dml_retry returns the retry setting of the DML statement. The statement is not re-issued alone
+/// unless the retry is set explicitly.
+fn dml_retry(retry: Option<RetrySetting>) -> Option<RetrySetting> {
+    retry.or_else(|| Some(RetrySetting::disabled()))
+}
+
+/// is_retryable_dml_error returns true if the DML statement failed with the error retried for the reads.
+fn is_retryable_dml_error(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::Unknown) || is_transient_internal(status)
+}
+
+/// dml_aborted converts the retryable error of the DML statement into ABORTED to retry the transaction.
+fn dml_aborted(status: Status) -> Status {
+    Status::with_details_and_metadata(
+        Code::Aborted,
+        format!(
+            "DML statement failed with {:?}, the transaction is retried: {}",
+            status.code(),
+            status.message()
+        ),
+        status.details().to_vec().into(),
+        status.metadata().clone(),
+    )
+}
+
+/// read_write_transaction_options returns the TransactionOptions to begin the read-write transaction.
+pub(crate) fn read_write_transaction_options(read_lock_mode: ReadLockMode) -> TransactionOptions {
+    TransactionOptions {
+        mode: Some(transaction_options::Mode::ReadWrite(transaction_options::ReadWrite {
+            read_lock_mode: read_lock_mode.into(),
+        })),
     }
 }
 
@@ -328,13 +1047,7 @@ pub(crate) async fn commit(
     tx: commit_request::Transaction,
     commit_options: CommitOptions,
 ) -> Result<CommitResponse, Status> {
-    let request = CommitRequest {
-        session: session.session.name.to_string(),
-        mutations: ms,
-        transaction: Some(tx),
-        request_options: Transaction::create_request_options(commit_options.call_options.priority),
-        return_commit_stats: commit_options.return_commit_stats,
-    };
+    let request = create_commit_request(session.session.name.to_string(), ms, tx, &commit_options);
     let result = session
         .spanner_client
         .commit(request, commit_options.call_options.cancel, commit_options.call_options.retry)
@@ -346,15 +1059,830 @@ pub(crate) async fn commit(
     }
 }
 
+/// create_commit_request creates the CommitRequest with all the fields supported by the CommitOptions.
+/// This is the only place to build the CommitRequest, so that every write path sends the same fields.
+fn create_commit_request(
+    session_name: String,
+    ms: Vec<Mutation>,
+    tx: commit_request::Transaction,
+    commit_options: &CommitOptions,
+) -> CommitRequest {
+    let priority = commit_options.call_options.priority;
+    let request_options = match &commit_options.transaction_tag {
+        Some(transaction_tag) => Some(RequestOptions {
+            priority: priority.unwrap_or(Priority::Unspecified).into(),
+            request_tag: "".to_string(),
+            transaction_tag: transaction_tag.to_string(),
+        }),
+        None => Transaction::create_request_options(priority),
+    };
+    CommitRequest {
+        session: session_name,
+        mutations: ms,
+        transaction: Some(tx),
+        request_options,
+        return_commit_stats: commit_options.return_commit_stats,
+    }
+}
+
+/// BatchUpdateResult is the result of ExecuteBatchDml.
+/// The statements are executed in order and the execution stops at the first failed statement.
+#[derive(Clone, Debug, Default)]
+pub struct BatchUpdateResult {
+    /// stats is the statistics of the statements executed successfully.
+    pub stats: Vec<ResultSetStats>,
+    /// status is the error of the failed statement, which is the statement at the index stats.len().
+    /// None if all the statements are executed successfully.
+    pub status: Option<Status>,
+}
+
+impl BatchUpdateResult {
+    /// row_counts returns the number of the rows modified by each statement executed successfully.
+    pub fn row_counts(&self) -> Vec<i64> {
+        self.stats.iter().map(|x| extract_row_count(Some(x.clone()))).collect()
+    }
+}
+
+impl From<ExecuteBatchDmlResponse> for BatchUpdateResult {
+    fn from(value: ExecuteBatchDmlResponse) -> Self {
+        BatchUpdateResult {
+            stats: value
+                .result_sets
+                .into_iter()
+                .map(|x| x.stats.unwrap_or_default())
+                .collect(),
+            status: value.status.filter(|s| s.code != Code::Ok as i32).map(|s| {
+                if s.details.is_empty() {
+                    return Status::new(Code::from(s.code), s.message);
+                }
+                // The details are kept in the encoded google.rpc.Status like the grpc-status-details-bin.
+                let details = Bytes::from(s.encode_to_vec());
+                Status::with_details(Code::from(s.code), s.message, details)
+            }),
+        }
+    }
+}
+
+/// row_count_mismatch returns FAILED_PRECONDITION error if the sum of the row counts is not expected_total.
+fn row_count_mismatch(row_counts: &[i64], expected_total: i64) -> Option<Status> {
+    let total: i64 = row_counts.iter().sum();
+    if total == expected_total {
+        return None;
+    }
+    Some(Status::new(
+        Code::FailedPrecondition,
+        format!(
+            "unexpected number of rows affected: expected={expected_total}, actual={total}, row_counts={row_counts:?}"
+        ),
+    ))
+}
+
+/// RowCount is the number of rows modified by a DML statement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowCount {
+    /// Exact is the exact number of rows modified by a standard DML.
+    Exact(i64),
+    /// LowerBound is the lower bound of the number of rows modified by a partitioned DML.
+    LowerBound(i64),
+}
+
+impl RowCount {
+    /// value returns the number of rows regardless of whether it is exact or not.
+    pub fn value(&self) -> i64 {
+        match self {
+            RowCount::Exact(v) => *v,
+            RowCount::LowerBound(v) => *v,
+        }
+    }
+}
+
+impl From<Option<ResultSetStats>> for RowCount {
+    fn from(rs: Option<ResultSetStats>) -> Self {
+        match rs.and_then(|o| o.row_count) {
+            Some(result_set_stats::RowCount::RowCountExact(v)) => RowCount::Exact(v),
+            Some(result_set_stats::RowCount::RowCountLowerBound(v)) => RowCount::LowerBound(v),
+            None => RowCount::Exact(0),
+        }
+    }
+}
+
 fn extract_row_count(rs: Option<ResultSetStats>) -> i64 {
-    match rs {
-        Some(o) => match o.row_count {
-            Some(o) => match o {
-                result_set_stats::RowCount::RowCountExact(v) => v,
-                result_set_stats::RowCount::RowCountLowerBound(v) => v,
+    RowCount::from(rs).value()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicI64;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use parking_lot::Mutex;
+    use prost::Message;
+
+    use google_cloud_gax::grpc::{Code, Status};
+    use google_cloud_gax::retry::{Condition, Retry, RetrySetting, TryAs};
+    use google_cloud_googleapis::rpc;
+    use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+    use google_cloud_googleapis::spanner::v1::request_options::Priority;
+    use google_cloud_googleapis::spanner::v1::{
+        commit_request, commit_response, result_set_stats, BeginTransactionRequest, CommitRequest, CommitResponse,
+        ExecuteBatchDmlResponse, ExecuteSqlRequest, ResultSet, ResultSetStats, TransactionSelector,
+    };
+
+    use google_cloud_gax::conn::Environment;
+
+    use crate::apiv1::fake::FakeSpanner;
+    use crate::apiv1::spanner_client::ROUTE_TO_LEADER_HEADER;
+    use crate::client::{Client, ClientConfig, Error, ReadWriteTransactionOption};
+    use crate::key::{all_keys, Key};
+    use crate::mutation::{delete, insert, update};
+    use crate::retry::TransactionRetrySetting;
+    use crate::statement::Statement;
+    use crate::transaction::{hex, CallOptions, QueryOptions, Transaction};
+    use crate::transaction_rw::{
+        create_commit_request, dml_aborted, dml_retry, extract_row_count, is_retryable_dml_error, reuses_session,
+        row_count_mismatch, should_rollback, ApplicationErrorPolicy, BatchUpdateResult, BeginRetrySession, CommitAudit,
+        CommitDeadlinePolicy, CommitOptions, CommitResult, CommitStats, EmptyCommitPolicy, MutationOperation,
+        MutationSummary, ReadWriteTransaction, RowCount, TransactionError, TransactionEvent, TransactionHook,
+        TransactionStats,
+    };
+    use crate::value::Timestamp;
+
+    const DATABASE: &str = "projects/local-project/instances/test-instance/databases/local-database";
+
+    #[test]
+    fn test_batch_update_result() {
+        let result_set = |row_count: i64| ResultSet {
+            metadata: None,
+            rows: vec![],
+            stats: Some(ResultSetStats {
+                query_plan: None,
+                query_stats: None,
+                row_count: Some(result_set_stats::RowCount::RowCountExact(row_count)),
+            }),
+        };
+        // the third statement fails
+        let result = BatchUpdateResult::from(ExecuteBatchDmlResponse {
+            result_sets: vec![result_set(1), result_set(2)],
+            status: Some(rpc::Status {
+                code: Code::InvalidArgument as i32,
+                message: "table not found".to_string(),
+                details: vec![],
+            }),
+        });
+        assert_eq!(result.row_counts(), vec![1, 2]);
+        let status = result.status.as_ref().unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "table not found");
+        assert!(status.details().is_empty());
+
+        // the details of the status are kept
+        let failed = rpc::Status {
+            code: Code::InvalidArgument as i32,
+            message: "table not found".to_string(),
+            details: vec![prost_types::Any {
+                type_url: "type.googleapis.com/google.rpc.BadRequest".to_string(),
+                value: vec![0x01, 0x02],
+            }],
+        };
+        let result = BatchUpdateResult::from(ExecuteBatchDmlResponse {
+            result_sets: vec![result_set(1)],
+            status: Some(failed.clone()),
+        });
+        let status = result.status.unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(rpc::Status::decode(status.details()).unwrap(), failed);
+
+        // the OK status is not an error
+        let result = BatchUpdateResult::from(ExecuteBatchDmlResponse {
+            result_sets: vec![result_set(3)],
+            status: Some(rpc::Status::default()),
+        });
+        assert!(result.status.is_none());
+        assert_eq!(result.row_counts(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_update_partial_failure() {
+        let fake = FakeSpanner::default();
+        let failed = rpc::Status {
+            code: Code::InvalidArgument as i32,
+            message: "table not found".to_string(),
+            details: vec![],
+        };
+        fake.set_batch_dml(ExecuteBatchDmlResponse {
+            result_sets: vec![ResultSet {
+                stats: Some(ResultSetStats {
+                    row_count: Some(result_set_stats::RowCount::RowCountExact(1)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            status: Some(failed),
+        });
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let client = Client::new(DATABASE, config).await.unwrap();
+        let stmts = || {
+            vec![
+                Statement::new("UPDATE User SET Name = 'a' WHERE true"),
+                Statement::new("UPDATE NotFound SET Name = 'a' WHERE true"),
+            ]
+        };
+        let result: Result<(CommitResult, (Status, BatchUpdateResult)), Error> = client
+            .read_write_transaction(|tx, _cancel| {
+                Box::pin(async move {
+                    // batch_update returns the partial failure as the error rather than the row counts of the first statements.
+                    let status = tx.batch_update(stmts()).await.unwrap_err();
+                    let result = tx
+                        .batch_update_result_with_option(stmts(), QueryOptions::default())
+                        .await?;
+                    Ok((status, result))
+                })
+            })
+            .await;
+        let (_, (status, result)) = match result {
+            Ok(result) => result,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "table not found");
+        assert_eq!(result.row_counts(), vec![1]);
+        assert_eq!(result.status.unwrap().code(), Code::InvalidArgument);
+        client.close().await;
+    }
+
+    #[test]
+    fn test_transaction_error() {
+        let err = TransactionError::Commit {
+            transaction_id: hex(&[0x01, 0xab, 0xff]),
+            session_name: "projects/p/instances/i/databases/d/sessions/s".to_string(),
+            status: Status::new(Code::Aborted, "transaction aborted"),
+        };
+        assert_eq!(err.try_as().unwrap().code(), Code::Aborted);
+
+        assert_eq!(err.transaction_id(), "01abff");
+        assert_eq!(err.session_name(), "projects/p/instances/i/databases/d/sessions/s");
+
+        let status = Status::from(err);
+        assert_eq!(status.code(), Code::Aborted);
+        assert_eq!(
+            status.message(),
+            "failed to commit: transaction_id=01abff, session=projects/p/instances/i/databases/d/sessions/s, transaction aborted"
+        );
+        // the error is taken back from the status.
+        let err = TransactionError::from_status(&status).unwrap();
+        assert_eq!(err.transaction_id(), "01abff");
+        assert_eq!(err.status().message(), "transaction aborted");
+        assert!(TransactionError::from_status(&Status::new(Code::Aborted, "aborted")).is_none());
+        match Error::from(status) {
+            Error::Transaction(err) => assert_eq!(err.status().code(), Code::Aborted),
+            other => panic!("must be the transaction error: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_error_with_client() {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let client = Client::new(DATABASE, config).await.unwrap();
+        let ms = vec![insert("User", &["UserId"], &[&"user"])];
+        fake.fail("Commit", Status::new(Code::InvalidArgument, "invalid mutation"));
+        match client.apply(ms).await {
+            // the fake returns "transaction" as the transaction id.
+            Err(Error::Transaction(err)) => {
+                assert!(matches!(*err, TransactionError::Commit { .. }));
+                assert_eq!(err.transaction_id(), "7472616e73616374696f6e");
+                assert!(err.session_name().starts_with(DATABASE));
+                assert_eq!(err.status().code(), Code::InvalidArgument);
+                assert_eq!(err.status().message(), "invalid mutation");
+            }
+            Err(e) => panic!("must be the transaction error: {e:?}"),
+            Ok(_) => panic!("the commit must fail"),
+        }
+        client.close().await;
+    }
+
+    #[test]
+    fn test_reuses_session() {
+        let transient = Status::new(Code::Unavailable, "transient");
+        let not_found = Status::new(
+            Code::NotFound,
+            "Session not found: projects/p/instances/i/databases/d/sessions/s",
+        );
+        assert_eq!(BeginRetrySession::default(), BeginRetrySession::Reuse);
+        assert!(reuses_session(&transient, BeginRetrySession::Reuse));
+        assert!(!reuses_session(&transient, BeginRetrySession::Replace));
+        // the poisoned session is replaced regardless of the policy.
+        assert!(!reuses_session(&not_found, BeginRetrySession::Reuse));
+        assert!(!reuses_session(&not_found, BeginRetrySession::Replace));
+    }
+
+    #[test]
+    fn test_dml_retry() {
+        // the DML statement is not re-issued alone by default.
+        assert!(dml_retry(None).unwrap().is_disabled());
+        let setting = RetrySetting::default();
+        assert_eq!(dml_retry(Some(setting.clone())).unwrap().take, setting.take);
+
+        // the retryable failure of the DML statement retries the whole transaction.
+        let unavailable = Status::new(Code::Unavailable, "connection reset");
+        assert!(is_retryable_dml_error(&unavailable));
+        assert!(is_retryable_dml_error(&Status::new(Code::Unknown, "")));
+        assert!(is_retryable_dml_error(&Status::new(
+            Code::Internal,
+            "stream terminated by RST_STREAM"
+        )));
+        assert!(!is_retryable_dml_error(&Status::new(Code::Internal, "internal error")));
+        assert!(!is_retryable_dml_error(&Status::new(Code::InvalidArgument, "syntax error")));
+
+        let status = dml_aborted(unavailable.clone());
+        assert_eq!(status.code(), Code::Aborted);
+        assert_eq!(
+            status.message(),
+            "DML statement failed with Unavailable, the transaction is retried: connection reset"
+        );
+        let setting = TransactionRetrySetting::default();
+        assert!(setting.condition().should_retry(&Error::GRPC(status)));
+        assert!(!setting.condition().should_retry(&Error::GRPC(unavailable)));
+    }
+
+    #[test]
+    fn test_should_rollback() {
+        let session_not_found = Status::new(
+            Code::NotFound,
+            "Session not found: projects/p/instances/i/databases/d/sessions/s",
+        );
+        assert!(!should_rollback(&session_not_found));
+        assert!(!should_rollback(&Status::new(Code::Aborted, "transaction aborted")));
+
+        let row_not_found = Status::new(Code::NotFound, "Row [user-1] in table User is missing.");
+        assert!(should_rollback(&row_not_found));
+        assert!(should_rollback(&Status::new(Code::InvalidArgument, "invalid")));
+    }
+
+    #[test]
+    fn test_commit_deadline_policy() {
+        let deadline = || Status::new(Code::DeadlineExceeded, "deadline exceeded");
+        let aborted = || Status::new(Code::Aborted, "transaction aborted");
+
+        assert_eq!(CommitOptions::default().on_commit_deadline, CommitDeadlinePolicy::Leave);
+        let leave = CommitDeadlinePolicy::Leave;
+        assert!(!leave.should_rollback(&deadline()));
+        assert_eq!(leave.convert(deadline()).code(), Code::DeadlineExceeded);
+
+        let rollback = CommitDeadlinePolicy::AttemptRollback;
+        assert!(rollback.should_rollback(&deadline()));
+        assert!(!rollback.should_rollback(&aborted()));
+        assert_eq!(rollback.convert(deadline()).code(), Code::DeadlineExceeded);
+
+        let unknown = CommitDeadlinePolicy::TreatAsUnknown;
+        assert!(!unknown.should_rollback(&deadline()));
+        let status = unknown.convert(deadline());
+        assert_eq!(status.code(), Code::Unknown);
+        assert_eq!(status.message(), "commit outcome is unknown: deadline exceeded");
+        assert_eq!(unknown.convert(aborted()).code(), Code::Aborted);
+    }
+
+    #[test]
+    fn test_extract_row_count() {
+        let stats = |row_count| ResultSetStats {
+            query_plan: None,
+            query_stats: None,
+            row_count: Some(row_count),
+        };
+        // partitioned DML returns the lower bound
+        let lower_bound = stats(result_set_stats::RowCount::RowCountLowerBound(100));
+        assert_eq!(100, extract_row_count(Some(lower_bound)));
+        let exact = stats(result_set_stats::RowCount::RowCountExact(3));
+        assert_eq!(3, extract_row_count(Some(exact)));
+        assert_eq!(0, extract_row_count(None));
+    }
+
+    #[test]
+    fn test_row_count() {
+        let stats = |row_count| {
+            Some(ResultSetStats {
+                query_plan: None,
+                query_stats: None,
+                row_count: Some(row_count),
+            })
+        };
+        let lower_bound = RowCount::from(stats(result_set_stats::RowCount::RowCountLowerBound(100)));
+        assert_eq!(lower_bound, RowCount::LowerBound(100));
+        let exact = RowCount::from(stats(result_set_stats::RowCount::RowCountExact(100)));
+        assert_eq!(exact, RowCount::Exact(100));
+        assert_ne!(lower_bound, exact);
+        assert_eq!(lower_bound.value(), exact.value());
+        assert_eq!(RowCount::from(None), RowCount::Exact(0));
+    }
+
+    #[test]
+    fn test_commit_result() {
+        let response = CommitResponse {
+            commit_timestamp: Some(prost_types::Timestamp {
+                seconds: 100,
+                nanos: 200,
+            }),
+            commit_stats: None,
+        };
+        let result = CommitResult::from(response.clone());
+        let timestamp = result.timestamp.unwrap();
+        assert_eq!((timestamp.seconds, timestamp.nanos), (100, 200));
+        assert!(result.mutation_count.is_none());
+
+        let response = CommitResponse {
+            commit_stats: Some(commit_response::CommitStats { mutation_count: 3 }),
+            ..response
+        };
+        let result = CommitResult::from(response);
+        assert!(result.timestamp.is_some());
+        assert_eq!(result.mutation_count, Some(3));
+        assert_eq!(result.commit_stats(), Some(CommitStats { mutation_count: 3 }));
+        assert!(CommitResult::default().commit_stats().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_commit_stats_json() {
+        let stats = CommitStats { mutation_count: 3 };
+        assert_eq!(serde_json::to_string(&stats).unwrap(), r#"{"mutation_count":3}"#);
+    }
+
+    #[test]
+    fn test_row_count_mismatch() {
+        assert!(row_count_mismatch(&[1, 2, 0], 3).is_none());
+        assert!(row_count_mismatch(&[], 0).is_none());
+
+        let err = row_count_mismatch(&[1, 2], 4).unwrap();
+        assert_eq!(err.code(), Code::FailedPrecondition);
+        assert!(err.message().contains("expected=4, actual=3"), "{err:?}");
+        assert!(should_rollback(&err));
+    }
+
+    fn transaction(tx_id: Vec<u8>) -> ReadWriteTransaction {
+        ReadWriteTransaction {
+            base_tx: Transaction {
+                session: None,
+                sequence_number: AtomicI64::new(0),
+                dialect: DatabaseDialect::GoogleStandardSql,
+                read_write: true,
+                transaction_selector: TransactionSelector::default(),
             },
-            None => 0,
-        },
-        None => 0,
+            tx_id,
+            wb: vec![],
+            default_commit_options: CommitOptions::default(),
+            statement_count: 0,
+            hook: None,
+            began_at: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skip_empty_commit() {
+        let options = CommitOptions {
+            on_empty_commit: EmptyCommitPolicy::Skip,
+            ..Default::default()
+        };
+        // The transaction has no session, so it would panic if the CommitRequest were sent.
+        let mut tx = transaction(vec![1]);
+        let response = tx.commit(options.clone()).await.unwrap();
+        assert!(response.commit_timestamp.is_none());
+
+        match tx.finish(Ok::<_, Status>(1), Some(options)).await {
+            Ok((result, value)) => {
+                assert_eq!(value, 1);
+                assert!(result.timestamp.is_none());
+            }
+            Err((err, _)) => panic!("{err:?}"),
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingHook {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TransactionHook for RecordingHook {
+        fn on_begin(&self, event: &TransactionEvent) {
+            self.events.lock().push(format!("begin {}", event.transaction_id));
+        }
+        fn on_statement(&self, event: &TransactionEvent, statements: &[String], result: Result<(), &Status>) {
+            self.events.lock().push(format!(
+                "statement {} {} {:?}",
+                event.transaction_id,
+                statements.len(),
+                result.is_ok()
+            ));
+        }
+        fn on_commit(&self, event: &TransactionEvent, result: Result<Option<&Timestamp>, &Status>) {
+            self.events
+                .lock()
+                .push(format!("commit {} {:?}", event.transaction_id, result.map(|t| t.is_some())));
+        }
+        fn on_rollback(&self, event: &TransactionEvent, result: Result<(), &Status>) {
+            self.events
+                .lock()
+                .push(format!("rollback {} {:?}", event.transaction_id, result.is_ok()));
+        }
+        fn on_abort(&self, event: &TransactionEvent, status: &Status) {
+            self.events
+                .lock()
+                .push(format!("abort {} {}", event.transaction_id, status.message()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hook() {
+        let hook = Arc::new(RecordingHook::default());
+        let mut tx = transaction(vec![0x0a]);
+        tx.attach_hook(Some(hook.clone()));
+        let options = CommitOptions {
+            on_empty_commit: EmptyCommitPolicy::Skip,
+            ..Default::default()
+        };
+        tx.commit(options).await.unwrap();
+        tx.notify_if_aborted(&Status::new(Code::Internal, "internal"));
+        tx.notify_if_aborted(&Status::new(Code::Aborted, "aborted"));
+        assert_eq!(*hook.events.lock(), vec!["begin 0a", "commit 0a Ok(false)", "abort 0a aborted"]);
+
+        // no event without the hook
+        let mut tx = transaction(vec![0x0b]);
+        tx.attach_hook(None);
+        assert!(tx.statements(["SELECT 1"]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hook_with_client() {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            ..Default::default()
+        };
+        let client = Client::new(DATABASE, config).await.unwrap();
+        let hook = RecordingHook::default();
+        let option = ReadWriteTransactionOption::default().with_hook(hook.clone());
+        let result: Result<(CommitResult, i64), Error> = client
+            .read_write_transaction_with_option(
+                |tx, _cancel| {
+                    Box::pin(async move {
+                        Ok(tx
+                            .update(Statement::new("UPDATE User SET Name = 'a' WHERE true"))
+                            .await?)
+                    })
+                },
+                option,
+            )
+            .await;
+        if let Err(e) = result {
+            panic!("{e:?}");
+        }
+        // the fake returns "transaction" as the transaction id.
+        assert_eq!(
+            *hook.events.lock(),
+            vec![
+                "begin 7472616e73616374696f6e",
+                "statement 7472616e73616374696f6e 1 true",
+                "commit 7472616e73616374696f6e Ok(true)"
+            ]
+        );
+        client.close().await;
+    }
+
+    /// routed returns whether each request of the method is sent with the route-to-leader header.
+    fn routed<M: Clone + 'static>(fake: &FakeSpanner, method: &str) -> Vec<bool> {
+        fake.requests::<M>(method)
+            .iter()
+            .map(|(metadata, _)| match metadata.get(ROUTE_TO_LEADER_HEADER) {
+                Some(value) => value == "true",
+                None => false,
+            })
+            .collect()
+    }
+
+    /// run_transactions runs the read-write transaction with a query and an update, and then the read-only query.
+    async fn run_transactions(route_to_leader: bool) -> FakeSpanner {
+        let fake = FakeSpanner::default();
+        let config = ClientConfig {
+            environment: Environment::Emulator(fake.start().await),
+            route_to_leader,
+            ..Default::default()
+        };
+        let client = Client::new(DATABASE, config).await.unwrap();
+        let result: Result<(CommitResult, ()), Error> = client
+            .read_write_transaction(|tx, _cancel| {
+                Box::pin(async move {
+                    tx.query(Statement::new("SELECT 1")).await?;
+                    tx.update(Statement::new("UPDATE User SET Name = 'a' WHERE true"))
+                        .await?;
+                    Ok(())
+                })
+            })
+            .await;
+        if let Err(e) = result {
+            panic!("{e:?}");
+        }
+        let read_write = fake.requests::<ExecuteSqlRequest>("ExecuteStreamingSql").len();
+        let mut tx = client.single().await.unwrap();
+        tx.query(Statement::new("SELECT 1")).await.unwrap();
+        assert_eq!(fake.requests::<ExecuteSqlRequest>("ExecuteStreamingSql").len(), read_write + 1);
+        drop(tx);
+        client.close().await;
+        fake
+    }
+
+    #[tokio::test]
+    async fn test_route_to_leader_header() {
+        let fake = run_transactions(true).await;
+        assert_eq!(routed::<BeginTransactionRequest>(&fake, "BeginTransaction"), vec![true]);
+        assert_eq!(routed::<ExecuteSqlRequest>(&fake, "ExecuteSql"), vec![true]);
+        assert_eq!(routed::<CommitRequest>(&fake, "Commit"), vec![true]);
+        // the query of the read-write transaction is routed, the read-only query is not.
+        let queries = routed::<ExecuteSqlRequest>(&fake, "ExecuteStreamingSql");
+        assert_eq!(queries[queries.len() - 2..], [true, false]);
+
+        let fake = run_transactions(false).await;
+        assert_eq!(routed::<BeginTransactionRequest>(&fake, "BeginTransaction"), vec![false]);
+        assert_eq!(routed::<ExecuteSqlRequest>(&fake, "ExecuteSql"), vec![false]);
+        assert_eq!(routed::<CommitRequest>(&fake, "Commit"), vec![false]);
+        assert!(routed::<ExecuteSqlRequest>(&fake, "ExecuteStreamingSql")
+            .iter()
+            .all(|routed| !routed));
+    }
+
+    #[derive(Debug)]
+    enum AppError {
+        Invalid,
+        Db(Status),
+    }
+
+    impl TryAs<Status> for AppError {
+        fn try_as(&self) -> Option<&Status> {
+            match self {
+                AppError::Db(status) => Some(status),
+                AppError::Invalid => None,
+            }
+        }
+    }
+
+    impl From<Status> for AppError {
+        fn from(status: Status) -> Self {
+            AppError::Db(status)
+        }
+    }
+
+    #[test]
+    fn test_application_error_policy() {
+        let rollback = ApplicationErrorPolicy::default();
+        assert_eq!(rollback, ApplicationErrorPolicy::Rollback);
+        assert!(!rollback.should_commit(&AppError::Invalid));
+        assert!(should_rollback(&AppError::Invalid));
+
+        let commit = ApplicationErrorPolicy::CommitThenReturn;
+        assert!(commit.should_commit(&AppError::Invalid));
+        assert!(!commit.should_commit(&AppError::Db(Status::new(Code::Internal, "internal"))));
+    }
+
+    #[tokio::test]
+    async fn test_commit_then_return_application_error() {
+        let hook = Arc::new(RecordingHook::default());
+        let mut tx = transaction(vec![0x0c]);
+        tx.attach_hook(Some(hook.clone()));
+        let options = CommitOptions {
+            on_application_error: ApplicationErrorPolicy::CommitThenReturn,
+            // The transaction has no session, so the commit RPC is skipped.
+            on_empty_commit: EmptyCommitPolicy::Skip,
+            ..Default::default()
+        };
+        match tx.finish(Err::<(), _>(AppError::Invalid), Some(options.clone())).await {
+            Err((AppError::Invalid, _)) => {}
+            other => panic!("must be the application error: {:?}", other.map(|_| ()).map_err(|e| e.0)),
+        }
+        assert!(matches!(
+            tx.end(Err::<(), _>(AppError::Invalid), Some(options)).await,
+            Err(AppError::Invalid)
+        ));
+        assert_eq!(
+            *hook.events.lock(),
+            vec!["begin 0c", "commit 0c Ok(false)", "commit 0c Ok(false)"]
+        );
+    }
+
+    #[test]
+    fn test_seqno_base() {
+        let mut tx = transaction(vec![]);
+        assert_eq!(tx.next_seqno(), 0);
+        tx.set_seqno_base(100);
+        assert_eq!(tx.next_seqno(), 100);
+        assert_eq!(tx.next_seqno(), 101);
+    }
+
+    #[test]
+    fn test_transaction_id() {
+        let tx = transaction(vec![0x00, 0x1f, 0xa0, 0xff]);
+        assert_eq!(tx.transaction_id(), "001fa0ff");
+        assert_eq!(tx.transaction_id(), tx.transaction_id());
+        assert_eq!(transaction(vec![]).transaction_id(), "");
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut tx = transaction(vec![]);
+        assert_eq!(tx.stats(), TransactionStats::default());
+
+        tx.buffer_write(vec![
+            insert("User", &["UserId", "Name"], &[&"user1", &"name1"]),
+            delete("User", Key::new(&"user2")),
+        ]);
+        tx.buffer_write(vec![insert("User", &["UserId"], &[&"user3"])]);
+        assert_eq!(
+            tx.stats(),
+            TransactionStats {
+                buffered_mutations: 3,
+                estimated_mutations: 4,
+                statements: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_create_commit_request() {
+        let tx = || commit_request::Transaction::TransactionId(vec![1]);
+        let ms = vec![insert("User", &["UserId"], &[&"user1"])];
+
+        // backward compatible with the default options
+        let request = create_commit_request("session".to_string(), ms.clone(), tx(), &CommitOptions::default());
+        assert_eq!(request.session, "session");
+        assert_eq!(request.mutations, ms);
+        assert_eq!(request.transaction, Some(tx()));
+        assert!(!request.return_commit_stats);
+        assert!(request.request_options.is_none());
+
+        let options = CommitOptions {
+            return_commit_stats: true,
+            call_options: CallOptions {
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+            transaction_tag: Some("tag1".to_string()),
+            ..Default::default()
+        };
+        let request = create_commit_request("session".to_string(), ms, tx(), &options);
+        assert!(request.return_commit_stats);
+        let request_options = request.request_options.unwrap();
+        assert_eq!(request_options.priority, Priority::High as i32);
+        assert_eq!(request_options.transaction_tag, "tag1");
+        assert_eq!(request_options.request_tag, "");
+
+        // the tag without the priority
+        let options = CommitOptions {
+            transaction_tag: Some("tag2".to_string()),
+            ..Default::default()
+        };
+        let request = create_commit_request("session".to_string(), vec![], tx(), &options);
+        let request_options = request.request_options.unwrap();
+        assert_eq!(request_options.priority, Priority::Unspecified as i32);
+        assert_eq!(request_options.transaction_tag, "tag2");
+    }
+
+    #[test]
+    fn test_audit() {
+        let mut tx = transaction(vec![0x01, 0xab]);
+        assert_eq!(
+            tx.audit(),
+            CommitAudit {
+                transaction_id: "01ab".to_string(),
+                mutations: vec![],
+            }
+        );
+
+        tx.buffer_write(vec![
+            insert("User", &["UserId", "Name"], &[&"user1", &"name1"]),
+            update("User", &["UserId", "Name"], &[&"user2", &"name2"]),
+            insert("Item", &["ItemId"], &[&1]),
+            delete("User", vec![Key::new(&"user3"), Key::new(&"user4")]),
+            insert("User", &["UserId"], &[&"user5"]),
+            delete("Item", all_keys()),
+        ]);
+        let summary = |table: &str, operation, key_count| MutationSummary {
+            table: table.to_string(),
+            operation,
+            key_count,
+        };
+        assert_eq!(
+            tx.audit().mutations,
+            vec![
+                summary("User", MutationOperation::Insert, 2),
+                summary("User", MutationOperation::Update, 1),
+                summary("Item", MutationOperation::Insert, 1),
+                summary("User", MutationOperation::Delete, 2),
+                summary("Item", MutationOperation::Delete, 1),
+            ]
+        );
     }
 }
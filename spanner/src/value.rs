@@ -27,6 +27,35 @@ impl SpannerNumeric {
     }
 }
 
+/// ProtoMessage is the value of the PROTO column.
+/// The message is sent as the serialized BYTES because the PROTO type code is not supported by this client,
+/// so use `CAST(@param AS package.Message)` to compare it with the PROTO column in the query.
+#[cfg(feature = "proto")]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProtoMessage<T: prost::Message + Default>(pub T);
+
+#[cfg(feature = "proto")]
+impl<T: prost::Message + Default> Deref for ProtoMessage<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// ProtoEnum is the value of the ENUM column.
+/// The value is sent as INT64, so the generated enum can be converted with `Into<i32>` and `from_i32`.
+#[cfg(feature = "proto")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProtoEnum(pub i32);
+
+#[cfg(feature = "proto")]
+impl From<i32> for ProtoEnum {
+    fn from(value: i32) -> Self {
+        ProtoEnum(value)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct Timestamp {
     /// Represents seconds of UTC time since Unix epoch
@@ -91,6 +120,268 @@ impl From<CommitTimestamp> for time::OffsetDateTime {
     }
 }
 
+/// TimestampPrecision is the precision of the TIMESTAMP value sent to Cloud Spanner.
+/// Cloud Spanner keeps the nanoseconds, so reduce the precision to compare the values
+/// with the stores keeping only the microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    /// Nanos sends the value as is.
+    #[default]
+    Nanos,
+    /// TruncateMicros drops the nanoseconds below the microsecond.
+    TruncateMicros,
+    /// RoundMicros rounds the value to the nearest microsecond, and 500 nanoseconds are rounded up.
+    RoundMicros,
+}
+
+impl TimestampPrecision {
+    /// apply returns the value reduced to the precision.
+    pub fn apply(&self, value: time::OffsetDateTime) -> time::OffsetDateTime {
+        let sub_micros = (value.nanosecond() % 1_000) as i64;
+        match self {
+            TimestampPrecision::Nanos => value,
+            TimestampPrecision::TruncateMicros => value - time::Duration::nanoseconds(sub_micros),
+            TimestampPrecision::RoundMicros if sub_micros >= 500 => {
+                value + time::Duration::nanoseconds(1_000 - sub_micros)
+            }
+            TimestampPrecision::RoundMicros => value - time::Duration::nanoseconds(sub_micros),
+        }
+    }
+}
+
+/// PrecisionTimestamp is the TIMESTAMP value bound with the precision.
+/// ```
+/// use google_cloud_spanner::statement::Statement;
+/// use google_cloud_spanner::value::{PrecisionTimestamp, TimestampPrecision};
+/// use time::OffsetDateTime;
+///
+/// let mut stmt = Statement::new("SELECT * FROM Event WHERE CreatedAt = @CreatedAt");
+/// stmt.add_param("CreatedAt", &PrecisionTimestamp::new(OffsetDateTime::now_utc(), TimestampPrecision::TruncateMicros));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrecisionTimestamp {
+    pub timestamp: time::OffsetDateTime,
+    pub precision: TimestampPrecision,
+}
+
+impl PrecisionTimestamp {
+    pub fn new(timestamp: time::OffsetDateTime, precision: TimestampPrecision) -> Self {
+        PrecisionTimestamp { timestamp, precision }
+    }
+}
+
+/// Interval is the value of the INTERVAL column.
+/// The months, the days and the nanoseconds are kept apart, because a month is not a fixed number of days
+/// and a day is not always 24 hours, so the interval does not collapse into a Duration.
+/// It is sent and received as the ISO 8601 duration such as `P1Y2M3DT4H5M6.5S`.
+/// ```
+/// use google_cloud_spanner::statement::Statement;
+/// use google_cloud_spanner::value::Interval;
+///
+/// let mut stmt = Statement::new("SELECT TIMESTAMP_ADD(CreatedAt, @Retention) FROM Event");
+/// stmt.add_param("Retention", &Interval::new(1, 15, 0));
+/// let interval: Interval = "P1Y2M3DT4H5M6.5S".parse().unwrap();
+/// assert_eq!(interval, Interval::new(14, 3, 14_706_500_000_000));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub nanos: i128,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid interval: {0}")]
+pub struct IntervalParseError(String);
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MINUTE: i128 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: i128 = 60 * NANOS_PER_MINUTE;
+
+impl Interval {
+    pub fn new(months: i32, days: i32, nanos: i128) -> Self {
+        Interval { months, days, nanos }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Interval::default() {
+            return write!(f, "P0Y");
+        }
+        write!(f, "P")?;
+        let (years, months) = (self.months / 12, self.months % 12);
+        for (value, unit) in [(years, "Y"), (months, "M"), (self.days, "D")] {
+            if value != 0 {
+                write!(f, "{value}{unit}")?;
+            }
+        }
+        if self.nanos == 0 {
+            return Ok(());
+        }
+        write!(f, "T")?;
+        let hours = self.nanos / NANOS_PER_HOUR;
+        let minutes = self.nanos % NANOS_PER_HOUR / NANOS_PER_MINUTE;
+        let nanos = self.nanos % NANOS_PER_MINUTE;
+        for (value, unit) in [(hours, "H"), (minutes, "M")] {
+            if value != 0 {
+                write!(f, "{value}{unit}")?;
+            }
+        }
+        if nanos != 0 {
+            let sign = if nanos < 0 { "-" } else { "" };
+            let (seconds, fraction) = (nanos.abs() / NANOS_PER_SECOND, nanos.abs() % NANOS_PER_SECOND);
+            write!(f, "{sign}{seconds}")?;
+            if fraction != 0 {
+                write!(f, ".{}", format!("{fraction:09}").trim_end_matches('0'))?;
+            }
+            write!(f, "S")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = IntervalParseError;
+
+    /// from_str parses the ISO 8601 duration. Each component may be negative, and only the seconds may be fractional.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let err = || IntervalParseError(value.to_string());
+        let mut rest = value.strip_prefix('P').ok_or_else(err)?;
+        let mut interval = Interval::default();
+        let mut time = false;
+        let mut empty = true;
+        while !rest.is_empty() {
+            if let Some(r) = rest.strip_prefix('T') {
+                if time {
+                    return Err(err());
+                }
+                time = true;
+                rest = r;
+                continue;
+            }
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '-' || c == '.' || c == ','))
+                .ok_or_else(err)?;
+            let number = &rest[..end];
+            // the unit may be any character, so advance by its length in bytes.
+            let unit = rest[end..].chars().next().ok_or_else(err)?;
+            rest = &rest[end + unit.len_utf8()..];
+            empty = false;
+            if time && unit == 'S' {
+                interval.nanos += parse_seconds(number).ok_or_else(err)?;
+                continue;
+            }
+            let n: i64 = number.parse().map_err(|_| err())?;
+            let int = |n: i64, per_unit: i64| {
+                n.checked_mul(per_unit)
+                    .and_then(|n| i32::try_from(n).ok())
+                    .ok_or_else(err)
+            };
+            match (time, unit) {
+                (false, 'Y') => interval.months = interval.months.checked_add(int(n, 12)?).ok_or_else(err)?,
+                (false, 'M') => interval.months = interval.months.checked_add(int(n, 1)?).ok_or_else(err)?,
+                (false, 'W') => interval.days = interval.days.checked_add(int(n, 7)?).ok_or_else(err)?,
+                (false, 'D') => interval.days = interval.days.checked_add(int(n, 1)?).ok_or_else(err)?,
+                (true, 'H') => interval.nanos += n as i128 * NANOS_PER_HOUR,
+                (true, 'M') => interval.nanos += n as i128 * NANOS_PER_MINUTE,
+                _ => return Err(err()),
+            }
+        }
+        if empty {
+            return Err(err());
+        }
+        Ok(interval)
+    }
+}
+
+/// parse_seconds parses the seconds with up to 9 fractional digits into the nanoseconds.
+fn parse_seconds(value: &str) -> Option<i128> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, value),
+    };
+    let (seconds, fraction) = value.split_once(['.', ',']).unwrap_or((value, ""));
+    if seconds.is_empty() || !seconds.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = seconds.parse::<i128>().ok()? * NANOS_PER_SECOND + format!("{fraction:0<9}").parse::<i128>().ok()?;
+    Some(if negative { -nanos } else { nanos })
+}
+
+/// DynamicValue is a value decoded by the Spanner type of the column without the static Rust type,
+/// such as the field of StructValue. The value of the type this crate does not decode is kept as is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynamicValue {
+    Null,
+    Bool(bool),
+    Int64(i64),
+    Float64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Numeric(SpannerNumeric),
+    Timestamp(time::OffsetDateTime),
+    Date(time::Date),
+    Json(String),
+    Interval(Interval),
+    Array(Vec<DynamicValue>),
+    Struct(StructValue),
+    Other(prost_types::Value),
+}
+
+/// StructValue is a STRUCT decoded into the fields in the order of the STRUCT type, for the tools and the
+/// generic serializers that can't define the type implementing TryFromStruct.
+/// ```
+/// use google_cloud_spanner::row::Row;
+/// use google_cloud_spanner::value::DynamicValue;
+///
+/// fn run(row: Row) -> Result<(), google_cloud_spanner::row::Error> {
+///     // SELECT STRUCT<Name STRING, Age INT64>('user', 20) AS User
+///     let user = row.get_struct_by_name("User")?;
+///     assert_eq!(user.get("Age"), Some(&DynamicValue::Int64(20)));
+///     for (name, value) in user.fields() {
+///         println!("{name}={value:?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructValue {
+    fields: Vec<(String, DynamicValue)>,
+}
+
+impl StructValue {
+    pub fn new(fields: Vec<(String, DynamicValue)>) -> Self {
+        StructValue { fields }
+    }
+
+    /// fields returns the name and the value of each field in the order of the STRUCT type.
+    pub fn fields(&self) -> &[(String, DynamicValue)] {
+        &self.fields
+    }
+
+    /// get returns the value of the first field of the name. The field of a STRUCT may be unnamed or duplicated.
+    pub fn get(&self, name: &str) -> Option<&DynamicValue> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// get_index returns the value of the field at the position.
+    pub fn get_index(&self, index: usize) -> Option<&DynamicValue> {
+        self.fields.get(index).map(|(_, v)| v)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
 #[derive(Clone)]
 pub struct TimestampBound {
     inner: InternalTimestampBound,
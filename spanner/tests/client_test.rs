@@ -1,16 +1,25 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use serial_test::serial;
 use time::OffsetDateTime;
 
 use common::*;
 use google_cloud_gax::grpc::{Code, Status};
 use google_cloud_gax::retry::TryAs;
-use google_cloud_spanner::client::{Client, ClientConfig, Error};
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+use google_cloud_googleapis::spanner::v1::GetSessionRequest;
+use google_cloud_spanner::client::{
+    Client, ClientConfig, EmulatorUnsupportedPolicy, Error, PartitionedUpdateOption, ReadWriteTransactionOption,
+};
+use google_cloud_spanner::committer::{BatchingCommitter, BatchingCommitterConfig};
 use google_cloud_spanner::key::Key;
 use google_cloud_spanner::retry::TransactionRetry;
 use google_cloud_spanner::row::Row;
 use google_cloud_spanner::session::SessionError;
 use google_cloud_spanner::statement::Statement;
-use google_cloud_spanner::value::Timestamp;
+use google_cloud_spanner::transaction::QueryOptions;
+use google_cloud_spanner::transaction_rw::{CommitOptions, CommitResult, RowCount};
 
 mod common;
 
@@ -67,7 +76,7 @@ async fn test_read_write_transaction() {
 
     // test
     let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
-    let result: Result<(Option<Timestamp>, i64), DomainError> = client
+    let result: Result<(CommitResult, i64), DomainError> = client
         .read_write_transaction(
             |tx, _cancel| {
                 let user_id= user_id.to_string();
@@ -86,7 +95,7 @@ async fn test_read_write_transaction() {
             },
         )
         .await;
-    let value = result.unwrap().0.unwrap();
+    let value = result.unwrap().0.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(value.seconds)
         .unwrap()
         .replace_nanosecond(value.nanos as u32)
@@ -123,7 +132,7 @@ async fn test_apply() {
     let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
     let now = OffsetDateTime::now_utc();
     let ms = users.iter().map(|id| create_user_mutation(id, &now)).collect();
-    let value = client.apply(ms).await.unwrap().unwrap();
+    let value = client.apply(ms).await.unwrap().timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(value.seconds)
         .unwrap()
         .replace_nanosecond(value.nanos as u32)
@@ -137,6 +146,115 @@ async fn test_apply() {
     }
 }
 
+#[tokio::test]
+#[serial]
+async fn test_apply_with_commit_stats() {
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    let now = OffsetDateTime::now_utc();
+    let options = CommitOptions {
+        return_commit_stats: true,
+        ..Default::default()
+    };
+
+    let ms = vec![create_user_mutation("user_client_stats_1", &now)];
+    let result = client
+        .apply_with_option(
+            ms,
+            ReadWriteTransactionOption {
+                commit_options: options.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(result.timestamp.is_some());
+    assert!(result.mutation_count.is_some());
+
+    let ms = vec![create_user_mutation("user_client_stats_2", &now)];
+    let result = client.apply_at_least_once_with_option(ms, options).await.unwrap();
+    assert!(result.timestamp.is_some());
+    assert!(result.mutation_count.is_some());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_apply_ignoring_commit_stats_on_emulator() {
+    let config = ClientConfig {
+        emulator_unsupported_options: EmulatorUnsupportedPolicy::WarnAndIgnore,
+        ..Default::default()
+    };
+    let client = Client::new(DATABASE, config).await.unwrap();
+    assert!(client.is_emulator());
+    assert!(!client.supports_commit_stats());
+    let options = CommitOptions {
+        return_commit_stats: true,
+        ..Default::default()
+    };
+
+    // the commit succeeds without the commit stats.
+    let ms = vec![create_user_mutation("user_client_stats_3", &OffsetDateTime::now_utc())];
+    let result = client.apply_at_least_once_with_option(ms, options).await.unwrap();
+    assert!(result.timestamp.is_some());
+    assert!(result.mutation_count.is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_database_dialect() {
+    let client = create_data_client().await;
+    assert_eq!(client.database_dialect().await.unwrap(), DatabaseDialect::GoogleStandardSql);
+    // cached
+    assert_eq!(client.database_dialect().await.unwrap(), DatabaseDialect::GoogleStandardSql);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_checkout_session() {
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    let session_count = client.session_count();
+    {
+        let mut session = client.checkout_session().await.unwrap();
+        let name = session.session.name.to_string();
+        let request = GetSessionRequest { name: name.to_string() };
+        let response = session.spanner_client.raw().get_session(request).await.unwrap();
+        assert_eq!(response.into_inner().name, name);
+    }
+    // the session is returned to the pool.
+    assert_eq!(client.session_count(), session_count);
+    assert!(client.single().await.is_ok());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batching_committer() {
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    let now = OffsetDateTime::now_utc();
+    let config = BatchingCommitterConfig {
+        window: Duration::from_millis(500),
+        max_mutations: 100,
+    };
+    let committer = Arc::new(BatchingCommitter::new(client.clone(), config));
+    let mut producers = Vec::new();
+    for i in 0..3 {
+        let committer = committer.clone();
+        let ms = vec![create_user_mutation(&format!("user_batching_{i}"), &now)];
+        producers.push(tokio::spawn(async move { committer.apply(ms).await }));
+    }
+    let mut timestamps = Vec::new();
+    for producer in producers {
+        timestamps.push(producer.await.unwrap().unwrap().timestamp.unwrap());
+    }
+    // the mutations within the window share one commit.
+    assert!(timestamps.iter().all(|ts| *ts == timestamps[0]));
+
+    let mut tx = client.single().await.unwrap();
+    let keys = (0..3)
+        .map(|i| Key::new(&format!("user_batching_{i}")))
+        .collect::<Vec<Key>>();
+    let rows = tx.batch_read_by_key("User", keys, &["UserId"]).await.unwrap();
+    assert!(rows.iter().all(|(_, row)| row.is_some()));
+}
+
 #[tokio::test]
 #[serial]
 async fn test_apply_at_least_once() {
@@ -144,7 +262,7 @@ async fn test_apply_at_least_once() {
     let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
     let now = OffsetDateTime::now_utc();
     let ms = users.iter().map(|id| create_user_mutation(id, &now)).collect();
-    let value = client.apply_at_least_once(ms).await.unwrap().unwrap();
+    let value = client.apply_at_least_once(ms).await.unwrap().timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(value.seconds)
         .unwrap()
         .replace_nanosecond(value.nanos as u32)
@@ -158,6 +276,40 @@ async fn test_apply_at_least_once() {
     }
 }
 
+#[tokio::test]
+#[serial]
+async fn test_batch_update_expecting_rollback() {
+    let now = OffsetDateTime::now_utc();
+    let user_id = format!("user_expecting_{}", now.unix_timestamp());
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    client.apply(vec![create_user_mutation(&user_id, &now)]).await.unwrap();
+
+    let result: Result<(CommitResult, Vec<i64>), Error> = client
+        .read_write_transaction(|tx, _cancel| {
+            let user_id = user_id.to_string();
+            Box::pin(async move {
+                let mut stmt = Statement::new("UPDATE User SET NullableString = 'expecting' WHERE UserId = @UserId");
+                stmt.add_param("UserId", &user_id);
+                Ok(tx.batch_update_expecting(vec![stmt], 2).await?)
+            })
+        })
+        .await;
+    match result {
+        Err(Error::GRPC(status)) => assert_eq!(status.code(), Code::FailedPrecondition),
+        _ => panic!("must be failed precondition"),
+    }
+
+    // the update is rolled back.
+    let mut single = client.single().await.unwrap();
+    let rows = single
+        .read("User", &["NullableString"], Key::new(&user_id))
+        .await
+        .unwrap();
+    let row = all_rows(rows).await.unwrap().pop().unwrap();
+    let value = row.column_by_name::<Option<String>>("NullableString").unwrap();
+    assert_ne!(value.as_deref(), Some("expecting"));
+}
+
 #[tokio::test]
 #[serial]
 async fn test_partitioned_update() {
@@ -185,6 +337,31 @@ async fn test_partitioned_update() {
     assert_eq!(value, "aaa");
 }
 
+#[tokio::test]
+#[serial]
+async fn test_partitioned_update_row_count() {
+    let now = OffsetDateTime::now_utc();
+    let user_id = format!("user_row_count_{}", now.unix_timestamp());
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    client.apply(vec![create_user_mutation(&user_id, &now)]).await.unwrap();
+
+    let mut stmt = Statement::new("UPDATE User SET NullableString = 'row_count' WHERE UserId = @UserId");
+    stmt.add_param("UserId", &user_id);
+    let row_count = client
+        .partitioned_update_row_count(stmt.clone(), PartitionedUpdateOption::default())
+        .await
+        .unwrap();
+    assert!(matches!(row_count, RowCount::LowerBound(v) if v >= 1), "{row_count:?}");
+
+    let result: Result<(CommitResult, RowCount), Error> = client
+        .read_write_transaction(|tx, _cancel| {
+            let stmt = stmt.clone();
+            Box::pin(async move { Ok(tx.update_row_count_with_option(stmt, QueryOptions::default()).await?) })
+        })
+        .await;
+    assert_eq!(result.unwrap().1, RowCount::Exact(1));
+}
+
 #[tokio::test]
 #[serial]
 async fn test_batch_read_only_transaction() {
@@ -232,3 +409,13 @@ async fn test_begin_read_write_transaction_retry() {
     }
     assert_eq!(retry_count, 5);
 }
+
+#[tokio::test]
+#[serial]
+async fn test_check_connection() {
+    let client = Client::new(DATABASE, ClientConfig::default()).await.unwrap();
+    let latency = client.check_connection(Duration::from_secs(5)).await.unwrap();
+    assert!(latency < Duration::from_secs(5));
+    // the session is returned to the pool.
+    assert_eq!(client.session_count(), ClientConfig::default().session_config.min_opened);
+}
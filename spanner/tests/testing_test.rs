@@ -0,0 +1,60 @@
+#![cfg(feature = "test-util")]
+
+use serial_test::serial;
+
+use google_cloud_googleapis::spanner::admin::instance::v1::ListInstancesRequest;
+use google_cloud_spanner::admin::client::Client as AdminClient;
+use google_cloud_spanner::mutation::insert;
+use google_cloud_spanner::reader::AsyncIterator;
+use google_cloud_spanner::statement::Statement;
+use google_cloud_spanner::testing::{with_emulator, Emulator};
+
+async fn instance_names(admin: &AdminClient) -> Vec<String> {
+    let request = ListInstancesRequest {
+        parent: "projects/local-project".to_string(),
+        page_size: 100,
+        page_token: "".to_string(),
+        filter: "".to_string(),
+    };
+    let mut names: Vec<String> = admin
+        .instance()
+        .list_instances(request, None, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|i| i.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+#[serial]
+async fn test_with_emulator() {
+    let emulator = Emulator::start().await.unwrap();
+    let admin = emulator.admin_client().await.unwrap();
+    let before = instance_names(&admin).await;
+
+    let ddl = ["CREATE TABLE Guild (GuildId STRING(MAX) NOT NULL) PRIMARY KEY(GuildId)"];
+    let (during, count) = with_emulator(&ddl, |client| async move {
+        client
+            .apply(vec![insert("Guild", &["GuildId"], &[&"guild1"])])
+            .await
+            .unwrap();
+        let mut tx = client.single().await.unwrap();
+        let mut iter = tx.query(Statement::new("SELECT GuildId FROM Guild")).await.unwrap();
+        let mut count = 0;
+        while iter.next().await.unwrap().is_some() {
+            count += 1;
+        }
+        (instance_names(&admin).await, count)
+    })
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(during.len(), before.len() + 1);
+
+    // the instance and the database are deleted after the test.
+    let admin = emulator.admin_client().await.unwrap();
+    assert_eq!(instance_names(&admin).await, before);
+}
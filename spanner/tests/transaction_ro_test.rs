@@ -1,13 +1,23 @@
 use std::collections::HashMap;
 
+use futures_util::TryStreamExt;
 use serial_test::serial;
 use time::OffsetDateTime;
 
 use common::*;
+use google_cloud_gax::grpc::Code;
+use google_cloud_googleapis::spanner::admin::database::v1::DatabaseDialect;
+use google_cloud_googleapis::spanner::v1::execute_sql_request::QueryMode;
+use google_cloud_googleapis::spanner::v1::PartialResultSet;
+use google_cloud_spanner::client::Error;
+use google_cloud_spanner::information_schema::{ColumnInfo, InformationSchema};
 use google_cloud_spanner::key::Key;
+use google_cloud_spanner::mutation::insert;
 use google_cloud_spanner::row::Row;
 use google_cloud_spanner::statement::Statement;
+use google_cloud_spanner::transaction::{QueryOneValueError, QueryOptions, ReadOptions};
 use google_cloud_spanner::transaction_ro::ReadOnlyTransaction;
+use google_cloud_spanner::value::{CommitTimestamp, TimestampBound};
 
 mod common;
 
@@ -67,7 +77,7 @@ async fn test_query_and_read() {
 
     //test
     let mut tx = data_client.read_only_transaction().await.unwrap();
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -111,7 +121,7 @@ async fn test_complex_query() {
     let row = rows.pop().unwrap();
 
     // check UserTable
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -170,7 +180,7 @@ async fn test_batch_partition_query_and_read() {
 
     // test
     let mut tx = data_client.batch_read_only_transaction().await.unwrap();
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -191,7 +201,7 @@ async fn test_batch_partition_query_and_read() {
         map.insert(user_id, row);
     }
 
-    let ts = cr2.unwrap();
+    let ts = cr2.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -215,7 +225,7 @@ async fn test_query(count: usize, prefix: &str) {
     let rows = execute_query(&mut tx, stmt).await;
     assert_eq!(count, rows.len());
 
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -320,3 +330,254 @@ async fn test_read_multi_row() {
         .unwrap();
     assert_eq!(2, all_rows(row).await.unwrap().len());
 }
+
+#[tokio::test]
+#[serial]
+async fn test_information_schema() {
+    let data_client = create_data_client().await;
+    let schema = InformationSchema::new(DatabaseDialect::GoogleStandardSql);
+    let mut tx = data_client.read_only_transaction().await.unwrap();
+
+    let tables = schema.tables(&mut tx).await.unwrap();
+    let user_item = tables.iter().find(|t| t.name == "UserItem").unwrap();
+    assert_eq!(user_item.parent_table_name, Some("User".to_string()));
+
+    let columns = schema.columns(&mut tx, "UserItem").await.unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            ColumnInfo {
+                name: "UserId".to_string(),
+                spanner_type: "STRING(36)".to_string(),
+                is_nullable: false,
+                ordinal_position: 1,
+            },
+            ColumnInfo {
+                name: "ItemId".to_string(),
+                spanner_type: "INT64".to_string(),
+                is_nullable: false,
+                ordinal_position: 2,
+            },
+            ColumnInfo {
+                name: "Quantity".to_string(),
+                spanner_type: "INT64".to_string(),
+                is_nullable: false,
+                ordinal_position: 3,
+            },
+            ColumnInfo {
+                name: "UpdatedAt".to_string(),
+                spanner_type: "TIMESTAMP".to_string(),
+                is_nullable: false,
+                ordinal_position: 4,
+            },
+        ]
+    );
+    let nullable = schema.columns(&mut tx, "User").await.unwrap();
+    let nullable = nullable.iter().find(|c| c.name == "NullableINT64").unwrap();
+    assert!(nullable.is_nullable);
+
+    let indexes = schema.indexes(&mut tx, "UserItem").await.unwrap();
+    assert!(indexes.iter().any(|i| i.index_type == "PRIMARY_KEY" && i.is_unique));
+
+    let validator = schema.commit_timestamp_validator(&mut tx).await.unwrap();
+    let columns = ["UserId", "ItemId", "UpdatedAt"];
+    let m = insert("UserItem", &columns, &[&"user1", &1, &CommitTimestamp::new()]);
+    assert!(validator.validate(m).is_ok());
+    let columns = ["UserId", "ItemId", "Quantity"];
+    let m = insert("UserItem", &columns, &[&"user1", &1, &CommitTimestamp::new()]);
+    assert!(validator.validate(m).is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_run_read_only() {
+    let now = OffsetDateTime::now_utc();
+    let data_client = create_data_client().await;
+    let user_id = format!("user_{}", now.unix_timestamp());
+    data_client
+        .apply(vec![create_user_mutation(&user_id, &now)])
+        .await
+        .unwrap();
+
+    let (rts1, rts2) = data_client
+        .run_read_only(TimestampBound::strong_read(), |tx| {
+            let user_id = user_id.clone();
+            Box::pin(async move {
+                let reader = tx.read("User", &["UserId"], Key::new(&user_id)).await?;
+                assert_eq!(1, all_rows(reader).await.unwrap().len());
+                let rts1 = tx.rts;
+                let mut stmt = Statement::new("SELECT UserId FROM User WHERE UserId = @UserId");
+                stmt.add_param("UserId", &user_id);
+                let reader = tx.query(stmt).await?;
+                assert_eq!(1, all_rows(reader).await.unwrap().len());
+                Ok::<_, Error>((rts1, tx.rts))
+            })
+        })
+        .await
+        .unwrap();
+    assert!(rts1.is_some());
+    assert_eq!(rts1, rts2);
+    assert_eq!(data_client.session_count(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_read_by_key() {
+    let now = OffsetDateTime::now_utc();
+    let data_client = create_data_client().await;
+    let user_id_1 = format!("user_batch_1_{}", now.unix_timestamp());
+    let user_id_2 = format!("user_batch_2_{}", now.unix_timestamp());
+    let missing = format!("user_batch_missing_{}", now.unix_timestamp());
+    data_client
+        .apply(vec![
+            create_user_mutation(&user_id_1, &now),
+            create_user_mutation(&user_id_2, &now),
+        ])
+        .await
+        .unwrap();
+
+    let mut tx = data_client.single().await.unwrap();
+    let keys = vec![Key::new(&user_id_2), Key::new(&missing), Key::new(&user_id_1)];
+    let result = tx.batch_read_by_key("User", keys, &["UserId"]).await.unwrap();
+    let user_ids = result
+        .iter()
+        .map(|(_, row)| row.as_ref().map(|r| r.column_by_name::<String>("UserId").unwrap()))
+        .collect::<Vec<Option<String>>>();
+    assert_eq!(vec![Some(user_id_2), None, Some(user_id_1)], user_ids);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_query_with_request_mutator() {
+    let data_client = create_data_client().await;
+    let mut tx = data_client.single().await.unwrap();
+    let options = QueryOptions::default().with_request_mutator(|r| r.sql = "SELECT 2".to_string());
+    let reader = tx.query_with_option(Statement::new("SELECT 1"), options).await.unwrap();
+    let rows = all_rows(reader).await.unwrap();
+    assert_eq!(1, rows.len());
+    assert_eq!(2, rows[0].column::<i64>(0).unwrap());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_query_one_value() {
+    let now = OffsetDateTime::now_utc();
+    let data_client = create_data_client().await;
+    let user_id = format!("user_one_value_{}", now.unix_timestamp());
+    data_client
+        .apply(vec![create_user_mutation(&user_id, &now)])
+        .await
+        .unwrap();
+
+    let mut tx = data_client.single().await.unwrap();
+    let mut stmt = Statement::new("SELECT COUNT(*) FROM User WHERE UserId = @UserId");
+    stmt.add_param("UserId", &user_id);
+    assert_eq!(1, tx.query_one_value::<i64>(stmt).await.unwrap());
+
+    let mut stmt = Statement::new("SELECT UserId FROM User WHERE UserId = @UserId");
+    stmt.add_param("UserId", &format!("{user_id}_missing"));
+    let result = tx.query_one_value::<String>(stmt).await;
+    assert!(matches!(result, Err(QueryOneValueError::NoRows)));
+
+    let stmt = Statement::new("SELECT * FROM UNNEST([1, 2])");
+    let result = tx.query_one_value::<i64>(stmt).await;
+    assert!(matches!(result, Err(QueryOneValueError::TooManyRows)));
+
+    let stmt = Statement::new("SELECT 1, 2");
+    let result = tx.query_one_value::<i64>(stmt).await;
+    assert!(matches!(result, Err(QueryOneValueError::InvalidColumnCount(2))));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_query_all() {
+    let data_client = create_data_client().await;
+    let mut tx = data_client.single().await.unwrap();
+
+    // the large result set is sent in multiple chunks.
+    let stmt = Statement::new("SELECT x, REPEAT('a', 1024) FROM UNNEST(GENERATE_ARRAY(1, 5000)) AS x ORDER BY x");
+    let rows = tx.query_all(stmt).await.unwrap();
+    assert_eq!(rows.len(), 5000);
+    for (i, row) in rows.iter().enumerate() {
+        assert_eq!(row.column::<i64>(0).unwrap(), i as i64 + 1);
+        assert_eq!(row.column::<String>(1).unwrap().len(), 1024);
+    }
+
+    let stmt = Statement::new("SELECT 1 FROM UNNEST([1]) AS x WHERE x > 1");
+    assert!(tx.query_all(stmt).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_validate() {
+    let data_client = create_data_client().await;
+    let mut tx = data_client.single().await.unwrap();
+
+    let mut stmt = Statement::new("SELECT UserId FROM User WHERE UserId = @UserId");
+    stmt.add_param("UserId", &"user_validate");
+    tx.validate(stmt).await.unwrap();
+
+    let stmt = Statement::new("SELEC UserId FROM User");
+    let err = tx.validate(stmt).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let stmt = Statement::new("SELECT NotExists FROM User");
+    let err = tx.validate(stmt).await.unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_execute_streaming_raw() {
+    let data_client = create_data_client().await;
+    let mut tx = data_client.single().await.unwrap();
+
+    let stmt = Statement::new("SELECT x, REPEAT('a', 1024) FROM UNNEST(GENERATE_ARRAY(1, 5000)) AS x ORDER BY x");
+    let options = QueryOptions {
+        mode: QueryMode::Profile,
+        ..Default::default()
+    };
+    let stream = tx.execute_streaming_raw(stmt, options).await.unwrap();
+    let result_sets: Vec<PartialResultSet> = stream.try_collect().await.unwrap();
+    assert!(result_sets.len() > 1, "must be sent in multiple chunks");
+
+    // the metadata comes first, then the values, and the stats last.
+    let metadata = result_sets[0].metadata.as_ref().unwrap();
+    assert_eq!(metadata.row_type.as_ref().unwrap().fields.len(), 2);
+    assert!(result_sets[1..].iter().all(|r| r.metadata.is_none()));
+    let values: usize = result_sets.iter().map(|r| r.values.len()).sum();
+    let chunked = result_sets.iter().filter(|r| r.chunked_value).count();
+    assert_eq!(values - chunked, 5000 * 2);
+    let (last, rest) = result_sets.split_last().unwrap();
+    assert!(last.stats.is_some());
+    assert!(rest.iter().all(|r| r.stats.is_none()));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_read_split_key_set() {
+    let now = OffsetDateTime::now_utc();
+    let user_ids: Vec<String> = (0..3)
+        .map(|i| format!("user_split_{i}_{}", now.unix_timestamp()))
+        .collect();
+    let data_client = create_data_client().await;
+    let mutations = user_ids.iter().map(|u| create_user_mutation(u, &now)).collect();
+    let _ = data_client.apply(mutations).await.unwrap();
+
+    // 100k keys including the existing users in the first, the middle and the last request.
+    let mut keys: Vec<Key> = (0..100_000)
+        .map(|i| Key::new(&format!("user_split_missing_{i}")))
+        .collect();
+    keys[0] = Key::new(&user_ids[0]);
+    keys[50_000] = Key::new(&user_ids[1]);
+    keys[99_999] = Key::new(&user_ids[2]);
+    let options = ReadOptions {
+        max_keys_per_request: Some(10_000),
+        ..Default::default()
+    };
+    let mut tx = data_client.read_only_transaction().await.unwrap();
+    let reader = tx.read_with_option("User", &["UserId"], keys, options).await.unwrap();
+    let rows = all_rows(reader).await.unwrap();
+    let found: Vec<String> = rows.iter().map(|r| r.column::<String>(0).unwrap()).collect();
+    assert_eq!(found, user_ids);
+}
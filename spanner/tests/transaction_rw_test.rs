@@ -2,9 +2,19 @@ use serial_test::serial;
 use time::OffsetDateTime;
 
 use common::*;
+use google_cloud_gax::cancel::CancellationToken;
+use google_cloud_gax::conn::Environment;
+use google_cloud_gax::grpc::Code;
+use google_cloud_googleapis::spanner::v1::execute_sql_request::QueryMode;
+use google_cloud_googleapis::spanner::v1::result_set_stats::RowCount;
+use google_cloud_spanner::client::{ChannelConfig, Client, ClientConfig, Error};
 use google_cloud_spanner::key::Key;
+use google_cloud_spanner::mutation::insert;
 use google_cloud_spanner::row::Row;
+use google_cloud_spanner::session::SessionConfig;
 use google_cloud_spanner::statement::Statement;
+use google_cloud_spanner::transaction::{CallOptions, QueryOptions};
+use google_cloud_spanner::transaction_rw::CommitOptions;
 
 mod common;
 
@@ -49,8 +59,8 @@ async fn test_mutation_and_statement() {
     let result = tx.end(result, None).await;
     let commit_timestamp = match result {
         Ok(s) => {
-            assert!(s.0.is_some());
-            let ts = s.0.unwrap();
+            assert!(s.0.timestamp.is_some());
+            let ts = s.0.timestamp.unwrap();
             let dt = OffsetDateTime::from_unix_timestamp(ts.seconds)
                 .unwrap()
                 .replace_nanosecond(ts.nanos as u32)
@@ -61,7 +71,7 @@ async fn test_mutation_and_statement() {
         Err(e) => panic!("error {e:?}"),
     };
 
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -98,7 +108,7 @@ async fn test_rollback() {
     let mut tx = data_client.read_only_transaction().await.unwrap();
     let reader = tx.read("User", &user_columns(), Key::new(&past_user)).await.unwrap();
     let row: Row = all_rows(reader).await.unwrap().pop().unwrap();
-    let ts = cr.unwrap();
+    let ts = cr.timestamp.unwrap();
     let ts = OffsetDateTime::from_unix_timestamp(ts.seconds)
         .unwrap()
         .replace_nanosecond(ts.nanos as u32)
@@ -161,3 +171,132 @@ async fn assert_data(
     );
     assert!(user_characters.is_empty());
 }
+
+#[tokio::test]
+#[serial]
+async fn test_default_commit_options() {
+    // the commit is cancelled when the client default is used.
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let mut session_config = SessionConfig::default();
+    session_config.min_opened = 1;
+    session_config.max_opened = 1;
+    let data_client = Client::new(
+        DATABASE,
+        ClientConfig {
+            session_config,
+            environment: Environment::Emulator("localhost:9010".to_string()),
+            channel_config: ChannelConfig {
+                num_channels: 1,
+                ..Default::default()
+            },
+            default_commit_options: CommitOptions {
+                return_commit_stats: false,
+                call_options: CallOptions {
+                    cancel: Some(cancel),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let now = OffsetDateTime::now_utc();
+    let user_id = format!("user_{}", now.unix_timestamp());
+    {
+        let mut tx = data_client.begin_read_write_transaction().await.unwrap();
+        tx.buffer_write(vec![create_user_mutation(&user_id, &now)]);
+        match tx.end(Ok::<(), Error>(()), None).await {
+            Err(Error::Transaction(err)) => assert_eq!(err.status().code(), Code::Cancelled),
+            Err(e) => panic!("unexpected error {e:?}"),
+            Ok(_) => panic!("must be cancelled"),
+        }
+    }
+
+    // the options specified by the caller override the client default.
+    let mut tx = data_client.begin_read_write_transaction().await.unwrap();
+    tx.buffer_write(vec![create_user_mutation(&user_id, &now)]);
+    let result = tx.end(Ok::<(), Error>(()), Some(CommitOptions::default())).await;
+    assert!(result.unwrap().0.timestamp.is_some());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_commit_error_context() {
+    let data_client = create_data_client().await;
+    let mut tx = data_client.begin_read_write_transaction().await.unwrap();
+    tx.buffer_write(vec![insert("UserNotFound", &["UserId"], &[&"user"])]);
+    match tx.end(Ok::<(), Error>(()), None).await {
+        Err(Error::Transaction(err)) => {
+            assert!(!err.transaction_id().is_empty(), "{err:?}");
+            assert!(err.session_name().starts_with("projects/"), "{err:?}");
+        }
+        Err(e) => panic!("unexpected error {e:?}"),
+        Ok(_) => panic!("must fail"),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_update_profile() {
+    let now = OffsetDateTime::now_utc();
+    let data_client = create_data_client().await;
+    let user_id = format!("user_profile_{}", now.unix_timestamp());
+    data_client
+        .apply(vec![create_user_mutation(&user_id, &now)])
+        .await
+        .unwrap();
+
+    let mut tx = data_client.begin_read_write_transaction().await.unwrap();
+    let result = async {
+        let mut stmt1 = Statement::new(
+            "INSERT INTO UserItem (UserId,ItemId,Quantity,UpdatedAt) VALUES(@UserId,1,1,PENDING_COMMIT_TIMESTAMP())",
+        );
+        stmt1.add_param("UserId", &user_id);
+        let mut stmt2 = Statement::new("UPDATE UserItem SET Quantity = 2 WHERE UserId = @UserId");
+        stmt2.add_param("UserId", &user_id);
+        let options = QueryOptions {
+            mode: QueryMode::Profile,
+            ..Default::default()
+        };
+        tx.batch_update_stats_with_option(vec![stmt1, stmt2], options).await
+    }
+    .await;
+    assert_eq!(tx.stats().statements, 2);
+    let stats = tx.end(result, None).await.unwrap().1;
+    assert_eq!(stats.len(), 2);
+    for s in stats {
+        assert_eq!(s.row_count, Some(RowCount::RowCountExact(1)));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_transaction_stats() {
+    let now = OffsetDateTime::now_utc();
+    let data_client = create_data_client().await;
+    let user_id = format!("user_stats_{}", now.unix_timestamp());
+    let mut tx = data_client.begin_read_write_transaction().await.unwrap();
+    let result = async {
+        tx.buffer_write(vec![create_user_mutation(&user_id, &now)]);
+        let mut stmt = Statement::new(
+            "INSERT INTO UserItem (UserId,ItemId,Quantity,UpdatedAt) VALUES(@UserId,1,1,PENDING_COMMIT_TIMESTAMP())",
+        );
+        stmt.add_param("UserId", &user_id);
+        tx.update(stmt).await?;
+        let mut stmt1 = Statement::new("UPDATE UserItem SET Quantity = 2 WHERE UserId = @UserId");
+        stmt1.add_param("UserId", &user_id);
+        let mut stmt2 = Statement::new("UPDATE UserItem SET Quantity = 3 WHERE UserId = @UserId");
+        stmt2.add_param("UserId", &user_id);
+        tx.batch_update(vec![stmt1, stmt2]).await
+    }
+    .await;
+    let stats = tx.stats();
+    assert_eq!(stats.buffered_mutations, 1);
+    assert!(stats.estimated_mutations > 1);
+    assert_eq!(stats.statements, 3);
+    tx.end(result, None).await.unwrap();
+}